@@ -0,0 +1,18 @@
+// aligned_alloc() returns memory with small and page sized alignments.
+// conf_test: describe=`aligned_alloc()`
+use std::os::raw::c_void;
+
+extern "C" {
+    fn aligned_alloc(alignment: usize, size: usize) -> *mut c_void;
+    fn free(ptr: *mut c_void);
+}
+
+fn main() {
+    for &alignment in &[16, 64, 4096] {
+        let ptr = unsafe { aligned_alloc(alignment, alignment * 2) };
+        if ptr.is_null() || ptr as usize % alignment != 0 {
+            std::process::exit(1);
+        }
+        unsafe { free(ptr) };
+    }
+}
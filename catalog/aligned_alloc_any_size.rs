@@ -0,0 +1,17 @@
+// aligned_alloc() accepts sizes which are not a multiple of the alignment, as C17 allows.
+// Some C11 implementations return NULL for them.
+// conf_test: describe=`aligned_alloc()` with sizes not a multiple of the alignment
+use std::os::raw::c_void;
+
+extern "C" {
+    fn aligned_alloc(alignment: usize, size: usize) -> *mut c_void;
+    fn free(ptr: *mut c_void);
+}
+
+fn main() {
+    let ptr = unsafe { aligned_alloc(64, 100) };
+    if ptr.is_null() || ptr as usize % 64 != 0 {
+        std::process::exit(1);
+    }
+    unsafe { free(ptr) };
+}
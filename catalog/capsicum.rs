@@ -0,0 +1,13 @@
+// Capsicum capability mode is supported by the kernel (FreeBSD).
+// conf_test: describe=the kernel supports Capsicum capability mode (FreeBSD)
+use std::os::raw::{c_int, c_uint};
+
+extern "C" {
+    fn cap_getmode(mode: *mut c_uint) -> c_int;
+}
+
+fn main() {
+    let mut mode: c_uint = 0;
+    let works = unsafe { cap_getmode(&mut mode) } == 0;
+    std::process::exit(if works { 0 } else { 1 });
+}
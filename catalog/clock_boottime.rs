@@ -0,0 +1,26 @@
+// clock_gettime() supports CLOCK_BOOTTIME, a monotonic clock that includes suspended time.
+// conf_test: describe=`clock_gettime()` supports `CLOCK_BOOTTIME` (Linux, Android)
+use std::os::raw::{c_int, c_long};
+
+#[repr(C)]
+struct Timespec {
+    tv_sec: c_long,
+    tv_nsec: c_long,
+}
+
+extern "C" {
+    fn clock_gettime(clock: c_int, ts: *mut Timespec) -> c_int;
+}
+
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const CLOCK_BOOTTIME: c_int = 7;
+
+fn main() {
+    let mut ts = Timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    let works = unsafe { clock_gettime(CLOCK_BOOTTIME, &mut ts) } == 0;
+    std::process::exit(if works { 0 } else { 1 });
+}
@@ -0,0 +1,26 @@
+// clock_gettime() supports CLOCK_MONOTONIC_RAW, a monotonic clock not slewed by NTP.
+// conf_test: describe=`clock_gettime()` supports `CLOCK_MONOTONIC_RAW` (Linux, Android, macOS)
+use std::os::raw::{c_int, c_long};
+
+#[repr(C)]
+struct Timespec {
+    tv_sec: c_long,
+    tv_nsec: c_long,
+}
+
+extern "C" {
+    fn clock_gettime(clock: c_int, ts: *mut Timespec) -> c_int;
+}
+
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos"))]
+const CLOCK_MONOTONIC_RAW: c_int = 4;
+
+fn main() {
+    let mut ts = Timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    let works = unsafe { clock_gettime(CLOCK_MONOTONIC_RAW, &mut ts) } == 0;
+    std::process::exit(if works { 0 } else { 1 });
+}
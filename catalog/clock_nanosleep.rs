@@ -0,0 +1,44 @@
+// clock_nanosleep() can sleep on CLOCK_MONOTONIC, relative and until an absolute time.
+// conf_test: describe=`clock_nanosleep()` works on `CLOCK_MONOTONIC`, relative and absolute (Linux, Android, FreeBSD)
+use std::os::raw::{c_int, c_long};
+use std::ptr;
+
+#[repr(C)]
+struct Timespec {
+    tv_sec: c_long,
+    tv_nsec: c_long,
+}
+
+extern "C" {
+    fn clock_gettime(clock: c_int, ts: *mut Timespec) -> c_int;
+    fn clock_nanosleep(
+        clock: c_int,
+        flags: c_int,
+        request: *const Timespec,
+        remain: *mut Timespec,
+    ) -> c_int;
+}
+
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const CLOCK_MONOTONIC: c_int = 1;
+#[cfg(target_os = "freebsd")]
+const CLOCK_MONOTONIC: c_int = 4;
+const TIMER_ABSTIME: c_int = 1;
+
+fn main() {
+    let relative = Timespec {
+        tv_sec: 0,
+        tv_nsec: 1000,
+    };
+    let mut now = Timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    let works = unsafe {
+        clock_nanosleep(CLOCK_MONOTONIC, 0, &relative, ptr::null_mut()) == 0
+            && clock_gettime(CLOCK_MONOTONIC, &mut now) == 0
+            && clock_nanosleep(CLOCK_MONOTONIC, TIMER_ABSTIME, &now, ptr::null_mut()) == 0
+    };
+    std::process::exit(if works { 0 } else { 1 });
+}
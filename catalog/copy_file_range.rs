@@ -0,0 +1,54 @@
+// copy_file_range() copies data between files inside the kernel.
+// conf_test: android-api=34
+// conf_test: describe=`copy_file_range()` copies between files
+use std::fs::{self, File, OpenOptions};
+use std::os::raw::{c_int, c_uint};
+use std::os::unix::io::AsRawFd;
+use std::path::PathBuf;
+use std::ptr;
+
+extern "C" {
+    fn copy_file_range(
+        fd_in: c_int,
+        off_in: *mut i64,
+        fd_out: c_int,
+        off_out: *mut i64,
+        len: usize,
+        flags: c_uint,
+    ) -> isize;
+}
+
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const CONTENT: &[u8] = b"conf_test copy_file_range";
+
+fn main() {
+    let dir = PathBuf::from(std::env::var_os("CONF_TEST_OUT_DIR").expect("CONF_TEST_OUT_DIR"));
+    let source = dir.join("copy_file_range.in");
+    let target = dir.join("copy_file_range.out");
+    fs::write(&source, CONTENT).expect("write probe file");
+    let input = File::open(&source).expect("open");
+    let output = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&target)
+        .expect("create");
+
+    let copied = unsafe {
+        copy_file_range(
+            input.as_raw_fd(),
+            ptr::null_mut(),
+            output.as_raw_fd(),
+            ptr::null_mut(),
+            CONTENT.len(),
+            0,
+        )
+    };
+    drop(output);
+    let works = copied == CONTENT.len() as isize
+        && fs::read(&target).map(|copy| copy == CONTENT).unwrap_or(false);
+    let _ = fs::remove_file(&source);
+    let _ = fs::remove_file(&target);
+    std::process::exit(if works { 0 } else { 1 });
+}
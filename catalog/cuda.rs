@@ -0,0 +1,38 @@
+// A CUDA toolkit is installed: nvcc runs and the CUDA runtime library is present. The toolkit
+// is looked up in CUDA_PATH or CUDA_HOME, else in '/usr/local/cuda'. CUDA_ROOT and
+// CUDA_LIB_DIR are set for the crate, CUDA_ROOT is exported to the following tests.
+// conf_test: describe=CUDA toolkit with `nvcc` and the CUDA runtime
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=CUDA_PATH");
+    println!("cargo:rerun-if-env-changed=CUDA_HOME");
+    let root = env::var_os("CUDA_PATH")
+        .or_else(|| env::var_os("CUDA_HOME"))
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/usr/local/cuda"));
+
+    let nvcc = root
+        .join("bin")
+        .join(if cfg!(windows) { "nvcc.exe" } else { "nvcc" });
+    let runs = Command::new(&nvcc)
+        .arg("--version")
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false);
+    let lib_dir = ["lib64", "lib/x64", "lib"]
+        .iter()
+        .map(|dir| root.join(dir))
+        .find(|dir| dir.join("libcudart.so").exists() || dir.join("cudart.lib").exists());
+
+    match lib_dir {
+        Some(lib_dir) if runs => {
+            println!("conf_test:set-env=CUDA_ROOT={}", root.display());
+            println!("conf_test:set-env=CUDA_LIB_DIR={}", lib_dir.display());
+            println!("conf_test:export=CUDA_ROOT={}", root.display());
+        }
+        _ => std::process::exit(1),
+    }
+}
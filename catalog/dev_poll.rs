@@ -0,0 +1,73 @@
+// /dev/poll registers file descriptors once and polls them repeatedly (illumos, Solaris).
+// conf_test: describe='/dev/poll' polls registered file descriptors
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::raw::{c_int, c_short, c_uint};
+use std::os::unix::io::AsRawFd;
+use std::slice;
+
+#[repr(C)]
+struct Pollfd {
+    fd: c_int,
+    events: c_short,
+    revents: c_short,
+}
+
+#[repr(C)]
+struct Dvpoll {
+    dp_fds: *mut Pollfd,
+    dp_nfds: c_uint,
+    dp_timeout: c_int,
+}
+
+extern "C" {
+    fn pipe(fds: *mut c_int) -> c_int;
+    fn write(fd: c_int, buf: *const u8, count: usize) -> isize;
+    fn ioctl(fd: c_int, request: c_int, ...) -> c_int;
+}
+
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "illumos", target_os = "solaris"))]
+const DP_POLL: c_int = 0xD001;
+const POLLIN: c_short = 1;
+
+fn main() {
+    let mut devpoll = match OpenOptions::new().read(true).write(true).open("/dev/poll") {
+        Ok(devpoll) => devpoll,
+        Err(_) => std::process::exit(1),
+    };
+    let mut fds = [0 as c_int; 2];
+    if unsafe { pipe(fds.as_mut_ptr()) } != 0 || unsafe { write(fds[1], b"x".as_ptr(), 1) } != 1 {
+        std::process::exit(1);
+    }
+
+    let register = Pollfd {
+        fd: fds[0],
+        events: POLLIN,
+        revents: 0,
+    };
+    let bytes = unsafe {
+        slice::from_raw_parts(
+            &register as *const Pollfd as *const u8,
+            std::mem::size_of::<Pollfd>(),
+        )
+    };
+    if devpoll.write_all(bytes).is_err() {
+        std::process::exit(1);
+    }
+
+    let mut ready = Pollfd {
+        fd: -1,
+        events: 0,
+        revents: 0,
+    };
+    let mut dvpoll = Dvpoll {
+        dp_fds: &mut ready,
+        dp_nfds: 1,
+        dp_timeout: 0,
+    };
+    let works = unsafe { ioctl(devpoll.as_raw_fd(), DP_POLL, &mut dvpoll) } == 1
+        && ready.fd == fds[0]
+        && ready.revents & POLLIN != 0;
+    std::process::exit(if works { 0 } else { 1 });
+}
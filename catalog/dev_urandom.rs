@@ -0,0 +1,18 @@
+// /dev/urandom is a readable character device, it may be missing in chroots and containers.
+// conf_test: describe='/dev/urandom' is a readable character device (Unix)
+use std::fs::{self, File};
+use std::io::Read;
+use std::os::unix::fs::FileTypeExt;
+
+fn main() {
+    let device = fs::metadata("/dev/urandom")
+        .map(|metadata| metadata.file_type().is_char_device())
+        .unwrap_or(false);
+    let mut buffer = [0u8; 32];
+    let works = device
+        && File::open("/dev/urandom")
+            .and_then(|mut file| file.read_exact(&mut buffer))
+            .is_ok()
+        && buffer.iter().any(|&byte| byte != 0);
+    std::process::exit(if works { 0 } else { 1 });
+}
@@ -0,0 +1,21 @@
+// Doors provide fast local procedure calls between processes (illumos, Solaris).
+// conf_test: describe=doors (`door_create()`) are available
+use std::os::raw::{c_char, c_int, c_uint, c_void};
+use std::ptr;
+
+type DoorServer = extern "C" fn(*mut c_void, *mut c_char, usize, *mut c_void, c_uint);
+
+extern "C" {
+    fn door_create(server_procedure: DoorServer, cookie: *mut c_void, attributes: c_uint) -> c_int;
+    fn door_revoke(d: c_int) -> c_int;
+}
+
+extern "C" fn server(_: *mut c_void, _: *mut c_char, _: usize, _: *mut c_void, _: c_uint) {}
+
+fn main() {
+    let door = unsafe { door_create(server, ptr::null_mut(), 0) };
+    if door < 0 {
+        std::process::exit(1);
+    }
+    unsafe { door_revoke(door) };
+}
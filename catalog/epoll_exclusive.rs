@@ -0,0 +1,40 @@
+// epoll_ctl() accepts EPOLLEXCLUSIVE to avoid thundering herds on shared descriptors.
+// conf_test: describe=`epoll_ctl()` accepts `EPOLLEXCLUSIVE`
+use std::os::raw::{c_int, c_uint};
+
+#[cfg_attr(target_arch = "x86_64", repr(C, packed))]
+#[cfg_attr(not(target_arch = "x86_64"), repr(C))]
+struct EpollEvent {
+    events: u32,
+    data: u64,
+}
+
+extern "C" {
+    fn epoll_create1(flags: c_int) -> c_int;
+    fn epoll_ctl(epfd: c_int, op: c_int, fd: c_int, event: *mut EpollEvent) -> c_int;
+    fn eventfd(initval: c_uint, flags: c_int) -> c_int;
+    fn close(fd: c_int) -> c_int;
+}
+
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const EPOLL_CLOEXEC: c_int = 0o2000000;
+const EPOLL_CTL_ADD: c_int = 1;
+const EPOLLIN: u32 = 1;
+const EPOLLEXCLUSIVE: u32 = 1 << 28;
+
+fn main() {
+    let works = unsafe {
+        let epfd = epoll_create1(EPOLL_CLOEXEC);
+        let fd = eventfd(0, EPOLL_CLOEXEC);
+        let mut event = EpollEvent {
+            events: EPOLLIN | EPOLLEXCLUSIVE,
+            data: 0,
+        };
+        let works = epfd >= 0 && fd >= 0 && epoll_ctl(epfd, EPOLL_CTL_ADD, fd, &mut event) == 0;
+        close(fd);
+        close(epfd);
+        works
+    };
+    std::process::exit(if works { 0 } else { 1 });
+}
@@ -0,0 +1,54 @@
+// Event ports deliver events from many sources through a single port (illumos, Solaris).
+// conf_test: describe=event ports (`port_create()`) are available
+use std::os::raw::{c_int, c_long, c_ushort, c_void};
+use std::ptr;
+
+#[repr(C)]
+struct PortEvent {
+    portev_events: c_int,
+    portev_source: c_ushort,
+    portev_pad: c_ushort,
+    portev_object: usize,
+    portev_user: *mut c_void,
+}
+
+#[repr(C)]
+struct Timespec {
+    tv_sec: c_long,
+    tv_nsec: c_long,
+}
+
+extern "C" {
+    fn port_create() -> c_int;
+    fn port_send(port: c_int, events: c_int, user: *mut c_void) -> c_int;
+    fn port_get(port: c_int, pe: *mut PortEvent, timeout: *mut Timespec) -> c_int;
+    fn close(fd: c_int) -> c_int;
+}
+
+const PORT_SOURCE_USER: c_ushort = 3;
+
+fn main() {
+    let port = unsafe { port_create() };
+    if port < 0 {
+        std::process::exit(1);
+    }
+    let mut event = PortEvent {
+        portev_events: 0,
+        portev_source: 0,
+        portev_pad: 0,
+        portev_object: 0,
+        portev_user: ptr::null_mut(),
+    };
+    let mut zero = Timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    let works = unsafe {
+        port_send(port, 42, ptr::null_mut()) == 0
+            && port_get(port, &mut event, &mut zero) == 0
+            && event.portev_source == PORT_SOURCE_USER
+            && event.portev_events == 42
+    };
+    unsafe { close(port) };
+    std::process::exit(if works { 0 } else { 1 });
+}
@@ -0,0 +1,27 @@
+// eventfd() creates counters usable as lightweight wakeup channels.
+// conf_test: describe=`eventfd()` counters work as wakeup channels
+use std::fs::File;
+use std::io::{Read, Write};
+use std::os::raw::{c_int, c_uint};
+use std::os::unix::io::FromRawFd;
+
+extern "C" {
+    fn eventfd(initval: c_uint, flags: c_int) -> c_int;
+}
+
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const EFD_CLOEXEC: c_int = 0o2000000;
+
+fn main() {
+    let fd = unsafe { eventfd(0, EFD_CLOEXEC) };
+    if fd < 0 {
+        std::process::exit(1);
+    }
+    let mut file = unsafe { File::from_raw_fd(fd) };
+    let mut counter = [0u8; 8];
+    let works = file.write_all(&3u64.to_ne_bytes()).is_ok()
+        && file.read_exact(&mut counter).is_ok()
+        && u64::from_ne_bytes(counter) == 3;
+    std::process::exit(if works { 0 } else { 1 });
+}
@@ -0,0 +1,67 @@
+// kqueue can wait for messages on Mach ports with EVFILT_MACHPORT (macOS).
+// conf_test: describe=kqueue waits for Mach port messages with `EVFILT_MACHPORT`
+use std::os::raw::{c_int, c_long, c_uint, c_void};
+use std::ptr;
+
+#[repr(C)]
+struct Kevent {
+    ident: usize,
+    filter: i16,
+    flags: u16,
+    fflags: u32,
+    data: isize,
+    udata: *mut c_void,
+}
+
+#[repr(C)]
+struct Timespec {
+    tv_sec: c_long,
+    tv_nsec: c_long,
+}
+
+extern "C" {
+    static mach_task_self_: c_uint;
+    fn mach_port_allocate(task: c_uint, right: c_uint, name: *mut c_uint) -> c_int;
+    fn kqueue() -> c_int;
+    fn kevent(
+        kq: c_int,
+        changelist: *const Kevent,
+        nchanges: c_int,
+        eventlist: *mut Kevent,
+        nevents: c_int,
+        timeout: *const Timespec,
+    ) -> c_int;
+    fn close(fd: c_int) -> c_int;
+}
+
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+const EVFILT_MACHPORT: i16 = -8;
+const EV_ADD: u16 = 0x0001;
+const MACH_PORT_RIGHT_RECEIVE: c_uint = 1;
+
+fn main() {
+    let mut port: c_uint = 0;
+    if unsafe { mach_port_allocate(mach_task_self_, MACH_PORT_RIGHT_RECEIVE, &mut port) } != 0 {
+        std::process::exit(1);
+    }
+    let kq = unsafe { kqueue() };
+    if kq < 0 {
+        std::process::exit(1);
+    }
+    let change = Kevent {
+        ident: port as usize,
+        filter: EVFILT_MACHPORT,
+        flags: EV_ADD,
+        fflags: 0,
+        data: 0,
+        udata: ptr::null_mut(),
+    };
+    let zero = Timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    let works = unsafe { kevent(kq, &change, 1, ptr::null_mut(), 0, &zero) } == 0;
+    unsafe { close(kq) };
+    std::process::exit(if works { 0 } else { 1 });
+}
@@ -0,0 +1,80 @@
+// kqueue supports EVFILT_USER events, triggered from user space to wake up kevent() waiters.
+// conf_test: describe=kqueue supports user triggered `EVFILT_USER` events (FreeBSD, macOS)
+use std::os::raw::{c_int, c_long, c_void};
+use std::ptr;
+
+#[repr(C)]
+struct Kevent {
+    ident: usize,
+    filter: i16,
+    flags: u16,
+    fflags: u32,
+    #[cfg(target_os = "freebsd")]
+    data: i64,
+    #[cfg(not(target_os = "freebsd"))]
+    data: isize,
+    udata: *mut c_void,
+    #[cfg(target_os = "freebsd")]
+    ext: [u64; 4],
+}
+
+#[repr(C)]
+struct Timespec {
+    tv_sec: c_long,
+    tv_nsec: c_long,
+}
+
+extern "C" {
+    fn kqueue() -> c_int;
+    fn kevent(
+        kq: c_int,
+        changelist: *const Kevent,
+        nchanges: c_int,
+        eventlist: *mut Kevent,
+        nevents: c_int,
+        timeout: *const Timespec,
+    ) -> c_int;
+    fn close(fd: c_int) -> c_int;
+}
+
+// undefined elsewhere, the test fails to compile there
+#[cfg(target_os = "freebsd")]
+const EVFILT_USER: i16 = -11;
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+const EVFILT_USER: i16 = -10;
+const EV_ADD: u16 = 0x0001;
+const EV_CLEAR: u16 = 0x0020;
+const NOTE_TRIGGER: u32 = 0x0100_0000;
+
+fn event(flags: u16, fflags: u32) -> Kevent {
+    Kevent {
+        ident: 1,
+        filter: EVFILT_USER,
+        flags,
+        fflags,
+        data: 0,
+        udata: ptr::null_mut(),
+        #[cfg(target_os = "freebsd")]
+        ext: [0; 4],
+    }
+}
+
+fn main() {
+    let kq = unsafe { kqueue() };
+    if kq < 0 {
+        std::process::exit(1);
+    }
+    let zero = Timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    let mut received = event(0, 0);
+    let works = unsafe {
+        kevent(kq, &event(EV_ADD | EV_CLEAR, 0), 1, ptr::null_mut(), 0, &zero) == 0
+            && kevent(kq, &event(0, NOTE_TRIGGER), 1, ptr::null_mut(), 0, &zero) == 0
+            && kevent(kq, ptr::null(), 0, &mut received, 1, &zero) == 1
+            && received.filter == EVFILT_USER
+    };
+    unsafe { close(kq) };
+    std::process::exit(if works { 0 } else { 1 });
+}
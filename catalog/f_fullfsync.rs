@@ -0,0 +1,24 @@
+// fcntl(F_FULLFSYNC) flushes a file through the drive cache to permanent storage (macOS).
+// conf_test: describe=`fcntl(F_FULLFSYNC)` flushes files to permanent storage
+use std::fs::{self, File};
+use std::os::raw::c_int;
+use std::os::unix::io::AsRawFd;
+use std::path::PathBuf;
+
+extern "C" {
+    fn fcntl(fd: c_int, cmd: c_int, ...) -> c_int;
+}
+
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+const F_FULLFSYNC: c_int = 51;
+
+fn main() {
+    let dir = PathBuf::from(std::env::var_os("CONF_TEST_OUT_DIR").expect("CONF_TEST_OUT_DIR"));
+    let path = dir.join("f_fullfsync.probe");
+    let file = File::create(&path).expect("create probe file");
+    let works = unsafe { fcntl(file.as_raw_fd(), F_FULLFSYNC) } == 0;
+    drop(file);
+    let _ = fs::remove_file(&path);
+    std::process::exit(if works { 0 } else { 1 });
+}
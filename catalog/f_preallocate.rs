@@ -0,0 +1,43 @@
+// fcntl(F_PREALLOCATE) reserves disk space for a file without writing it (macOS).
+// conf_test: describe=`fcntl(F_PREALLOCATE)` reserves disk space
+use std::fs::{self, File};
+use std::os::raw::{c_int, c_uint};
+use std::os::unix::io::AsRawFd;
+use std::path::PathBuf;
+
+#[repr(C)]
+struct Fstore {
+    fst_flags: c_uint,
+    fst_posmode: c_int,
+    fst_offset: i64,
+    fst_length: i64,
+    fst_bytesalloc: i64,
+}
+
+extern "C" {
+    fn fcntl(fd: c_int, cmd: c_int, ...) -> c_int;
+}
+
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+const F_PREALLOCATE: c_int = 42;
+const F_ALLOCATEALL: c_uint = 4;
+const F_PEOFPOSMODE: c_int = 3;
+
+fn main() {
+    let dir = PathBuf::from(std::env::var_os("CONF_TEST_OUT_DIR").expect("CONF_TEST_OUT_DIR"));
+    let path = dir.join("f_preallocate.probe");
+    let file = File::create(&path).expect("create probe file");
+    let mut store = Fstore {
+        fst_flags: F_ALLOCATEALL,
+        fst_posmode: F_PEOFPOSMODE,
+        fst_offset: 0,
+        fst_length: 1 << 20,
+        fst_bytesalloc: 0,
+    };
+    let works = unsafe { fcntl(file.as_raw_fd(), F_PREALLOCATE, &mut store) } == 0
+        && store.fst_bytesalloc >= 1 << 20;
+    drop(file);
+    let _ = fs::remove_file(&path);
+    std::process::exit(if works { 0 } else { 1 });
+}
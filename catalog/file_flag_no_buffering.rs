@@ -0,0 +1,38 @@
+// Files in the build directory can be written unbuffered with FILE_FLAG_NO_BUFFERING and
+// conf_test: describe=files in the build directory can be written with `FILE_FLAG_NO_BUFFERING | FILE_FLAG_WRITE_THROUGH`
+// FILE_FLAG_WRITE_THROUGH, bypassing the system cache (Windows).
+use std::alloc::{self, Layout};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::windows::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::slice;
+
+const FILE_FLAG_NO_BUFFERING: u32 = 0x2000_0000;
+const FILE_FLAG_WRITE_THROUGH: u32 = 0x8000_0000;
+
+fn main() {
+    let dir = PathBuf::from(std::env::var_os("CONF_TEST_OUT_DIR").expect("CONF_TEST_OUT_DIR"));
+    let path = dir.join("file_flag_no_buffering.probe");
+    let mut file = match OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .custom_flags(FILE_FLAG_NO_BUFFERING | FILE_FLAG_WRITE_THROUGH)
+        .open(&path)
+    {
+        Ok(file) => file,
+        Err(_) => std::process::exit(1),
+    };
+    // unbuffered writes need sector aligned buffers and sizes
+    let layout = Layout::from_size_align(4096, 4096).expect("layout");
+    let works = unsafe {
+        let buffer = alloc::alloc_zeroed(layout);
+        let works = file.write_all(slice::from_raw_parts(buffer, 4096)).is_ok();
+        alloc::dealloc(buffer, layout);
+        works
+    };
+    drop(file);
+    let _ = fs::remove_file(&path);
+    std::process::exit(if works { 0 } else { 1 });
+}
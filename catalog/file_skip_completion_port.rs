@@ -0,0 +1,39 @@
+// SetFileCompletionNotificationModes() skips completion packets for overlapped operations
+// conf_test: describe=`SetFileCompletionNotificationModes()` skips packets for operations that complete immediately (Vista)
+// that succeed immediately (Windows Vista).
+use std::fs::{self, OpenOptions};
+use std::os::raw::c_void;
+use std::os::windows::fs::OpenOptionsExt;
+use std::os::windows::io::AsRawHandle;
+use std::path::PathBuf;
+
+// kernel32 is missing elsewhere, the test fails to link there
+#[link(name = "kernel32")]
+extern "system" {
+    fn SetFileCompletionNotificationModes(file: *mut c_void, flags: u8) -> i32;
+}
+
+const FILE_FLAG_OVERLAPPED: u32 = 0x4000_0000;
+const FILE_SKIP_COMPLETION_PORT_ON_SUCCESS: u8 = 1;
+const FILE_SKIP_SET_EVENT_ON_HANDLE: u8 = 2;
+
+fn main() {
+    let dir = PathBuf::from(std::env::var_os("CONF_TEST_OUT_DIR").expect("CONF_TEST_OUT_DIR"));
+    let path = dir.join("file_skip_completion_port.probe");
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .custom_flags(FILE_FLAG_OVERLAPPED)
+        .open(&path)
+        .expect("create probe file");
+    let works = unsafe {
+        SetFileCompletionNotificationModes(
+            file.as_raw_handle() as *mut c_void,
+            FILE_SKIP_COMPLETION_PORT_ON_SUCCESS | FILE_SKIP_SET_EVENT_ON_HANDLE,
+        )
+    } != 0;
+    drop(file);
+    let _ = fs::remove_file(&path);
+    std::process::exit(if works { 0 } else { 1 });
+}
@@ -0,0 +1,15 @@
+// The filesystem of the build directory tells file names apart by case.
+// conf_test: describe=File names differing only in case are distinct files
+use std::fs;
+use std::path::PathBuf;
+
+fn main() {
+    let dir = PathBuf::from(std::env::var_os("CONF_TEST_OUT_DIR").expect("CONF_TEST_OUT_DIR"));
+    let lower = dir.join("fs_case_sensitive.probe");
+    let upper = dir.join("FS_CASE_SENSITIVE.PROBE");
+    let _ = fs::remove_file(&upper);
+    fs::write(&lower, b"").expect("write probe file");
+    let sensitive = !upper.exists();
+    let _ = fs::remove_file(&lower);
+    std::process::exit(if sensitive { 0 } else { 1 });
+}
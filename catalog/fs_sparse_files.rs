@@ -0,0 +1,27 @@
+// Files in the build directory can have holes which take no disk space.
+// conf_test: describe=Files can have holes which take no disk space (Unix)
+use std::fs::{self, OpenOptions};
+use std::io::{Seek, SeekFrom, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::PathBuf;
+
+fn main() {
+    let dir = PathBuf::from(std::env::var_os("CONF_TEST_OUT_DIR").expect("CONF_TEST_OUT_DIR"));
+    let path = dir.join("fs_sparse_files.probe");
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .expect("create probe file");
+    let size = 64 * 1024 * 1024;
+    file.seek(SeekFrom::Start(size - 1)).expect("seek");
+    file.write_all(b"\0").expect("write");
+    file.sync_all().expect("sync");
+    let metadata = file.metadata().expect("metadata");
+    drop(file);
+    let _ = fs::remove_file(&path);
+    // st_blocks counts 512 byte units
+    let sparse = metadata.len() == size && metadata.blocks() * 512 < size / 2;
+    std::process::exit(if sparse { 0 } else { 1 });
+}
@@ -0,0 +1,22 @@
+// Symbolic links can be created and followed in the build directory.
+// conf_test: describe=Symbolic links can be created and followed
+use std::fs;
+use std::path::PathBuf;
+
+#[cfg(unix)]
+use std::os::unix::fs::symlink;
+#[cfg(windows)]
+use std::os::windows::fs::symlink_file as symlink;
+
+fn main() {
+    let dir = PathBuf::from(std::env::var_os("CONF_TEST_OUT_DIR").expect("CONF_TEST_OUT_DIR"));
+    let target = dir.join("fs_symlinks.target");
+    let link = dir.join("fs_symlinks.link");
+    let _ = fs::remove_file(&link);
+    fs::write(&target, b"conf_test").expect("write probe file");
+    let works = symlink(&target, &link).is_ok()
+        && fs::read(&link).map(|content| content == b"conf_test").unwrap_or(false);
+    let _ = fs::remove_file(&link);
+    let _ = fs::remove_file(&target);
+    std::process::exit(if works { 0 } else { 1 });
+}
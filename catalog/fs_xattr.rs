@@ -0,0 +1,80 @@
+// Extended attributes can be set and read back on files in the build directory.
+// conf_test: describe=Extended attributes can be set and read back (Linux, Android, macOS)
+use std::ffi::CString;
+use std::fs;
+use std::os::raw::{c_char, c_int, c_void};
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+extern "C" {
+    fn setxattr(
+        path: *const c_char,
+        name: *const c_char,
+        value: *const c_void,
+        size: usize,
+        flags: c_int,
+    ) -> c_int;
+    fn getxattr(path: *const c_char, name: *const c_char, value: *mut c_void, size: usize)
+        -> isize;
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const NAME: &str = "user.conf_test";
+
+#[cfg(target_os = "macos")]
+extern "C" {
+    fn setxattr(
+        path: *const c_char,
+        name: *const c_char,
+        value: *const c_void,
+        size: usize,
+        position: u32,
+        options: c_int,
+    ) -> c_int;
+    fn getxattr(
+        path: *const c_char,
+        name: *const c_char,
+        value: *mut c_void,
+        size: usize,
+        position: u32,
+        options: c_int,
+    ) -> isize;
+}
+
+#[cfg(target_os = "macos")]
+const NAME: &str = "org.conf_test.probe";
+
+fn main() {
+    let dir = PathBuf::from(std::env::var_os("CONF_TEST_OUT_DIR").expect("CONF_TEST_OUT_DIR"));
+    let file = dir.join("fs_xattr.probe");
+    fs::write(&file, b"").expect("write probe file");
+    let path = CString::new(file.as_os_str().as_bytes()).expect("path");
+    let name = CString::new(NAME).expect("name");
+    let value = b"conf_test";
+    let mut read = [0u8; 16];
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    let works = unsafe {
+        setxattr(path.as_ptr(), name.as_ptr(), value.as_ptr() as *const c_void, value.len(), 0)
+            == 0
+            && getxattr(path.as_ptr(), name.as_ptr(), read.as_mut_ptr() as *mut c_void, read.len())
+                == value.len() as isize
+    };
+    #[cfg(target_os = "macos")]
+    let works = unsafe {
+        setxattr(path.as_ptr(), name.as_ptr(), value.as_ptr() as *const c_void, value.len(), 0, 0)
+            == 0
+            && getxattr(
+                path.as_ptr(),
+                name.as_ptr(),
+                read.as_mut_ptr() as *mut c_void,
+                read.len(),
+                0,
+                0,
+            ) == value.len() as isize
+    };
+
+    let _ = fs::remove_file(&file);
+    std::process::exit(if works && &read[..value.len()] == value { 0 } else { 1 });
+}
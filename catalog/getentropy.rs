@@ -0,0 +1,15 @@
+// getentropy() fills small buffers (up to 256 bytes) with random data.
+// conf_test: android-api=28
+// conf_test: describe=`getentropy()` is available
+use std::os::raw::{c_int, c_void};
+
+extern "C" {
+    fn getentropy(buf: *mut c_void, len: usize) -> c_int;
+}
+
+fn main() {
+    let mut buffer = [0u8; 32];
+    let works = unsafe { getentropy(buffer.as_mut_ptr() as *mut c_void, buffer.len()) } == 0
+        && buffer.iter().any(|&byte| byte != 0);
+    std::process::exit(if works { 0 } else { 1 });
+}
@@ -0,0 +1,19 @@
+// getrandom() fills buffers from the kernel entropy pool without opening files.
+// conf_test: android-api=28
+// conf_test: describe=`getrandom()` is available (Linux, Android, FreeBSD)
+use std::os::raw::{c_uint, c_void};
+
+extern "C" {
+    fn getrandom(buf: *mut c_void, len: usize, flags: c_uint) -> isize;
+}
+
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+const FLAGS: c_uint = 0;
+
+fn main() {
+    let mut buffer = [0u8; 32];
+    let read = unsafe { getrandom(buffer.as_mut_ptr() as *mut c_void, buffer.len(), FLAGS) };
+    let works = read == buffer.len() as isize && buffer.iter().any(|&byte| byte != 0);
+    std::process::exit(if works { 0 } else { 1 });
+}
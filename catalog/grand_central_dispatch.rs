@@ -0,0 +1,22 @@
+// Grand Central Dispatch (libdispatch) runs work on its global queues.
+// conf_test: describe=Grand Central Dispatch runs work on its global queues
+use std::os::raw::{c_long, c_ulong, c_void};
+
+extern "C" {
+    fn dispatch_get_global_queue(identifier: c_long, flags: c_ulong) -> *mut c_void;
+    fn dispatch_sync_f(queue: *mut c_void, context: *mut c_void, work: extern "C" fn(*mut c_void));
+}
+
+extern "C" fn work(context: *mut c_void) {
+    unsafe { *(context as *mut u32) = 42 };
+}
+
+fn main() {
+    let queue = unsafe { dispatch_get_global_queue(0, 0) };
+    if queue.is_null() {
+        std::process::exit(1);
+    }
+    let mut value = 0u32;
+    unsafe { dispatch_sync_f(queue, &mut value as *mut u32 as *mut c_void, work) };
+    std::process::exit(if value == 42 { 0 } else { 1 });
+}
@@ -0,0 +1,19 @@
+// getrandom() accepts GRND_INSECURE, never blocking even before the pool is initialized.
+// conf_test: android-api=28
+// conf_test: describe=`getrandom()` accepts `GRND_INSECURE` (Linux 5.6, Android)
+use std::os::raw::{c_uint, c_void};
+
+extern "C" {
+    fn getrandom(buf: *mut c_void, len: usize, flags: c_uint) -> isize;
+}
+
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const GRND_INSECURE: c_uint = 4;
+
+fn main() {
+    let mut buffer = [0u8; 32];
+    let read =
+        unsafe { getrandom(buffer.as_mut_ptr() as *mut c_void, buffer.len(), GRND_INSECURE) };
+    std::process::exit(if read == buffer.len() as isize { 0 } else { 1 });
+}
@@ -0,0 +1,63 @@
+// Memory can be backed by hugepages, either reserved ones mapped with MAP_HUGETLB or
+// transparent hugepages. HUGEPAGES is set to 'hugetlb' or 'transparent' accordingly.
+// conf_test: describe=Memory can be backed by hugepages (`MAP_HUGETLB` or transparent)
+use std::fs;
+use std::os::raw::{c_int, c_long, c_void};
+use std::ptr;
+
+extern "C" {
+    fn mmap(
+        addr: *mut c_void,
+        len: usize,
+        prot: c_int,
+        flags: c_int,
+        fd: c_int,
+        offset: c_long,
+    ) -> *mut c_void;
+    fn munmap(addr: *mut c_void, len: usize) -> c_int;
+}
+
+const PROT_READ: c_int = 1;
+const PROT_WRITE: c_int = 2;
+const MAP_PRIVATE: c_int = 2;
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const MAP_ANONYMOUS: c_int = 0x20;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const MAP_HUGETLB: c_int = 0x40000;
+
+/// Maps and touches a 2MiB hugepage, fails unless hugepages are reserved.
+fn hugetlb() -> bool {
+    let len = 2 << 20;
+    unsafe {
+        let memory = mmap(
+            ptr::null_mut(),
+            len,
+            PROT_READ | PROT_WRITE,
+            MAP_PRIVATE | MAP_ANONYMOUS | MAP_HUGETLB,
+            -1,
+            0,
+        );
+        if memory as isize == -1 {
+            return false;
+        }
+        *(memory as *mut u8) = 42;
+        munmap(memory, len);
+    }
+    true
+}
+
+fn main() {
+    if hugetlb() {
+        println!("conf_test:set-env=HUGEPAGES=hugetlb");
+        return;
+    }
+    // "always [madvise] never", the selected mode in brackets
+    let transparent = fs::read_to_string("/sys/kernel/mm/transparent_hugepage/enabled")
+        .map(|modes| !modes.contains("[never]"))
+        .unwrap_or(false);
+    if !transparent {
+        std::process::exit(1);
+    }
+    println!("conf_test:set-env=HUGEPAGES=transparent");
+}
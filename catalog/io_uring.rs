@@ -0,0 +1,23 @@
+// io_uring instances can be set up, io_uring may be compiled out, disabled by the
+// 'kernel.io_uring_disabled' sysctl or blocked by seccomp in containers.
+// conf_test: describe=io_uring instances can be set up (Linux 5.1)
+use std::os::raw::{c_int, c_long};
+
+extern "C" {
+    fn syscall(number: c_long, ...) -> c_long;
+    fn close(fd: c_int) -> c_int;
+}
+
+// undefined elsewhere, the test fails to compile there
+#[cfg(target_os = "linux")]
+const SYS_IO_URING_SETUP: c_long = 425;
+
+fn main() {
+    // struct io_uring_params, zeroed for the defaults
+    let mut params = [0u8; 120];
+    let fd = unsafe { syscall(SYS_IO_URING_SETUP, 1 as c_long, params.as_mut_ptr()) };
+    if fd < 0 {
+        std::process::exit(1);
+    }
+    unsafe { close(fd as c_int) };
+}
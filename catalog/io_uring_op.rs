@@ -0,0 +1,57 @@
+// io_uring supports the opcode OPCODE. This is the template of the 'uring_<opcode>' tests,
+// the built-in catalog puts the `OPCODE` constant and the description in front of it.
+use std::os::raw::{c_int, c_long, c_void};
+
+extern "C" {
+    fn syscall(number: c_long, ...) -> c_long;
+    fn close(fd: c_int) -> c_int;
+}
+
+// undefined elsewhere, the test fails to compile there
+#[cfg(target_os = "linux")]
+const SYS_IO_URING_SETUP: c_long = 425;
+const SYS_IO_URING_REGISTER: c_long = 427;
+const IORING_REGISTER_PROBE: c_long = 8;
+const IO_URING_OP_SUPPORTED: u16 = 1;
+const PROBE_OPS: usize = 256;
+
+#[repr(C)]
+struct ProbeOp {
+    op: u8,
+    resv: u8,
+    flags: u16,
+    resv2: u32,
+}
+
+#[repr(C)]
+struct Probe {
+    last_op: u8,
+    ops_len: u8,
+    resv: u16,
+    resv2: [u32; 3],
+    ops: [ProbeOp; PROBE_OPS],
+}
+
+fn main() {
+    let mut params = [0u8; 120];
+    let fd = unsafe { syscall(SYS_IO_URING_SETUP, 1 as c_long, params.as_mut_ptr()) };
+    if fd < 0 {
+        std::process::exit(1);
+    }
+    // the kernel rejects probes that are not zeroed
+    let mut probe: Probe = unsafe { std::mem::zeroed() };
+    let registered = unsafe {
+        syscall(
+            SYS_IO_URING_REGISTER,
+            fd,
+            IORING_REGISTER_PROBE,
+            &mut probe as *mut Probe as *mut c_void,
+            PROBE_OPS as c_long,
+        )
+    };
+    unsafe { close(fd as c_int) };
+    let supported = registered == 0
+        && OPCODE <= probe.last_op
+        && probe.ops[OPCODE as usize].flags & IO_URING_OP_SUPPORTED != 0;
+    std::process::exit(if supported { 0 } else { 1 });
+}
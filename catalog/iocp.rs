@@ -0,0 +1,48 @@
+// I/O completion ports queue and dequeue completion packets (Windows).
+// conf_test: describe=I/O completion ports queue and dequeue completion packets
+use std::os::raw::c_void;
+use std::ptr;
+
+// kernel32 is missing elsewhere, the test fails to link there
+#[link(name = "kernel32")]
+extern "system" {
+    fn CreateIoCompletionPort(
+        file: *mut c_void,
+        existing: *mut c_void,
+        key: usize,
+        threads: u32,
+    ) -> *mut c_void;
+    fn PostQueuedCompletionStatus(
+        port: *mut c_void,
+        bytes: u32,
+        key: usize,
+        overlapped: *mut c_void,
+    ) -> i32;
+    fn GetQueuedCompletionStatus(
+        port: *mut c_void,
+        bytes: *mut u32,
+        key: *mut usize,
+        overlapped: *mut *mut c_void,
+        milliseconds: u32,
+    ) -> i32;
+    fn CloseHandle(handle: *mut c_void) -> i32;
+}
+
+const INVALID_HANDLE_VALUE: *mut c_void = -1isize as *mut c_void;
+
+fn main() {
+    let port = unsafe { CreateIoCompletionPort(INVALID_HANDLE_VALUE, ptr::null_mut(), 0, 0) };
+    if port.is_null() {
+        std::process::exit(1);
+    }
+    let mut bytes = 0;
+    let mut key = 0;
+    let mut overlapped = ptr::null_mut();
+    let works = unsafe {
+        PostQueuedCompletionStatus(port, 42, 7, ptr::null_mut()) != 0
+            && GetQueuedCompletionStatus(port, &mut bytes, &mut key, &mut overlapped, 0) != 0
+    } && bytes == 42
+        && key == 7;
+    unsafe { CloseHandle(port) };
+    std::process::exit(if works { 0 } else { 1 });
+}
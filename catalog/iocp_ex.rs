@@ -0,0 +1,63 @@
+// GetQueuedCompletionStatusEx() dequeues many completion packets at once (Windows Vista).
+// conf_test: describe=`GetQueuedCompletionStatusEx()` dequeues many packets at once (Vista)
+use std::os::raw::c_void;
+use std::ptr;
+
+#[repr(C)]
+struct OverlappedEntry {
+    key: usize,
+    overlapped: *mut c_void,
+    internal: usize,
+    bytes: u32,
+}
+
+// kernel32 is missing elsewhere, the test fails to link there
+#[link(name = "kernel32")]
+extern "system" {
+    fn CreateIoCompletionPort(
+        file: *mut c_void,
+        existing: *mut c_void,
+        key: usize,
+        threads: u32,
+    ) -> *mut c_void;
+    fn PostQueuedCompletionStatus(
+        port: *mut c_void,
+        bytes: u32,
+        key: usize,
+        overlapped: *mut c_void,
+    ) -> i32;
+    fn GetQueuedCompletionStatusEx(
+        port: *mut c_void,
+        entries: *mut OverlappedEntry,
+        count: u32,
+        removed: *mut u32,
+        milliseconds: u32,
+        alertable: i32,
+    ) -> i32;
+    fn CloseHandle(handle: *mut c_void) -> i32;
+}
+
+const INVALID_HANDLE_VALUE: *mut c_void = -1isize as *mut c_void;
+
+fn main() {
+    let port = unsafe { CreateIoCompletionPort(INVALID_HANDLE_VALUE, ptr::null_mut(), 0, 0) };
+    if port.is_null() {
+        std::process::exit(1);
+    }
+    let mut entries: Vec<OverlappedEntry> = (0..4)
+        .map(|_| OverlappedEntry {
+            key: 0,
+            overlapped: ptr::null_mut(),
+            internal: 0,
+            bytes: 0,
+        })
+        .collect();
+    let mut removed = 0;
+    let works = unsafe {
+        PostQueuedCompletionStatus(port, 1, 1, ptr::null_mut()) != 0
+            && PostQueuedCompletionStatus(port, 2, 2, ptr::null_mut()) != 0
+            && GetQueuedCompletionStatusEx(port, entries.as_mut_ptr(), 4, &mut removed, 0, 0) != 0
+    } && removed == 2;
+    unsafe { CloseHandle(port) };
+    std::process::exit(if works { 0 } else { 1 });
+}
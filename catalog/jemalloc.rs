@@ -0,0 +1,41 @@
+// libjemalloc can be linked and tells its version through mallctl(). JEMALLOC_VERSION is set
+// for the crate which gets linked against it. Builds with a symbol prefix are not found.
+// conf_test: describe=jemalloc library
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int, c_void};
+use std::ptr;
+
+#[link(name = "jemalloc")]
+extern "C" {
+    fn mallctl(
+        name: *const c_char,
+        oldp: *mut c_void,
+        oldlenp: *mut usize,
+        newp: *mut c_void,
+        newlen: usize,
+    ) -> c_int;
+}
+
+fn main() {
+    let mut version: *const c_char = ptr::null();
+    let mut len = std::mem::size_of::<*const c_char>();
+    let name = b"version\0";
+    let ret = unsafe {
+        mallctl(
+            name.as_ptr() as *const c_char,
+            &mut version as *mut *const c_char as *mut c_void,
+            &mut len,
+            ptr::null_mut(),
+            0,
+        )
+    };
+    if ret != 0 || version.is_null() {
+        std::process::exit(1);
+    }
+    // "5.3.0-0-g54eaed1d8b56b1aa528be3bdd1877e59c56fa90c"
+    let version = unsafe { CStr::from_ptr(version) }.to_string_lossy();
+    let version = version.split('-').next().unwrap_or(&version);
+
+    println!("cargo:rustc-link-lib=jemalloc");
+    println!("conf_test:set-env=JEMALLOC_VERSION={}", version);
+}
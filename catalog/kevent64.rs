@@ -0,0 +1,49 @@
+// kevent64() takes 64 bit identifiers and user data on every architecture (macOS).
+// conf_test: describe=`kevent64()` is available (macOS)
+use std::os::raw::{c_int, c_long, c_uint};
+use std::ptr;
+
+#[repr(C)]
+struct Kevent64 {
+    ident: u64,
+    filter: i16,
+    flags: u16,
+    fflags: u32,
+    data: i64,
+    udata: u64,
+    ext: [u64; 2],
+}
+
+#[repr(C)]
+struct Timespec {
+    tv_sec: c_long,
+    tv_nsec: c_long,
+}
+
+extern "C" {
+    fn kqueue() -> c_int;
+    fn kevent64(
+        kq: c_int,
+        changelist: *const Kevent64,
+        nchanges: c_int,
+        eventlist: *mut Kevent64,
+        nevents: c_int,
+        flags: c_uint,
+        timeout: *const Timespec,
+    ) -> c_int;
+    fn close(fd: c_int) -> c_int;
+}
+
+fn main() {
+    let kq = unsafe { kqueue() };
+    if kq < 0 {
+        std::process::exit(1);
+    }
+    let zero = Timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    let works = unsafe { kevent64(kq, ptr::null(), 0, ptr::null_mut(), 0, 0, &zero) } == 0;
+    unsafe { close(kq) };
+    std::process::exit(if works { 0 } else { 1 });
+}
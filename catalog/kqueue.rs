@@ -0,0 +1,16 @@
+// kqueue() creates an event queue, the BSD counterpart to epoll.
+// conf_test: describe=`kqueue()` is available (FreeBSD, OpenBSD, NetBSD, DragonFly, macOS)
+use std::os::raw::c_int;
+
+extern "C" {
+    fn kqueue() -> c_int;
+    fn close(fd: c_int) -> c_int;
+}
+
+fn main() {
+    let kq = unsafe { kqueue() };
+    if kq < 0 {
+        std::process::exit(1);
+    }
+    unsafe { close(kq) };
+}
@@ -0,0 +1,20 @@
+// libdeflate can be linked and allocates a compressor. The crate gets linked against it,
+// libdeflate does not tell its version at runtime.
+// conf_test: describe=libdeflate
+use std::os::raw::{c_int, c_void};
+
+#[link(name = "deflate")]
+extern "C" {
+    fn libdeflate_alloc_compressor(level: c_int) -> *mut c_void;
+    fn libdeflate_free_compressor(compressor: *mut c_void);
+}
+
+fn main() {
+    let compressor = unsafe { libdeflate_alloc_compressor(6) };
+    if compressor.is_null() {
+        std::process::exit(1);
+    }
+    unsafe { libdeflate_free_compressor(compressor) };
+
+    println!("cargo:rustc-link-lib=deflate");
+}
@@ -0,0 +1,46 @@
+// madvise() accepts MADV_FREE for lazily freeing anonymous memory.
+// conf_test: describe=`madvise()` accepts `MADV_FREE` (also macOS, FreeBSD)
+use std::os::raw::{c_int, c_long, c_void};
+use std::ptr;
+
+extern "C" {
+    fn mmap(addr: *mut c_void, len: usize, prot: c_int, flags: c_int, fd: c_int, offset: c_long)
+        -> *mut c_void;
+    fn madvise(addr: *mut c_void, len: usize, advice: c_int) -> c_int;
+    fn munmap(addr: *mut c_void, len: usize) -> c_int;
+}
+
+const PROT_READ: c_int = 1;
+const PROT_WRITE: c_int = 2;
+const MAP_PRIVATE: c_int = 2;
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const MAP_ANONYMOUS: c_int = 0x20;
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+const MAP_ANONYMOUS: c_int = 0x1000;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const MADV_FREE: c_int = 8;
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+const MADV_FREE: c_int = 5;
+
+fn main() {
+    let len = 1 << 20;
+    let works = unsafe {
+        let memory = mmap(
+            ptr::null_mut(),
+            len,
+            PROT_READ | PROT_WRITE,
+            MAP_PRIVATE | MAP_ANONYMOUS,
+            -1,
+            0,
+        );
+        if memory as isize == -1 {
+            std::process::exit(1);
+        }
+        ptr::write_bytes(memory as *mut u8, 1, len);
+        let works = madvise(memory, len, MADV_FREE) == 0;
+        munmap(memory, len);
+        works
+    };
+    std::process::exit(if works { 0 } else { 1 });
+}
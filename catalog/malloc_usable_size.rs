@@ -0,0 +1,20 @@
+// malloc_usable_size() reports at least the requested size of an allocation (Linux,
+// Android, FreeBSD).
+// conf_test: describe=`malloc_usable_size()`
+use std::os::raw::c_void;
+
+extern "C" {
+    fn malloc(size: usize) -> *mut c_void;
+    fn free(ptr: *mut c_void);
+    fn malloc_usable_size(ptr: *mut c_void) -> usize;
+}
+
+fn main() {
+    unsafe {
+        let ptr = malloc(100);
+        if ptr.is_null() || malloc_usable_size(ptr) < 100 {
+            std::process::exit(1);
+        }
+        free(ptr);
+    }
+}
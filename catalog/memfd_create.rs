@@ -0,0 +1,29 @@
+// memfd_create() creates anonymous files living in memory.
+// conf_test: android-api=30
+// conf_test: describe=`memfd_create()` creates anonymous in-memory files
+use std::ffi::CString;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::os::raw::{c_char, c_int, c_uint};
+use std::os::unix::io::FromRawFd;
+
+extern "C" {
+    fn memfd_create(name: *const c_char, flags: c_uint) -> c_int;
+}
+
+const MFD_CLOEXEC: c_uint = 1;
+
+fn main() {
+    let name = CString::new("conf_test").expect("name");
+    let fd = unsafe { memfd_create(name.as_ptr(), MFD_CLOEXEC) };
+    if fd < 0 {
+        std::process::exit(1);
+    }
+    let mut file = unsafe { File::from_raw_fd(fd) };
+    let mut content = Vec::new();
+    let works = file.write_all(b"conf_test").is_ok()
+        && file.seek(SeekFrom::Start(0)).is_ok()
+        && file.read_to_end(&mut content).is_ok()
+        && content == b"conf_test";
+    std::process::exit(if works { 0 } else { 1 });
+}
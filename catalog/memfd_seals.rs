@@ -0,0 +1,41 @@
+// memfd files can be sealed against shrinking, growing and writing.
+// conf_test: android-api=30
+// conf_test: describe=memfd files can be sealed against shrinking, growing and writing
+use std::ffi::CString;
+use std::fs::File;
+use std::io::Write;
+use std::os::raw::{c_char, c_int, c_uint};
+use std::os::unix::io::FromRawFd;
+
+extern "C" {
+    fn memfd_create(name: *const c_char, flags: c_uint) -> c_int;
+    fn fcntl(fd: c_int, cmd: c_int, ...) -> c_int;
+}
+
+const MFD_CLOEXEC: c_uint = 1;
+const MFD_ALLOW_SEALING: c_uint = 2;
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const F_ADD_SEALS: c_int = 1033;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const F_GET_SEALS: c_int = 1034;
+const F_SEAL_SHRINK: c_int = 2;
+const F_SEAL_GROW: c_int = 4;
+const F_SEAL_WRITE: c_int = 8;
+
+fn main() {
+    let name = CString::new("conf_test").expect("name");
+    let fd = unsafe { memfd_create(name.as_ptr(), MFD_CLOEXEC | MFD_ALLOW_SEALING) };
+    if fd < 0 {
+        std::process::exit(1);
+    }
+    let mut file = unsafe { File::from_raw_fd(fd) };
+    file.write_all(b"conf_test").expect("write");
+
+    let seals = F_SEAL_SHRINK | F_SEAL_GROW | F_SEAL_WRITE;
+    let works = unsafe { fcntl(fd, F_ADD_SEALS, seals) == 0 && fcntl(fd, F_GET_SEALS) == seals }
+        // sealed files refuse changes
+        && file.write_all(b"more").is_err()
+        && file.set_len(0).is_err();
+    std::process::exit(if works { 0 } else { 1 });
+}
@@ -0,0 +1,47 @@
+// mremap() can grow an anonymous mapping, moving it when needed.
+// conf_test: describe=`mremap()` grows a mapping, moving it when needed
+use std::os::raw::{c_int, c_long, c_void};
+use std::ptr;
+
+extern "C" {
+    fn mmap(addr: *mut c_void, len: usize, prot: c_int, flags: c_int, fd: c_int, offset: c_long)
+        -> *mut c_void;
+    fn mremap(old: *mut c_void, old_len: usize, new_len: usize, flags: c_int, ...) -> *mut c_void;
+    fn munmap(addr: *mut c_void, len: usize) -> c_int;
+}
+
+const PROT_READ: c_int = 1;
+const PROT_WRITE: c_int = 2;
+const MAP_PRIVATE: c_int = 2;
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const MAP_ANONYMOUS: c_int = 0x20;
+const MREMAP_MAYMOVE: c_int = 1;
+
+fn main() {
+    let len = 1 << 16;
+    let works = unsafe {
+        let memory = mmap(
+            ptr::null_mut(),
+            len,
+            PROT_READ | PROT_WRITE,
+            MAP_PRIVATE | MAP_ANONYMOUS,
+            -1,
+            0,
+        );
+        if memory as isize == -1 {
+            std::process::exit(1);
+        }
+        *(memory as *mut u8) = 42;
+        let grown = mremap(memory, len, len * 16, MREMAP_MAYMOVE);
+        if grown as isize == -1 {
+            munmap(memory, len);
+            false
+        } else {
+            let works = *(grown as *const u8) == 42;
+            munmap(grown, len * 16);
+            works
+        }
+    };
+    std::process::exit(if works { 0 } else { 1 });
+}
@@ -0,0 +1,50 @@
+// The kernel exposes a NUMA topology and libnuma can be loaded, the node count is exported
+// as NUMA_NODES.
+// conf_test: describe=NUMA topology in '/sys' and a loadable libnuma
+use std::ffi::CString;
+use std::fs;
+use std::os::raw::{c_char, c_int, c_void};
+
+extern "C" {
+    fn dlopen(filename: *const c_char, flags: c_int) -> *mut c_void;
+    fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+}
+
+const RTLD_NOW: c_int = 2;
+
+fn is_node(name: &str) -> bool {
+    name.starts_with("node") && name[4..].parse::<u32>().is_ok()
+}
+
+fn libnuma_available() -> bool {
+    for library in &["libnuma.so.1", "libnuma.so"] {
+        let library = CString::new(*library).unwrap();
+        unsafe {
+            let handle = dlopen(library.as_ptr(), RTLD_NOW);
+            if handle.is_null() {
+                continue;
+            }
+            let symbol = dlsym(handle, b"numa_available\0".as_ptr() as *const c_char);
+            if symbol.is_null() {
+                continue;
+            }
+            let numa_available: extern "C" fn() -> c_int = std::mem::transmute(symbol);
+            return numa_available() >= 0;
+        }
+    }
+    false
+}
+
+fn main() {
+    let nodes = match fs::read_dir("/sys/devices/system/node") {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| is_node(&entry.file_name().to_string_lossy()))
+            .count(),
+        Err(_) => 0,
+    };
+    if nodes == 0 || !libnuma_available() {
+        std::process::exit(1);
+    }
+    println!("conf_test:set-env=NUMA_NODES={}", nodes);
+}
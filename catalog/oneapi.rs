@@ -0,0 +1,34 @@
+// An Intel oneAPI toolkit is installed: the DPC++ compiler runs and the SYCL runtime library
+// is present. The toolkit is looked up in ONEAPI_ROOT, else in '/opt/intel/oneapi'.
+// ONEAPI_ROOT and ONEAPI_LIB_DIR are set for the crate, ONEAPI_ROOT is exported to the
+// following tests.
+// conf_test: describe=Intel oneAPI toolkit with the DPC++ compiler and the SYCL runtime
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=ONEAPI_ROOT");
+    let root = env::var_os("ONEAPI_ROOT")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/opt/intel/oneapi"));
+
+    let compiler = root.join("compiler").join("latest");
+    let icpx = compiler
+        .join("bin")
+        .join(if cfg!(windows) { "icx.exe" } else { "icpx" });
+    let runs = Command::new(&icpx)
+        .arg("--version")
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false);
+    let lib_dir = compiler.join("lib");
+
+    if runs && (lib_dir.join("libsycl.so").exists() || lib_dir.join("sycl.lib").exists()) {
+        println!("conf_test:set-env=ONEAPI_ROOT={}", root.display());
+        println!("conf_test:set-env=ONEAPI_LIB_DIR={}", lib_dir.display());
+        println!("conf_test:export=ONEAPI_ROOT={}", root.display());
+    } else {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,50 @@
+// An OpenSSL compatible TLS library is installed: libssl and libcrypto link and report their
+// version. This is OpenSSL 1.1.0 or later, LibreSSL or BoringSSL. OPENSSL_FLAVOR
+// ('openssl', 'libressl' or 'boringssl'), OPENSSL_VERSION_NUMBER and OPENSSL_VERSION, when
+// the library tells it, are set for the crate which gets linked against both libraries.
+// OPENSSL_FLAVOR is exported to the following tests.
+// conf_test: describe=OpenSSL compatible libssl and libcrypto
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int, c_ulong, c_void};
+use std::ptr;
+
+#[allow(non_snake_case)]
+#[link(name = "ssl")]
+extern "C" {
+    fn OPENSSL_init_ssl(opts: u64, settings: *const c_void) -> c_int;
+}
+
+#[allow(non_snake_case)]
+#[link(name = "crypto")]
+extern "C" {
+    fn OpenSSL_version_num() -> c_ulong;
+    fn OpenSSL_version(kind: c_int) -> *const c_char;
+}
+
+const OPENSSL_VERSION: c_int = 0;
+
+fn main() {
+    if unsafe { OPENSSL_init_ssl(0, ptr::null()) } != 1 {
+        std::process::exit(1);
+    }
+    // "OpenSSL 3.0.2 15 Mar 2022", "LibreSSL 3.8.2" or "BoringSSL"
+    let version = unsafe { CStr::from_ptr(OpenSSL_version(OPENSSL_VERSION)) }.to_string_lossy();
+    let mut words = version.split_whitespace();
+    let flavor = match words.next() {
+        Some("OpenSSL") => "openssl",
+        Some("LibreSSL") => "libressl",
+        Some("BoringSSL") => "boringssl",
+        _ => std::process::exit(1),
+    };
+
+    println!("cargo:rustc-link-lib=ssl");
+    println!("cargo:rustc-link-lib=crypto");
+    println!("conf_test:set-env=OPENSSL_FLAVOR={}", flavor);
+    println!("conf_test:set-env=OPENSSL_VERSION_NUMBER={:#x}", unsafe {
+        OpenSSL_version_num()
+    });
+    if let Some(number) = words.next() {
+        println!("conf_test:set-env=OPENSSL_VERSION={}", number);
+    }
+    println!("conf_test:export=OPENSSL_FLAVOR={}", flavor);
+}
@@ -0,0 +1,14 @@
+// libcrypto provides the function FUNCTION. This is the template of the 'evp_<name>' tests,
+// the built-in catalog puts the `function` macro naming it and the description in front of
+// it. Linking fails when the function is missing.
+#[link(name = "crypto")]
+extern "C" {
+    #[link_name = function!()]
+    fn function();
+}
+
+fn main() {
+    if function as *const () as usize == 0 {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,23 @@
+// libcrypto is of the flavor FLAVOR. This is the template of the OpenSSL flavor tests, the
+// built-in catalog puts the `FLAVOR` its version string starts with, the `MIN_VERSION`
+// number it needs and the description in front of it.
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int, c_ulong};
+
+#[allow(non_snake_case)]
+#[link(name = "crypto")]
+extern "C" {
+    fn OpenSSL_version_num() -> c_ulong;
+    fn OpenSSL_version(kind: c_int) -> *const c_char;
+}
+
+const OPENSSL_VERSION: c_int = 0;
+
+fn main() {
+    let version = unsafe { CStr::from_ptr(OpenSSL_version(OPENSSL_VERSION)) }.to_string_lossy();
+    if version.split_whitespace().next() != Some(FLAVOR)
+        || unsafe { OpenSSL_version_num() } < MIN_VERSION
+    {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,18 @@
+// os_unfair_lock is available, the low level lock replacing OSSpinLock (macOS 10.12).
+// conf_test: describe=`os_unfair_lock` is available (macOS 10.12)
+extern "C" {
+    fn os_unfair_lock_lock(lock: *mut u32);
+    fn os_unfair_lock_trylock(lock: *mut u32) -> bool;
+    fn os_unfair_lock_unlock(lock: *mut u32);
+}
+
+fn main() {
+    let mut lock = 0u32;
+    let works = unsafe {
+        os_unfair_lock_lock(&mut lock);
+        let contended = !os_unfair_lock_trylock(&mut lock);
+        os_unfair_lock_unlock(&mut lock);
+        contended
+    };
+    std::process::exit(if works { 0 } else { 1 });
+}
@@ -0,0 +1,24 @@
+// pidfd_open() refers to processes by file descriptor, free of pid reuse races.
+// conf_test: android-api=31
+// conf_test: describe=`pidfd_open()` refers to processes by file descriptor
+use std::os::raw::{c_int, c_long};
+
+extern "C" {
+    fn syscall(number: c_long, ...) -> c_long;
+    fn getpid() -> c_int;
+    fn close(fd: c_int) -> c_int;
+}
+
+// the same on all architectures, undefined elsewhere so the test fails to compile there
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const SYS_PIDFD_OPEN: c_long = 434;
+
+fn main() {
+    let fd = unsafe { syscall(SYS_PIDFD_OPEN, getpid(), 0) };
+    if fd < 0 {
+        std::process::exit(1);
+    }
+    unsafe {
+        close(fd as c_int);
+    }
+}
@@ -0,0 +1,13 @@
+// pledge() restricts the process to a set of promised operations (OpenBSD).
+// conf_test: describe=`pledge()` is available (OpenBSD)
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+
+extern "C" {
+    fn pledge(promises: *const c_char, execpromises: *const c_char) -> c_int;
+}
+
+fn main() {
+    let works = unsafe { pledge(b"stdio\0".as_ptr() as *const c_char, ptr::null()) } == 0;
+    std::process::exit(if works { 0 } else { 1 });
+}
@@ -0,0 +1,43 @@
+// preadv2() accepts RWF_NOWAIT for reads that fail instead of blocking on uncached data.
+// conf_test: android-api=33
+// conf_test: describe=`preadv2()` accepts `RWF_NOWAIT`
+use std::fs::{self, File};
+use std::io::Read;
+use std::os::raw::{c_int, c_void};
+use std::os::unix::io::AsRawFd;
+use std::path::PathBuf;
+
+#[repr(C)]
+struct Iovec {
+    base: *mut c_void,
+    len: usize,
+}
+
+extern "C" {
+    fn preadv2(fd: c_int, iov: *const Iovec, count: c_int, offset: i64, flags: c_int) -> isize;
+}
+
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const RWF_NOWAIT: c_int = 8;
+const CONTENT: &[u8] = b"conf_test preadv2";
+
+fn main() {
+    let dir = PathBuf::from(std::env::var_os("CONF_TEST_OUT_DIR").expect("CONF_TEST_OUT_DIR"));
+    let path = dir.join("preadv2_nowait.probe");
+    fs::write(&path, CONTENT).expect("write probe file");
+    let mut file = File::open(&path).expect("open");
+    // get the data into the page cache, an uncached read may legitimately fail with EAGAIN
+    let mut cached = Vec::new();
+    file.read_to_end(&mut cached).expect("read");
+
+    let mut buffer = [0u8; 64];
+    let iov = Iovec {
+        base: buffer.as_mut_ptr() as *mut c_void,
+        len: buffer.len(),
+    };
+    let read = unsafe { preadv2(file.as_raw_fd(), &iov, 1, 0, RWF_NOWAIT) };
+    let works = read == CONTENT.len() as isize && &buffer[..CONTENT.len()] == CONTENT;
+    let _ = fs::remove_file(&path);
+    std::process::exit(if works { 0 } else { 1 });
+}
@@ -0,0 +1,70 @@
+// Winsock provides the Registered I/O (RIO) extension functions (Windows 8).
+// conf_test: describe=Winsock provides the Registered I/O extension (Windows 8)
+use std::mem;
+use std::net::UdpSocket;
+use std::os::raw::c_void;
+use std::os::windows::io::AsRawSocket;
+use std::ptr;
+
+#[repr(C)]
+struct Guid {
+    data1: u32,
+    data2: u16,
+    data3: u16,
+    data4: [u8; 8],
+}
+
+#[repr(C)]
+struct RioExtensionFunctionTable {
+    size: u32,
+    functions: [*mut c_void; 12],
+}
+
+// ws2_32 is missing elsewhere, the test fails to link there
+#[link(name = "ws2_32")]
+extern "system" {
+    fn WSAIoctl(
+        socket: usize,
+        code: u32,
+        input: *const c_void,
+        input_size: u32,
+        output: *mut c_void,
+        output_size: u32,
+        returned: *mut u32,
+        overlapped: *mut c_void,
+        completion: *mut c_void,
+    ) -> i32;
+}
+
+const SIO_GET_MULTIPLE_EXTENSION_FUNCTION_POINTER: u32 = 0xC800_0024;
+const WSAID_MULTIPLE_RIO: Guid = Guid {
+    data1: 0x8509_e081,
+    data2: 0x96dd,
+    data3: 0x4005,
+    data4: [0xb1, 0x65, 0x9e, 0x2e, 0xe8, 0xc7, 0x9e, 0x3f],
+};
+
+fn main() {
+    // binding initializes winsock
+    let socket = UdpSocket::bind("127.0.0.1:0").expect("bind");
+    let mut table = RioExtensionFunctionTable {
+        size: mem::size_of::<RioExtensionFunctionTable>() as u32,
+        functions: [ptr::null_mut(); 12],
+    };
+    let mut returned = 0;
+    let works = unsafe {
+        WSAIoctl(
+            socket.as_raw_socket() as usize,
+            SIO_GET_MULTIPLE_EXTENSION_FUNCTION_POINTER,
+            &WSAID_MULTIPLE_RIO as *const Guid as *const c_void,
+            mem::size_of::<Guid>() as u32,
+            &mut table as *mut RioExtensionFunctionTable as *mut c_void,
+            mem::size_of::<RioExtensionFunctionTable>() as u32,
+            &mut returned,
+            ptr::null_mut(),
+            ptr::null_mut(),
+        )
+    } == 0
+        && table.functions.iter().all(|function| !function.is_null());
+    std::process::exit(if works { 0 } else { 1 });
+}
@@ -0,0 +1,29 @@
+// A ROCm installation is present: hipcc runs and the HIP runtime library is present. ROCm is
+// looked up in ROCM_PATH, else in '/opt/rocm'. ROCM_ROOT and ROCM_LIB_DIR are set for the
+// crate, ROCM_ROOT is exported to the following tests.
+// conf_test: describe=ROCm with `hipcc` and the HIP runtime
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=ROCM_PATH");
+    let root = env::var_os("ROCM_PATH")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/opt/rocm"));
+
+    let runs = Command::new(root.join("bin").join("hipcc"))
+        .arg("--version")
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false);
+    let lib_dir = root.join("lib");
+
+    if runs && lib_dir.join("libamdhip64.so").exists() {
+        println!("conf_test:set-env=ROCM_ROOT={}", root.display());
+        println!("conf_test:set-env=ROCM_LIB_DIR={}", lib_dir.display());
+        println!("conf_test:export=ROCM_ROOT={}", root.display());
+    } else {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,44 @@
+// sendfile() copies from a file to another file descriptor inside the kernel.
+// conf_test: describe=`sendfile()` copies from a file to another file
+use std::fs::{self, File, OpenOptions};
+use std::os::raw::c_int;
+use std::os::unix::io::AsRawFd;
+use std::path::PathBuf;
+use std::ptr;
+
+extern "C" {
+    fn sendfile(out_fd: c_int, in_fd: c_int, offset: *mut i64, count: usize) -> isize;
+}
+
+// other systems have a different sendfile(), undefined there so the test fails to compile
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const CONTENT: &[u8] = b"conf_test sendfile";
+
+fn main() {
+    let dir = PathBuf::from(std::env::var_os("CONF_TEST_OUT_DIR").expect("CONF_TEST_OUT_DIR"));
+    let source = dir.join("sendfile.in");
+    let target = dir.join("sendfile.out");
+    fs::write(&source, CONTENT).expect("write probe file");
+    let input = File::open(&source).expect("open");
+    let output = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&target)
+        .expect("create");
+
+    let sent = unsafe {
+        sendfile(
+            output.as_raw_fd(),
+            input.as_raw_fd(),
+            ptr::null_mut(),
+            CONTENT.len(),
+        )
+    };
+    drop(output);
+    let works = sent == CONTENT.len() as isize
+        && fs::read(&target).map(|copy| copy == CONTENT).unwrap_or(false);
+    let _ = fs::remove_file(&source);
+    let _ = fs::remove_file(&target);
+    std::process::exit(if works { 0 } else { 1 });
+}
@@ -0,0 +1,33 @@
+// signalfd() receives signals through a file descriptor.
+// conf_test: android-api=18
+// conf_test: describe=`signalfd()` receives signals through a file descriptor
+use std::os::raw::{c_int, c_void};
+
+extern "C" {
+    fn sigemptyset(set: *mut c_void) -> c_int;
+    fn sigaddset(set: *mut c_void, signal: c_int) -> c_int;
+    fn signalfd(fd: c_int, mask: *const c_void, flags: c_int) -> c_int;
+    fn close(fd: c_int) -> c_int;
+}
+
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const SFD_CLOEXEC: c_int = 0o2000000;
+const SIGUSR1: c_int = 10;
+
+fn main() {
+    // large enough for the sigset_t of any libc
+    let mut mask = [0u64; 16];
+    let mask = mask.as_mut_ptr() as *mut c_void;
+    let fd = unsafe {
+        sigemptyset(mask);
+        sigaddset(mask, SIGUSR1);
+        signalfd(-1, mask, SFD_CLOEXEC)
+    };
+    if fd < 0 {
+        std::process::exit(1);
+    }
+    unsafe {
+        close(fd);
+    }
+}
@@ -0,0 +1,41 @@
+// SO_INCOMING_CPU reports the CPU that processed the packets of a socket, -1 until the
+// conf_test: describe=`SO_INCOMING_CPU` can be queried (Linux, Android)
+// kernel recorded one.
+use std::mem;
+use std::net::UdpSocket;
+use std::os::raw::{c_int, c_void};
+use std::os::unix::io::AsRawFd;
+
+extern "C" {
+    fn getsockopt(fd: c_int, level: c_int, name: c_int, value: *mut c_void, len: *mut u32)
+        -> c_int;
+}
+
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const SOL_SOCKET: c_int = 1;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const SO_INCOMING_CPU: c_int = 49;
+
+fn main() {
+    let receiver = UdpSocket::bind("127.0.0.1:0").expect("bind");
+    let sender = UdpSocket::bind("127.0.0.1:0").expect("bind");
+    sender
+        .send_to(b"conf_test", receiver.local_addr().expect("local_addr"))
+        .expect("send");
+    let mut buffer = [0u8; 16];
+    receiver.recv(&mut buffer).expect("recv");
+
+    let mut cpu: c_int = -1;
+    let mut len = mem::size_of::<c_int>() as u32;
+    let works = unsafe {
+        getsockopt(
+            receiver.as_raw_fd(),
+            SOL_SOCKET,
+            SO_INCOMING_CPU,
+            &mut cpu as *mut c_int as *mut c_void,
+            &mut len,
+        )
+    } == 0;
+    std::process::exit(if works { 0 } else { 1 });
+}
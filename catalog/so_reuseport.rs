@@ -0,0 +1,116 @@
+// Two UDP sockets with SO_REUSEPORT can bind the same port.
+// conf_test: describe=Two UDP sockets with `SO_REUSEPORT` bind the same port (Linux, Android, macOS, BSDs)
+use std::mem;
+use std::os::raw::{c_int, c_void};
+
+extern "C" {
+    fn socket(domain: c_int, kind: c_int, protocol: c_int) -> c_int;
+    fn setsockopt(fd: c_int, level: c_int, name: c_int, value: *const c_void, len: u32) -> c_int;
+    fn bind(fd: c_int, addr: *const SockaddrIn, len: u32) -> c_int;
+    fn getsockname(fd: c_int, addr: *mut SockaddrIn, len: *mut u32) -> c_int;
+    fn close(fd: c_int) -> c_int;
+}
+
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod consts {
+    pub const SOL_SOCKET: i32 = 1;
+    pub const SO_REUSEPORT: i32 = 15;
+
+    #[repr(C)]
+    pub struct SockaddrIn {
+        pub sin_family: u16,
+        pub sin_port: u16,
+        pub sin_addr: u32,
+        pub sin_zero: [u8; 8],
+    }
+
+    pub fn sockaddr(port: u16) -> SockaddrIn {
+        SockaddrIn {
+            sin_family: 2,
+            sin_port: port.to_be(),
+            sin_addr: u32::from_be_bytes([127, 0, 0, 1]).to_be(),
+            sin_zero: [0; 8],
+        }
+    }
+}
+
+#[cfg(any(
+    target_os = "macos",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+))]
+mod consts {
+    pub const SOL_SOCKET: i32 = 0xffff;
+    pub const SO_REUSEPORT: i32 = 0x200;
+
+    #[repr(C)]
+    pub struct SockaddrIn {
+        pub sin_len: u8,
+        pub sin_family: u8,
+        pub sin_port: u16,
+        pub sin_addr: u32,
+        pub sin_zero: [u8; 8],
+    }
+
+    pub fn sockaddr(port: u16) -> SockaddrIn {
+        SockaddrIn {
+            sin_len: 16,
+            sin_family: 2,
+            sin_port: port.to_be(),
+            sin_addr: u32::from_be_bytes([127, 0, 0, 1]).to_be(),
+            sin_zero: [0; 8],
+        }
+    }
+}
+
+use consts::*;
+
+const AF_INET: c_int = 2;
+const SOCK_DGRAM: c_int = 2;
+
+/// A UDP socket with SO_REUSEPORT bound to 127.0.0.1:'port', returns the fd and bound port.
+fn reuseport_socket(port: u16) -> Option<(c_int, u16)> {
+    unsafe {
+        let fd = socket(AF_INET, SOCK_DGRAM, 0);
+        if fd < 0 {
+            return None;
+        }
+        let one: c_int = 1;
+        let mut addr = sockaddr(port);
+        let mut len = mem::size_of::<SockaddrIn>() as u32;
+        if setsockopt(
+            fd,
+            SOL_SOCKET,
+            SO_REUSEPORT,
+            &one as *const c_int as *const c_void,
+            mem::size_of::<c_int>() as u32,
+        ) != 0
+            || bind(fd, &addr, len) != 0
+            || getsockname(fd, &mut addr, &mut len) != 0
+        {
+            close(fd);
+            return None;
+        }
+        Some((fd, u16::from_be(addr.sin_port)))
+    }
+}
+
+fn main() {
+    let works = match reuseport_socket(0) {
+        Some((first, port)) => {
+            let second = reuseport_socket(port);
+            unsafe {
+                close(first);
+                if let Some((second, _)) = second {
+                    close(second);
+                }
+            }
+            second.is_some()
+        }
+        None => false,
+    };
+    std::process::exit(if works { 0 } else { 1 });
+}
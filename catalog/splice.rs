@@ -0,0 +1,69 @@
+// splice() moves data between files and pipes without copying through userspace.
+// conf_test: describe=`splice()` moves data from a file through a pipe to another file
+use std::fs::{self, File, OpenOptions};
+use std::os::raw::{c_int, c_uint};
+use std::os::unix::io::AsRawFd;
+use std::path::PathBuf;
+use std::ptr;
+
+extern "C" {
+    fn pipe(fds: *mut c_int) -> c_int;
+    fn splice(
+        fd_in: c_int,
+        off_in: *mut i64,
+        fd_out: c_int,
+        off_out: *mut i64,
+        len: usize,
+        flags: c_uint,
+    ) -> isize;
+    fn close(fd: c_int) -> c_int;
+}
+
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const CONTENT: &[u8] = b"conf_test splice";
+
+fn main() {
+    let dir = PathBuf::from(std::env::var_os("CONF_TEST_OUT_DIR").expect("CONF_TEST_OUT_DIR"));
+    let source = dir.join("splice.in");
+    let target = dir.join("splice.out");
+    fs::write(&source, CONTENT).expect("write probe file");
+    let input = File::open(&source).expect("open");
+    let output = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&target)
+        .expect("create");
+
+    let mut fds = [-1; 2];
+    let len = CONTENT.len();
+    let spliced = unsafe {
+        pipe(fds.as_mut_ptr()) == 0
+            && splice(
+                input.as_raw_fd(),
+                ptr::null_mut(),
+                fds[1],
+                ptr::null_mut(),
+                len,
+                0,
+            ) == len as isize
+            && splice(
+                fds[0],
+                ptr::null_mut(),
+                output.as_raw_fd(),
+                ptr::null_mut(),
+                len,
+                0,
+            ) == len as isize
+    };
+    unsafe {
+        close(fds[0]);
+        close(fds[1]);
+    }
+    drop(output);
+    let works = spliced && fs::read(&target).map(|copy| copy == CONTENT).unwrap_or(false);
+    let _ = fs::remove_file(&source);
+    let _ = fs::remove_file(&target);
+    std::process::exit(if works { 0 } else { 1 });
+}
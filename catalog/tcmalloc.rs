@@ -0,0 +1,19 @@
+// libtcmalloc from gperftools can be linked and tells its version. TCMALLOC_VERSION is set
+// for the crate which gets linked against it.
+// conf_test: describe=tcmalloc library from gperftools
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+
+#[link(name = "tcmalloc")]
+extern "C" {
+    fn tc_version(major: *mut c_int, minor: *mut c_int, patch: *mut *const c_char)
+        -> *const c_char;
+}
+
+fn main() {
+    let (mut major, mut minor) = (0, 0);
+    unsafe { tc_version(&mut major, &mut minor, ptr::null_mut()) };
+
+    println!("cargo:rustc-link-lib=tcmalloc");
+    println!("conf_test:set-env=TCMALLOC_VERSION={}.{}", major, minor);
+}
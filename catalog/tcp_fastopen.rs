@@ -0,0 +1,34 @@
+// A listening TCP socket accepts TCP_FASTOPEN (server side fast open).
+// conf_test: describe=A listening socket accepts `TCP_FASTOPEN` (Linux, Android, macOS, FreeBSD)
+use std::mem;
+use std::net::TcpListener;
+use std::os::raw::{c_int, c_void};
+use std::os::unix::io::AsRawFd;
+
+extern "C" {
+    fn setsockopt(fd: c_int, level: c_int, name: c_int, value: *const c_void, len: u32) -> c_int;
+}
+
+const IPPROTO_TCP: c_int = 6;
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const TCP_FASTOPEN: c_int = 23;
+#[cfg(target_os = "macos")]
+const TCP_FASTOPEN: c_int = 0x105;
+#[cfg(target_os = "freebsd")]
+const TCP_FASTOPEN: c_int = 1025;
+
+fn main() {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let queue: c_int = 5;
+    let works = unsafe {
+        setsockopt(
+            listener.as_raw_fd(),
+            IPPROTO_TCP,
+            TCP_FASTOPEN,
+            &queue as *const c_int as *const c_void,
+            mem::size_of::<c_int>() as u32,
+        )
+    } == 0;
+    std::process::exit(if works { 0 } else { 1 });
+}
@@ -0,0 +1,24 @@
+// timerfd_create() creates timers that deliver expirations through a file descriptor.
+// conf_test: android-api=19
+// conf_test: describe=`timerfd_create()` creates timers on `CLOCK_MONOTONIC`
+use std::os::raw::c_int;
+
+extern "C" {
+    fn timerfd_create(clock: c_int, flags: c_int) -> c_int;
+    fn close(fd: c_int) -> c_int;
+}
+
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const CLOCK_MONOTONIC: c_int = 1;
+const TFD_CLOEXEC: c_int = 0o2000000;
+
+fn main() {
+    let fd = unsafe { timerfd_create(CLOCK_MONOTONIC, TFD_CLOEXEC) };
+    if fd < 0 {
+        std::process::exit(1);
+    }
+    unsafe {
+        close(fd);
+    }
+}
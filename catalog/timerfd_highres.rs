@@ -0,0 +1,82 @@
+// timerfd timers on CLOCK_MONOTONIC fire with sub-millisecond resolution.
+// conf_test: describe=timerfd timers fire with sub-millisecond resolution (Linux, Android)
+use std::os::raw::{c_int, c_long, c_void};
+use std::ptr;
+use std::time::{Duration, Instant};
+
+#[repr(C)]
+struct Timespec {
+    tv_sec: c_long,
+    tv_nsec: c_long,
+}
+
+#[repr(C)]
+struct Itimerspec {
+    it_interval: Timespec,
+    it_value: Timespec,
+}
+
+extern "C" {
+    fn clock_getres(clock: c_int, res: *mut Timespec) -> c_int;
+    fn timerfd_create(clock: c_int, flags: c_int) -> c_int;
+    fn timerfd_settime(
+        fd: c_int,
+        flags: c_int,
+        new: *const Itimerspec,
+        old: *mut Itimerspec,
+    ) -> c_int;
+    fn read(fd: c_int, buf: *mut c_void, count: usize) -> isize;
+    fn close(fd: c_int) -> c_int;
+}
+
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const CLOCK_MONOTONIC: c_int = 1;
+
+fn main() {
+    let mut res = Timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    if unsafe { clock_getres(CLOCK_MONOTONIC, &mut res) } != 0
+        || res.tv_sec != 0
+        || res.tv_nsec > 1000
+    {
+        std::process::exit(1);
+    }
+
+    let fd = unsafe { timerfd_create(CLOCK_MONOTONIC, 0) };
+    if fd < 0 {
+        std::process::exit(1);
+    }
+
+    // the best of a few rounds, the machine may be busy
+    let mut best = Duration::from_secs(1);
+    for _ in 0..5 {
+        let timer = Itimerspec {
+            it_interval: Timespec {
+                tv_sec: 0,
+                tv_nsec: 0,
+            },
+            it_value: Timespec {
+                tv_sec: 0,
+                tv_nsec: 100_000,
+            },
+        };
+        let start = Instant::now();
+        let mut expirations = 0u64;
+        let fired = unsafe {
+            timerfd_settime(fd, 0, &timer, ptr::null_mut()) == 0
+                && read(fd, &mut expirations as *mut u64 as *mut c_void, 8) == 8
+        };
+        if !fired {
+            std::process::exit(1);
+        }
+        best = best.min(start.elapsed());
+    }
+    unsafe {
+        close(fd);
+    }
+
+    std::process::exit(if best < Duration::from_millis(1) { 0 } else { 1 });
+}
@@ -0,0 +1,42 @@
+// UDP generic segmentation offload (UDP_SEGMENT) can be set on a socket.
+// conf_test: describe=UDP generic segmentation offload can be set (Linux, Android)
+use std::mem;
+use std::net::UdpSocket;
+use std::os::raw::{c_int, c_void};
+use std::os::unix::io::AsRawFd;
+
+extern "C" {
+    fn setsockopt(fd: c_int, level: c_int, name: c_int, value: *const c_void, len: u32) -> c_int;
+    fn getsockopt(fd: c_int, level: c_int, name: c_int, value: *mut c_void, len: *mut u32)
+        -> c_int;
+}
+
+// undefined elsewhere, the test fails to compile there
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const SOL_UDP: c_int = 17;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const UDP_SEGMENT: c_int = 103;
+
+fn main() {
+    let socket = UdpSocket::bind("127.0.0.1:0").expect("bind");
+    let value: c_int = 1400;
+    let mut read: c_int = 0;
+    let mut len = mem::size_of::<c_int>() as u32;
+    let works = unsafe {
+        setsockopt(
+            socket.as_raw_fd(),
+            SOL_UDP,
+            UDP_SEGMENT,
+            &value as *const c_int as *const c_void,
+            mem::size_of::<c_int>() as u32,
+        ) == 0
+            && getsockopt(
+                socket.as_raw_fd(),
+                SOL_UDP,
+                UDP_SEGMENT,
+                &mut read as *mut c_int as *mut c_void,
+                &mut len,
+            ) == 0
+    };
+    std::process::exit(if works && read == value { 0 } else { 1 });
+}
@@ -0,0 +1,19 @@
+// unveil() restricts the filesystem view of the process (OpenBSD).
+// conf_test: describe=`unveil()` is available (OpenBSD)
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+
+extern "C" {
+    fn unveil(path: *const c_char, permissions: *const c_char) -> c_int;
+}
+
+fn main() {
+    let works = unsafe {
+        unveil(
+            b"/\0".as_ptr() as *const c_char,
+            b"r\0".as_ptr() as *const c_char,
+        ) == 0
+            && unveil(ptr::null(), ptr::null()) == 0
+    };
+    std::process::exit(if works { 0 } else { 1 });
+}
@@ -0,0 +1,24 @@
+// libz can be linked, the original zlib or zlib-ng built in compatibility mode. ZLIB_FLAVOR
+// ('zlib' or 'zlib-ng') and ZLIB_VERSION are set for the crate which gets linked against it.
+// conf_test: describe=zlib compatible libz
+use std::ffi::CStr;
+use std::os::raw::c_char;
+
+#[allow(non_snake_case)]
+#[link(name = "z")]
+extern "C" {
+    fn zlibVersion() -> *const c_char;
+}
+
+fn main() {
+    // "1.2.13" or "1.3.0.zlib-ng"
+    let version = unsafe { CStr::from_ptr(zlibVersion()) }.to_string_lossy();
+    let (flavor, version) = match version.strip_suffix(".zlib-ng") {
+        Some(version) => ("zlib-ng", version),
+        None => ("zlib", &*version),
+    };
+
+    println!("cargo:rustc-link-lib=z");
+    println!("conf_test:set-env=ZLIB_FLAVOR={}", flavor);
+    println!("conf_test:set-env=ZLIB_VERSION={}", version);
+}
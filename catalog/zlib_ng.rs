@@ -0,0 +1,17 @@
+// libz-ng with the native zlib-ng API can be linked. ZLIB_NG_VERSION is set for the crate
+// which gets linked against it.
+// conf_test: describe=zlib-ng native API in libz-ng
+use std::ffi::CStr;
+use std::os::raw::c_char;
+
+#[link(name = "z-ng")]
+extern "C" {
+    fn zlibng_version() -> *const c_char;
+}
+
+fn main() {
+    let version = unsafe { CStr::from_ptr(zlibng_version()) }.to_string_lossy();
+
+    println!("cargo:rustc-link-lib=z-ng");
+    println!("conf_test:set-env=ZLIB_NG_VERSION={}", version);
+}
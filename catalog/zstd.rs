@@ -0,0 +1,17 @@
+// libzstd can be linked. ZSTD_VERSION is set for the crate which gets linked against it.
+// conf_test: describe=Zstandard library libzstd
+use std::ffi::CStr;
+use std::os::raw::c_char;
+
+#[allow(non_snake_case)]
+#[link(name = "zstd")]
+extern "C" {
+    fn ZSTD_versionString() -> *const c_char;
+}
+
+fn main() {
+    let version = unsafe { CStr::from_ptr(ZSTD_versionString()) }.to_string_lossy();
+
+    println!("cargo:rustc-link-lib=zstd");
+    println!("conf_test:set-env=ZSTD_VERSION={}", version);
+}
@@ -0,0 +1,23 @@
+//! Crash-safe writing of the log, reports and state files.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Writes 'contents' to 'path' like `fs::write()`, but through a temporary file in the same
+/// directory which is renamed to 'path' once complete. A build interrupted meanwhile leaves
+/// the previous file in place rather than a truncated one.
+pub(crate) fn write<C: AsRef<[u8]>>(path: &Path, contents: C) -> io::Result<()> {
+    let tmp = tmp_path(path);
+    let result = fs::write(&tmp, contents).and_then(|()| fs::rename(&tmp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// '.NAME.PID.tmp' next to 'path', unique for concurrent build scripts writing the same file.
+fn tmp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()))
+}
@@ -0,0 +1,677 @@
+//! Configuration of a ConfTest run.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::hooks::Hooks;
+use crate::report::Report;
+use crate::{matrix, LAST_REPORT};
+use crate::{
+    Catalog, CfgStyle, ConfTest, NicheTargetPolicy, Outcome, ProbeContext, ProbeInfo, ProbeOptions,
+    ProbeOutcome, ProbeProvider, ProbeResult, ReportSink,
+};
+
+/// Configures and runs the configuration tests, created by `ConfTest::builder()`.
+///
+/// ```rust,ignore
+/// fn main() {
+///     conf_test::ConfTest::builder()
+///         .assume("io_uring", false)
+///         .run();
+/// }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct ConfTestBuilder {
+    pub(crate) assume: BTreeMap<String, bool>,
+    pub(crate) overrides_file: Option<PathBuf>,
+    pub(crate) cross_file: Option<PathBuf>,
+    pub(crate) category_separator: Option<String>,
+    pub(crate) cfg_styles: Vec<CfgStyle>,
+    pub(crate) host_info: bool,
+    pub(crate) host_parallelism: bool,
+    pub(crate) log_history: usize,
+    pub(crate) html_report: bool,
+    pub(crate) dot_graph: bool,
+    pub(crate) markdown_report: bool,
+    pub(crate) json_report: Option<PathBuf>,
+    pub(crate) github_actions: bool,
+    pub(crate) cache: bool,
+    pub(crate) reproducible: bool,
+    pub(crate) directives_only: bool,
+    pub(crate) progress_events: bool,
+    pub(crate) niche_target_policy: NicheTargetPolicy,
+    pub(crate) missing_tests_policy: MissingTestsPolicy,
+    pub(crate) skip_conf_tests: bool,
+    pub(crate) fail_on_inconclusive: bool,
+    pub(crate) warn_sandboxed: bool,
+    pub(crate) strict: bool,
+    pub(crate) max_output: Option<usize>,
+    pub(crate) manual_features_in_tests: bool,
+    pub(crate) catalogs: Vec<Catalog>,
+    pub(crate) providers: Vec<Arc<dyn ProbeProvider>>,
+    pub(crate) hooks: Hooks,
+    pub(crate) sinks: Vec<Arc<dyn ReportSink>>,
+    pub(crate) manifest_dir: Option<PathBuf>,
+    pub(crate) out_dir: Option<PathBuf>,
+    pub(crate) tests_dir: Option<PathBuf>,
+    pub(crate) template_vars: BTreeMap<String, String>,
+    pub(crate) template_probes: BTreeMap<String, (PathBuf, BTreeMap<String, String>)>,
+    pub(crate) instance: Option<String>,
+    pub(crate) matrices: BTreeMap<String, Vec<String>>,
+    pub(crate) keep_work_dirs: bool,
+    pub(crate) include_features: Vec<String>,
+    pub(crate) exclude_features: Vec<String>,
+    pub(crate) skip_extern_crates: bool,
+    pub(crate) verbose: Option<usize>,
+    pub(crate) catalog_features: Vec<String>,
+}
+
+/// What happens when the crate has no 'conf_tests/' directory, set by
+/// `ConfTestBuilder::missing_tests_policy()`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MissingTestsPolicy {
+    /// Only log it, the features without catalog test are not tested. The default.
+    #[default]
+    Silent,
+    /// Emit a cargo warning.
+    Warn,
+    /// Fail the build.
+    Error,
+}
+
+const DEFAULT_CFG_STYLES: &[CfgStyle] = &[CfgStyle::Feature];
+
+/// How many bytes a test may print on stdout by default.
+const DEFAULT_MAX_OUTPUT: usize = 1024 * 1024;
+
+impl ConfTestBuilder {
+    /// Assume the outcome of the test for 'feature' without running it. Assumptions given in
+    /// the overrides file or the `CONF_TEST_ASSUME` environment variable take precedence.
+    pub fn assume(&mut self, feature: &str, enabled: bool) -> &mut Self {
+        self.assume.insert(feature.to_string(), enabled);
+        self
+    }
+
+    /// Use 'path' as overrides file instead of 'conf_test.overrides.toml' in the crate
+    /// directory. Unlike the default, this file must exist.
+    pub fn overrides_file<P: AsRef<Path>>(&mut self, path: P) -> &mut Self {
+        self.overrides_file = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Use 'path' as table of assumptions for cross builds instead of 'conf_test.cross.toml'
+    /// in the crate directory. Unlike the default, this file must exist.
+    pub fn cross_assumptions_file<P: AsRef<Path>>(&mut self, path: P) -> &mut Self {
+        self.cross_file = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Tests in subdirectories of 'conf_tests/' are named by joining the directory names and
+    /// the file name with 'separator', the default is `_`. With `__` the test
+    /// 'conf_tests/net/so_reuseport.rs' probes the feature 'net__so_reuseport'.
+    pub fn category_separator(&mut self, separator: &str) -> &mut Self {
+        self.category_separator = Some(separator.to_string());
+        self
+    }
+
+    /// Set the form of the cfgs emitted for discovered features, the default is
+    /// `CfgStyle::Feature`. Tests are compiled with the same cfgs for the features discovered
+    /// so far.
+    pub fn cfg_style(&mut self, style: CfgStyle) -> &mut Self {
+        self.cfg_styles = vec![style];
+        self
+    }
+
+    /// Emit discovered features additionally in 'style'. Emitting both `feature="x"` and
+    /// `has_x` lets downstream code migrate its `#[cfg]`s incrementally:
+    ///
+    /// ```rust,ignore
+    /// conf_test::ConfTest::builder()
+    ///     .add_cfg_style(conf_test::CfgStyle::Prefix("has_".into()))
+    ///     .run();
+    /// ```
+    pub fn add_cfg_style(&mut self, style: CfgStyle) -> &mut Self {
+        if self.cfg_styles.is_empty() {
+            self.cfg_styles.push(CfgStyle::Feature);
+        }
+        if !self.cfg_styles.contains(&style) {
+            self.cfg_styles.push(style);
+        }
+        self
+    }
+
+    /// Record the OS, architecture, kernel and distribution of the host as constants in the
+    /// generated values module.
+    pub fn host_info(&mut self, enabled: bool) -> &mut Self {
+        self.host_info = enabled;
+        self
+    }
+
+    /// Add the CPU count of the build host and a recommended thread pool size to the
+    /// generated values module, for crates baking defaults into builds for appliances.
+    pub fn host_parallelism(&mut self, enabled: bool) -> &mut Self {
+        self.host_parallelism = enabled;
+        self
+    }
+
+    /// Keep the logs of the last 'runs' runs in 'OUT_DIR/conf_test/logs/', named after the
+    /// time of the run. 'conf_test.log' always holds the log of the latest run. The default is
+    /// 0, keeping no history.
+    pub fn log_history(&mut self, runs: usize) -> &mut Self {
+        self.log_history = runs;
+        self
+    }
+
+    /// Render the report as HTML page to 'OUT_DIR/conf_test/report.html'.
+    pub fn html_report(&mut self, enabled: bool) -> &mut Self {
+        self.html_report = enabled;
+        self
+    }
+
+    /// Export the features and their relations as DOT graph to
+    /// 'OUT_DIR/conf_test/features.dot'.
+    pub fn dot_graph(&mut self, enabled: bool) -> &mut Self {
+        self.dot_graph = enabled;
+        self
+    }
+
+    /// Render the features, what their tests detect and the results as Markdown table to
+    /// 'OUT_DIR/conf_test/features.md', for inclusion in the README or crate docs.
+    pub fn markdown_report(&mut self, enabled: bool) -> &mut Self {
+        self.markdown_report = enabled;
+        self
+    }
+
+    /// Write the results as JSON document to 'path' as well, a location outside of 'OUT_DIR'
+    /// that CI jobs can upload as artifact. Relative paths are relative to the crate
+    /// directory. The `CONF_TEST_REPORT` environment variable takes precedence.
+    pub fn json_report<P: AsRef<Path>>(&mut self, path: P) -> &mut Self {
+        self.json_report = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// When running in GitHub Actions, append the results to the step outputs in
+    /// `GITHUB_OUTPUT` and a table of them to the job summary in `GITHUB_STEP_SUMMARY`.
+    pub fn github_actions(&mut self, enabled: bool) -> &mut Self {
+        self.github_actions = enabled;
+        self
+    }
+
+    /// Cache the results of successful tests in 'OUT_DIR/conf_test/cache.toml' and skip them
+    /// while their inputs and the environment fingerprint are unchanged. Off by default.
+    pub fn cache(&mut self, enabled: bool) -> &mut Self {
+        self.cache = enabled;
+        self
+    }
+
+    /// Make the log and the reports byte-stable for identical inputs. Timestamps are taken
+    /// from `SOURCE_DATE_EPOCH` or left out, durations are left out and paths below the crate
+    /// or 'OUT_DIR' are written relative to them. Setting the `CONF_TEST_REPRODUCIBLE`
+    /// environment variable enables this as well.
+    pub fn reproducible(&mut self, enabled: bool) -> &mut Self {
+        self.reproducible = enabled;
+        self
+    }
+
+    /// Print only the cargo directives to stdout, the '#' commentary goes to the log alone.
+    /// For build pipelines that parse the output of build scripts strictly.
+    pub fn directives_only(&mut self, enabled: bool) -> &mut Self {
+        self.directives_only = enabled;
+        self
+    }
+
+    /// Add machine-parsable `# conf_test/1 {json}` comments for the start, the outcome of each
+    /// feature and the end of the run, for tools extracting the progress from cargo's output.
+    pub fn progress_events(&mut self, enabled: bool) -> &mut Self {
+        self.progress_events = enabled;
+        self
+    }
+
+    /// How tests are handled when cross building for Haiku, Redox or UEFI, where executing
+    /// them on the host gives results that describe the host. The default is
+    /// `NicheTargetPolicy::CompileOnly`.
+    pub fn niche_target_policy(&mut self, policy: NicheTargetPolicy) -> &mut Self {
+        self.niche_target_policy = policy;
+        self
+    }
+
+    /// What happens when the crate has no 'conf_tests/' directory. The default is
+    /// `MissingTestsPolicy::Silent`, a crate that relies on its tests can make a missing
+    /// directory, for example one left out of the published package, a warning or an error.
+    pub fn missing_tests_policy(&mut self, policy: MissingTestsPolicy) -> &mut Self {
+        self.missing_tests_policy = policy;
+        self
+    }
+
+    /// Probe the tests in 'conf_tests/', enabled by default. Crates that only use
+    /// [catalog tests](crate#catalog-tests) and probe providers can disable this, the directory
+    /// is not looked at then.
+    pub fn filesystem_probes(&mut self, enabled: bool) -> &mut Self {
+        self.skip_conf_tests = !enabled;
+        self
+    }
+
+    /// Compile the tests that come after a feature enabled manually with `--features` with
+    /// its cfg as well. By default tests only see the features enabled by a test or an
+    /// assumption.
+    pub fn manual_features_in_tests(&mut self, enabled: bool) -> &mut Self {
+        self.manual_features_in_tests = enabled;
+        self
+    }
+
+    /// Fail the build when a test could not be run because rustc crashed, was missing or a
+    /// process failed to spawn. By default such features are disabled with a warning.
+    pub fn fail_on_inconclusive(&mut self, enabled: bool) -> &mut Self {
+        self.fail_on_inconclusive = enabled;
+        self
+    }
+
+    /// Warn about tests that were denied by a sandbox, as common in containerized CI. Their
+    /// features are disabled as 'sandboxed' and only listed in the summary by default.
+    pub fn warn_sandboxed(&mut self, enabled: bool) -> &mut Self {
+        self.warn_sandboxed = enabled;
+        self
+    }
+
+    /// Probe only the features matching one of 'patterns', a trailing `*` matches any rest of
+    /// the name (`net_*`). The other features are not tested, assumptions still apply to them.
+    /// Can be given more than once.
+    pub fn include_features(&mut self, patterns: &[&str]) -> &mut Self {
+        self.include_features
+            .extend(patterns.iter().map(|pattern| pattern.to_string()));
+        self
+    }
+
+    /// Don't probe the features matching one of 'patterns', like `include_features()`. Takes
+    /// precedence over `include_features()`.
+    pub fn exclude_features(&mut self, patterns: &[&str]) -> &mut Self {
+        self.exclude_features
+            .extend(patterns.iter().map(|pattern| pattern.to_string()));
+        self
+    }
+
+    /// Whether tests can use the build-dependencies of the crate, enabled by default. Resolving
+    /// them runs cargo once more, crates whose tests use no other crates can save this.
+    pub fn extern_crates(&mut self, enabled: bool) -> &mut Self {
+        self.skip_extern_crates = !enabled;
+        self
+    }
+
+    /// Show the first 'lines' of the rustc errors of tests which failed to compile as
+    /// warnings, like setting `CONF_TEST_VERBOSE`. The environment variable takes precedence.
+    pub fn verbose(&mut self, lines: usize) -> &mut Self {
+        self.verbose = Some(lines);
+        self
+    }
+
+    /// Keep the directories the tests ran in instead of removing them after each test, for
+    /// inspecting the files a test created.
+    pub fn keep_work_dirs(&mut self, enabled: bool) -> &mut Self {
+        self.keep_work_dirs = enabled;
+        self
+    }
+
+    /// Fail the build when a test in 'conf_tests/' failed to compile or could not be run,
+    /// with the rustc errors shown as warnings. Meant for the CI of the crate author, to
+    /// catch tests that rotted before users silently lose their features. Setting the
+    /// `CONF_TEST_STRICT` environment variable enables this as well.
+    pub fn strict(&mut self, enabled: bool) -> &mut Self {
+        self.strict = enabled;
+        self
+    }
+
+    /// Limit what a test may print on stdout to 'bytes', the default is 1 MiB. A test that
+    /// prints more gets killed and the build fails, a runaway test can't flood cargo with
+    /// directives this way.
+    pub fn max_output(&mut self, bytes: usize) -> &mut Self {
+        self.max_output = Some(bytes);
+        self
+    }
+
+    /// Probe 'features' by the catalog test of the same name when they have no test in
+    /// 'conf_tests/', see [Catalog tests](crate#catalog-tests). The features must be declared
+    /// in 'Cargo.toml' and must not enable a dependency. Can be given more than once.
+    pub fn catalog_features(&mut self, features: &[&str]) -> &mut Self {
+        self.catalog_features
+            .extend(features.iter().map(|feature| feature.to_string()));
+        self
+    }
+
+    /// Add the tests of 'catalog' to the catalog tests. The features opted in with
+    /// `catalog_features()` are probed by the catalog test of the same name, see `Catalog`
+    /// for which one is used when several catalogs have one.
+    pub fn register_catalog(&mut self, catalog: Catalog) -> &mut Self {
+        self.catalogs.push(catalog);
+        self
+    }
+
+    /// Add the tests contributed by 'provider' to the catalog tests, see `ProbeProvider`.
+    pub fn probe_provider<P: ProbeProvider + 'static>(&mut self, provider: P) -> &mut Self {
+        self.providers.push(Arc::new(provider));
+        self
+    }
+
+    /// Call 'hook' before each test is compiled. Returning `Some(enabled)` decides the feature
+    /// without running the test, it counts as assumed then. With several hooks the first one
+    /// that decides wins. Tests of manually set features and assumed features are not
+    /// passed to the hooks.
+    ///
+    /// ```rust,ignore
+    /// conf_test::ConfTest::builder()
+    ///     .on_probe_start(|probe| (probe.feature == "io_uring").then_some(false))
+    ///     .run();
+    /// ```
+    pub fn on_probe_start<F>(&mut self, hook: F) -> &mut Self
+    where
+        F: Fn(&ProbeContext) -> Option<bool> + Send + Sync + 'static,
+    {
+        self.hooks.probe_start.push(Arc::new(hook));
+        self
+    }
+
+    /// Call 'hook' after each test with its outcome, for custom logging or metrics. Cached
+    /// results are passed as well.
+    pub fn on_probe_end<F>(&mut self, hook: F) -> &mut Self
+    where
+        F: Fn(&ProbeContext, &ProbeOutcome) + Send + Sync + 'static,
+    {
+        self.hooks.probe_end.push(Arc::new(hook));
+        self
+    }
+
+    /// Call 'hook' once all features are decided, with each feature and whether it got
+    /// enabled.
+    pub fn on_finish<F>(&mut self, hook: F) -> &mut Self
+    where
+        F: Fn(&BTreeMap<String, bool>) + Send + Sync + 'static,
+    {
+        self.hooks.finish.push(Arc::new(hook));
+        self
+    }
+
+    /// Deliver the outcome and timings of every feature to 'sink' at the end of the run, see
+    /// `FileSink` and `StdoutSink`.
+    ///
+    /// ```rust,ignore
+    /// conf_test::ConfTest::builder()
+    ///     .report_sink(conf_test::FileSink::new("target/conf_test_metrics.jsonl"))
+    ///     .run();
+    /// ```
+    pub fn report_sink<S: ReportSink + 'static>(&mut self, sink: S) -> &mut Self {
+        self.sinks.push(Arc::new(sink));
+        self
+    }
+
+    /// The directory of the crate to test, containing 'Cargo.toml' and 'conf_tests/'. Defaults
+    /// to `CARGO_MANIFEST_DIR` as set by cargo for build scripts. Together with `out_dir()`
+    /// this allows running ConfTest outside of a build script, see `check()`.
+    pub fn manifest_dir<P: AsRef<Path>>(&mut self, path: P) -> &mut Self {
+        self.manifest_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// The directory where ConfTest places its files below 'conf_test/'. Defaults to `OUT_DIR`
+    /// as set by cargo for build scripts.
+    pub fn out_dir<P: AsRef<Path>>(&mut self, path: P) -> &mut Self {
+        self.out_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Take the tests from 'path' instead of 'conf_tests/', relative to the crate directory.
+    pub fn tests_dir<P: AsRef<Path>>(&mut self, path: P) -> &mut Self {
+        self.tests_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Set the variable 'name' to 'value' for the `@NAME@` placeholders of probe
+    /// [templates](crate#templates).
+    pub fn template_var(&mut self, name: &str, value: &str) -> &mut Self {
+        self.template_vars
+            .insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Probe 'feature' with the template at 'path', relative to the crate directory, and the
+    /// variables 'vars' in addition to the ones set by `template_var()`. This generates
+    /// families of nearly identical tests from a single template.
+    pub fn template_probe<P: AsRef<Path>>(
+        &mut self,
+        feature: &str,
+        path: P,
+        vars: &[(&str, &str)],
+    ) -> &mut Self {
+        let vars = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        self.template_probes
+            .insert(feature.to_string(), (path.as_ref().to_path_buf(), vars));
+        self
+    }
+
+    /// Probe 'feature' once more for each combination of 'features' being enabled or not, in
+    /// addition to its regular probe. Catches features that only work when combined in
+    /// specific ways, see [Feature matrix](crate#feature-matrix).
+    pub fn probe_matrix(&mut self, feature: &str, features: &[&str]) -> &mut Self {
+        if features.len() > 8 {
+            panic!(
+                "Feature matrix of '{}' has more than 8 features, {} combinations",
+                feature,
+                1usize << features.len()
+            );
+        }
+        self.matrices.insert(
+            feature.to_string(),
+            features.iter().map(|feature| feature.to_string()).collect(),
+        );
+        self
+    }
+
+    /// Name this run for running several differently configured ones from one build script,
+    /// for example with their own `tests_dir()` and `cfg_style()`. Its files go to
+    /// 'conf_test/NAME/' and its report is merged with the reports of the other named runs
+    /// for `ConfTest::enabled()` and the other queries. The build fails when two of them come
+    /// to different results for the same feature, features set manually are recorded by all
+    /// of them.
+    pub fn instance(&mut self, name: &str) -> &mut Self {
+        if name.is_empty() || name.contains(['/', '\\', '.']) {
+            panic!("Invalid ConfTest instance name: {:?}", name);
+        }
+        self.instance = Some(name.to_string());
+        self
+    }
+
+    /// The crate directory as set or from `CARGO_MANIFEST_DIR`.
+    pub(crate) fn crate_dir(&self) -> PathBuf {
+        match &self.manifest_dir {
+            Some(dir) => dir.clone(),
+            None => PathBuf::from(
+                std::env::var_os("CARGO_MANIFEST_DIR")
+                    .expect("env var CARGO_MANIFEST_DIR is not set"),
+            ),
+        }
+    }
+
+    /// The output directory as set or from `OUT_DIR`.
+    pub(crate) fn build_dir(&self) -> PathBuf {
+        match &self.out_dir {
+            Some(dir) => dir.clone(),
+            None => PathBuf::from(std::env::var_os("OUT_DIR").expect("env var OUT_DIR is not set")),
+        }
+    }
+
+    /// Where ConfTest places its files, 'conf_test/' in the output directory or the directory
+    /// of the instance below it.
+    pub(crate) fn conf_test_dir(&self) -> PathBuf {
+        let mut dir = self.build_dir().join("conf_test");
+        if let Some(instance) = &self.instance {
+            dir.push(instance);
+        }
+        dir
+    }
+
+    /// The 'conf_tests/' directory or the one set by `tests_dir()`, relative to the current
+    /// directory in build scripts where that is the crate directory.
+    pub(crate) fn conf_tests_dir(&self) -> PathBuf {
+        let tests_dir = self
+            .tests_dir
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("conf_tests"));
+        match &self.manifest_dir {
+            Some(dir) => dir.join(tests_dir),
+            None => tests_dir,
+        }
+    }
+
+    /// The 'conf_test_groups/' directory with the setups of the probe groups, relative to the
+    /// current directory in build scripts where that is the crate directory.
+    pub(crate) fn groups_dir(&self) -> PathBuf {
+        match &self.manifest_dir {
+            Some(dir) => dir.join("conf_test_groups"),
+            None => PathBuf::from("conf_test_groups"),
+        }
+    }
+
+    /// The registered catalogs followed by the catalogs of the probe providers.
+    pub(crate) fn catalogs(&self) -> Vec<Catalog> {
+        let target_os = std::env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+        let mut catalogs = self.catalogs.clone();
+        catalogs.extend(crate::catalog::provided(&self.providers, &target_os));
+        catalogs
+    }
+
+    /// Where the JSON report goes, from `CONF_TEST_REPORT` or as set.
+    pub(crate) fn json_report_path(&self) -> Option<PathBuf> {
+        std::env::var_os("CONF_TEST_REPORT")
+            .map(PathBuf::from)
+            .or_else(|| self.json_report.clone())
+            .map(|path| self.crate_dir().join(path))
+    }
+
+    /// How many bytes a test may print on stdout.
+    pub(crate) fn stdout_limit(&self) -> usize {
+        self.max_output.unwrap_or(DEFAULT_MAX_OUTPUT)
+    }
+
+    /// Whether the feature filters let 'feature' be probed.
+    pub(crate) fn is_selected(&self, feature: &str) -> bool {
+        let matches = |pattern: &String| match pattern.strip_suffix('*') {
+            Some(prefix) => feature.starts_with(prefix),
+            None => feature == pattern,
+        };
+        (self.include_features.is_empty() || self.include_features.iter().any(matches))
+            && !self.exclude_features.iter().any(matches)
+    }
+
+    /// How many lines of the rustc errors of failed tests are shown as warnings, None when
+    /// they are not shown.
+    pub(crate) fn verbose_lines(&self) -> Option<usize> {
+        match std::env::var("CONF_TEST_VERBOSE") {
+            Ok(lines) => Some(lines.parse().unwrap_or(10)),
+            Err(_) => self.verbose,
+        }
+    }
+
+    /// Whether failing tests fail the build.
+    pub(crate) fn is_strict(&self) -> bool {
+        self.strict || std::env::var_os("CONF_TEST_STRICT").is_some()
+    }
+
+    /// Whether this run must produce reproducible output.
+    pub(crate) fn is_reproducible(&self) -> bool {
+        self.reproducible || std::env::var_os("CONF_TEST_REPRODUCIBLE").is_some()
+    }
+
+    /// The styles in which discovered features are emitted.
+    pub(crate) fn cfg_styles(&self) -> &[CfgStyle] {
+        if self.cfg_styles.is_empty() {
+            DEFAULT_CFG_STYLES
+        } else {
+            &self.cfg_styles
+        }
+    }
+
+    /// The cargo instructions enabling 'feature' in all configured styles.
+    pub(crate) fn rustc_cfgs(&self, feature: &str) -> Vec<String> {
+        self.cfg_styles()
+            .iter()
+            .map(|style| style.rustc_cfg(feature))
+            .collect()
+    }
+
+    /// List the tests with this configuration without running them.
+    pub fn discover(&self) -> Vec<ProbeInfo> {
+        ConfTest::discover_with(self)
+    }
+
+    /// Run the single test at 'path' with this configuration, see `ConfTest::run_probe()`.
+    pub fn run_probe<P: AsRef<Path>>(&self, path: P, options: &ProbeOptions) -> ProbeResult {
+        ConfTest::run_probe_with(self, path.as_ref(), options)
+    }
+
+    /// Run the configuration tests with this configuration.
+    pub fn run(&self) {
+        ConfTest::run_with(self)
+    }
+
+    /// Run the configuration tests with this configuration like `run()` and return their
+    /// results. With several [instances](crate#multiple-instances) these are the merged
+    /// results of all instances run so far. Empty when the tests are inhibited.
+    pub fn run_report(&self) -> Report {
+        ConfTest::run_with(self);
+        Self::last_report(Report::clone)
+    }
+
+    /// Run the configuration tests without emitting anything to cargo, for use outside of build
+    /// scripts (an xtask or other developer tooling checking the environment up front). Returns
+    /// each feature and whether it got enabled. The log and reports are still written to the
+    /// output directory.
+    ///
+    /// ```rust,ignore
+    /// let features = conf_test::ConfTest::builder()
+    ///     .manifest_dir("crates/netio")
+    ///     .out_dir("target/conf_test")
+    ///     .check();
+    /// ```
+    pub fn check(&self) -> BTreeMap<String, bool> {
+        ConfTest::execute(self, false);
+        Self::last_report(|report| {
+            report
+                .features
+                .iter()
+                .map(|(feature, status)| (feature.clone(), status.is_enabled()))
+                .collect()
+        })
+    }
+
+    /// Like `check()`, but tells apart features that were checked to be absent from those
+    /// whose test could not decide.
+    pub fn outcomes(&self) -> BTreeMap<String, Outcome> {
+        ConfTest::execute(self, false);
+        Self::last_report(|report| {
+            report
+                .features
+                .iter()
+                .map(|(feature, status)| (feature.clone(), status.outcome()))
+                .collect()
+        })
+    }
+
+    /// Like `check()`, but returns a human readable table of the features, whether they are
+    /// supported and why, see `ConfTest::print_matrix()`.
+    pub fn matrix(&self) -> String {
+        ConfTest::execute(self, false);
+        Self::last_report(matrix::render)
+    }
+
+    /// Applies 'f' to the report of the last run.
+    fn last_report<T: Default>(f: impl FnOnce(&Report) -> T) -> T {
+        LAST_REPORT
+            .lock()
+            .expect("ConfTest report poisoned")
+            .as_ref()
+            .map(f)
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,138 @@
+//! Caching of successful test results between runs of the build script.
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::{atomic, command_output, Status};
+
+/// The cached stdout of successful tests, only valid for the environment fingerprint they were
+/// recorded with.
+#[derive(Debug)]
+pub(crate) struct Cache {
+    path: PathBuf,
+    fingerprint: String,
+    entries: BTreeMap<String, Entry>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    /// Hash over all inputs of the test.
+    hash: String,
+    stdout: String,
+}
+
+impl Cache {
+    /// Loads the cache from 'path'. The cache starts empty when it does not exist or was
+    /// recorded for another fingerprint. Returns whether the cache was invalidated.
+    pub(crate) fn load(path: &Path, fingerprint: String) -> (Cache, bool) {
+        let mut cache = Cache {
+            path: path.to_path_buf(),
+            fingerprint,
+            entries: BTreeMap::new(),
+        };
+
+        let table = match fs::read_to_string(path)
+            .ok()
+            .and_then(|source| source.parse::<toml::Value>().ok())
+        {
+            Some(toml::Value::Table(table)) => table,
+            _ => return (cache, false),
+        };
+
+        if table.get("fingerprint").and_then(toml::Value::as_str) != Some(&cache.fingerprint) {
+            return (cache, true);
+        }
+
+        if let Some(toml::Value::Table(tests)) = table.get("tests") {
+            for (feature, entry) in tests {
+                let field = |name| entry.get(name).and_then(toml::Value::as_str);
+                // ignore anything unexpected, the test will run again
+                if let (Some(hash), Some(stdout)) = (field("hash"), field("stdout")) {
+                    cache.entries.insert(
+                        feature.clone(),
+                        Entry {
+                            hash: hash.to_string(),
+                            stdout: stdout.to_string(),
+                        },
+                    );
+                }
+            }
+        }
+
+        (cache, false)
+    }
+
+    /// Returns the cached stdout of the successful test for 'feature' when its inputs did not
+    /// change.
+    pub(crate) fn get(&self, feature: &str, hash: &str) -> Option<String> {
+        self.entries
+            .get(feature)
+            .filter(|entry| entry.hash == hash)
+            .map(|entry| entry.stdout.clone())
+    }
+
+    /// Records the result of the test for 'feature'. Failed tests are not cached, they may
+    /// succeed once the missing library or tool gets installed.
+    pub(crate) fn insert(&mut self, feature: &str, hash: String, result: &Result<String, Status>) {
+        match result {
+            Ok(stdout) => {
+                self.entries.insert(
+                    feature.to_string(),
+                    Entry {
+                        hash,
+                        stdout: stdout.clone(),
+                    },
+                );
+            }
+            Err(_) => {
+                self.entries.remove(feature);
+            }
+        }
+    }
+
+    /// Writes the cache back to disk.
+    pub(crate) fn save(&self) {
+        let mut tests = toml::value::Table::new();
+        for (feature, entry) in &self.entries {
+            let mut table = toml::value::Table::new();
+            table.insert("hash".into(), toml::Value::String(entry.hash.clone()));
+            table.insert("stdout".into(), toml::Value::String(entry.stdout.clone()));
+            tests.insert(feature.clone(), toml::Value::Table(table));
+        }
+
+        let mut cache = toml::value::Table::new();
+        cache.insert(
+            "fingerprint".into(),
+            toml::Value::String(self.fingerprint.clone()),
+        );
+        cache.insert("tests".into(), toml::Value::Table(tests));
+
+        let cache = toml::to_string(&toml::Value::Table(cache)).expect("Failed to format cache");
+        atomic::write(&self.path, cache).expect("Failed to write cache");
+    }
+}
+
+/// Fingerprints the things tests may depend on: the rustc version and commit, kernel release,
+/// libc version, target and compiler flags and the environment variables set for the tests.
+pub(crate) fn fingerprint(test_env: &BTreeMap<String, String>) -> String {
+    let rustc = std::env::var_os("RUSTC").unwrap_or_else(|| OsString::from("rustc"));
+
+    let mut fingerprint = String::new();
+    fingerprint.push_str(&command_output(Command::new(rustc).arg("-vV")));
+    fingerprint.push_str(&command_output(Command::new("uname").arg("-srvm")));
+    fingerprint.push_str(&command_output(
+        Command::new("getconf").arg("GNU_LIBC_VERSION"),
+    ));
+    for var in ["TARGET", "HOST", "CARGO_ENCODED_RUSTFLAGS"] {
+        if let Some(value) = std::env::var_os(var) {
+            fingerprint.push_str(&format!("{}={}\n", var, value.to_string_lossy()));
+        }
+    }
+    for (var, value) in test_env {
+        fingerprint.push_str(&format!("{}={}\n", var, value));
+    }
+    fingerprint
+}
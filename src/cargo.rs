@@ -0,0 +1,206 @@
+//! The cargo running the build and what it supports.
+
+use std::process::Command;
+use std::sync::Mutex;
+
+use crate::command_output;
+
+/// Directives cargo knows, everything else in the old `cargo:KEY=VALUE` form is metadata for
+/// dependent packages.
+const DIRECTIVES: &[&str] = &[
+    "rerun-if-changed",
+    "rerun-if-env-changed",
+    "rustc-link-arg",
+    "rustc-link-arg-bin",
+    "rustc-link-arg-bins",
+    "rustc-link-arg-tests",
+    "rustc-link-arg-examples",
+    "rustc-link-arg-benches",
+    "rustc-link-arg-cdylib",
+    "rustc-cdylib-link-arg",
+    "rustc-link-lib",
+    "rustc-link-search",
+    "rustc-flags",
+    "rustc-cfg",
+    "rustc-check-cfg",
+    "rustc-env",
+    "warning",
+    "error",
+    "metadata",
+];
+
+/// What the cargo running the build supports beyond the `rust-version` of this crate. When
+/// its version can't be determined the old directive syntax is used.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Capabilities {
+    /// The (major, minor) version of cargo.
+    pub(crate) version: Option<(u32, u32)>,
+    /// The `cargo::KEY=VALUE` directive syntax can be used.
+    pub(crate) new_syntax: bool,
+    /// The `cargo::error=MESSAGE` directive is understood (since cargo 1.84), older ones get
+    /// a warning and the build script fails on its own.
+    pub(crate) error: bool,
+}
+
+static CAPABILITIES: Mutex<Option<Capabilities>> = Mutex::new(None);
+
+/// The capabilities of the cargo running the build, queried once per build script run.
+pub(crate) fn capabilities() -> Capabilities {
+    *CAPABILITIES
+        .lock()
+        .expect("cargo capabilities poisoned")
+        .get_or_insert_with(|| {
+            let version = version();
+            let new_syntax = version.is_some() && rust_version_allows_new_syntax();
+            Capabilities {
+                version,
+                new_syntax,
+                error: new_syntax && version.is_some_and(|version| version >= (1, 84)),
+            }
+        })
+}
+
+/// The (major, minor) version of the cargo running the build script, from `$CARGO --version`.
+fn version() -> Option<(u32, u32)> {
+    let cargo = std::env::var_os("CARGO")?;
+    let output = command_output(Command::new(cargo).arg("--version"));
+    // "cargo 1.77.0 (3fe68eabf 2024-02-29)"
+    parse_version(output.split_whitespace().nth(1)?)
+}
+
+fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let mut parts = version.split('.');
+    Some((parts.next()?.parse().ok()?, parts.next()?.parse().ok()?))
+}
+
+/// Cargo rejects the `cargo::KEY=VALUE` syntax for packages declaring a 'rust-version' older
+/// than 1.77.
+fn rust_version_allows_new_syntax() -> bool {
+    std::env::var("CARGO_PKG_RUST_VERSION")
+        .ok()
+        .and_then(|version| parse_version(&version))
+        .is_none_or(|version| version >= (1, 77))
+}
+
+/// Splits a directive in either syntax into key and value, None for other lines.
+fn split(line: &str) -> Option<(&str, &str)> {
+    line.strip_prefix("cargo::")
+        .or_else(|| line.strip_prefix("cargo:"))
+        .and_then(|directive| directive.split_once('='))
+}
+
+/// The message of an `error` directive, None for other lines.
+pub(crate) fn error_message(line: &str) -> Option<&str> {
+    match split(line)? {
+        ("error", message) => Some(message),
+        _ => None,
+    }
+}
+
+/// Rewrites a line of output to the directive syntax in use, lines which are no directives are
+/// returned unchanged. Without support for `error` it becomes a warning.
+pub(crate) fn directive(line: &str, capabilities: &Capabilities) -> String {
+    let (key, value) = match split(line) {
+        Some(directive) => directive,
+        None => return line.to_string(),
+    };
+
+    match (capabilities.new_syntax, key) {
+        (true, "error") if !capabilities.error => format!("cargo::warning={}", value),
+        (true, _) if DIRECTIVES.contains(&key) => format!("cargo::{}={}", key, value),
+        (true, _) => format!("cargo::metadata={}={}", key, value),
+        (false, "metadata") => format!("cargo:{}", value),
+        (false, "error") => format!("cargo:warning={}", value),
+        (false, _) => format!("cargo:{}={}", key, value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: Capabilities = Capabilities {
+        version: Some((1, 76)),
+        new_syntax: false,
+        error: false,
+    };
+    const NEW: Capabilities = Capabilities {
+        version: Some((1, 80)),
+        new_syntax: true,
+        error: false,
+    };
+    const ERROR: Capabilities = Capabilities {
+        version: Some((1, 84)),
+        new_syntax: true,
+        error: true,
+    };
+
+    #[test]
+    fn versions() {
+        assert_eq!(parse_version("1.77.0"), Some((1, 77)));
+        assert_eq!(parse_version("1.84.0-nightly"), Some((1, 84)));
+        assert_eq!(parse_version("1.82"), Some((1, 82)));
+        assert_eq!(parse_version("1"), None);
+        assert_eq!(parse_version("(3fe68eabf"), None);
+    }
+
+    #[test]
+    fn old_syntax() {
+        assert_eq!(
+            directive("cargo::rustc-cfg=foo", &OLD),
+            "cargo:rustc-cfg=foo"
+        );
+        assert_eq!(
+            directive("cargo:rustc-cfg=foo", &OLD),
+            "cargo:rustc-cfg=foo"
+        );
+        assert_eq!(
+            directive("cargo::metadata=key=value", &OLD),
+            "cargo:key=value"
+        );
+        assert_eq!(
+            directive("cargo::error=broken", &OLD),
+            "cargo:warning=broken"
+        );
+    }
+
+    #[test]
+    fn new_syntax() {
+        for capabilities in [&NEW, &ERROR] {
+            assert_eq!(
+                directive("cargo:rustc-cfg=foo", capabilities),
+                "cargo::rustc-cfg=foo"
+            );
+            assert_eq!(
+                directive("cargo:rustc-env=A=1", capabilities),
+                "cargo::rustc-env=A=1"
+            );
+            assert_eq!(
+                directive("cargo:key=value", capabilities),
+                "cargo::metadata=key=value"
+            );
+        }
+    }
+
+    #[test]
+    fn error_gating() {
+        assert_eq!(
+            directive("cargo:error=broken", &NEW),
+            "cargo::warning=broken"
+        );
+        assert_eq!(
+            directive("cargo:error=broken", &ERROR),
+            "cargo::error=broken"
+        );
+        assert_eq!(error_message("cargo::error=broken"), Some("broken"));
+        assert_eq!(error_message("cargo:warning=broken"), None);
+    }
+
+    #[test]
+    fn no_directive() {
+        for line in ["# a comment", "cargo:no-value", "plain output"] {
+            assert_eq!(directive(line, &ERROR), line);
+            assert_eq!(directive(line, &OLD), line);
+        }
+    }
+}
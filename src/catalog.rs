@@ -0,0 +1,412 @@
+//! Tests shipped with conf_test for commonly needed capabilities and catalogs of tests
+//! registered by the user.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Pairs each name with the source of 'catalog/<name>.rs'.
+macro_rules! catalog {
+    ($($name:literal),* $(,)?) => {
+        &[$(($name, include_str!(concat!("../catalog/", $name, ".rs")))),*]
+    };
+}
+
+/// The version of the tests in the built-in catalog. Bumped for a test when it changes in
+/// a way that makes it decide differently.
+const BUILTIN_VERSION: u32 = 1;
+
+/// The catalog tests by feature name.
+static CATALOG: &[(&str, &str)] = catalog![
+    "aligned_alloc",
+    "aligned_alloc_any_size",
+    "capsicum",
+    "clock_boottime",
+    "clock_monotonic_raw",
+    "clock_nanosleep",
+    "copy_file_range",
+    "cuda",
+    "dev_poll",
+    "dev_urandom",
+    "doors",
+    "epoll_exclusive",
+    "event_ports",
+    "eventfd",
+    "evfilt_machport",
+    "evfilt_user",
+    "f_fullfsync",
+    "f_preallocate",
+    "file_flag_no_buffering",
+    "file_skip_completion_port",
+    "fs_case_sensitive",
+    "fs_sparse_files",
+    "fs_symlinks",
+    "fs_xattr",
+    "getentropy",
+    "getrandom",
+    "grand_central_dispatch",
+    "grnd_insecure",
+    "hugepages",
+    "io_uring",
+    "iocp",
+    "iocp_ex",
+    "jemalloc",
+    "kevent64",
+    "kqueue",
+    "libdeflate",
+    "madv_cold",
+    "madv_free",
+    "malloc_usable_size",
+    "memfd_create",
+    "memfd_seals",
+    "mremap",
+    "numa",
+    "oneapi",
+    "openssl",
+    "os_unfair_lock",
+    "pidfd_open",
+    "pledge",
+    "preadv2_nowait",
+    "registered_io",
+    "rocm",
+    "sendfile",
+    "signalfd",
+    "so_incoming_cpu",
+    "so_reuseport",
+    "splice",
+    "tcmalloc",
+    "tcp_fastopen",
+    "timerfd",
+    "timerfd_highres",
+    "transparent_hugepages",
+    "udp_gro",
+    "udp_gso",
+    "unveil",
+    "zlib",
+    "zlib_ng",
+    "zstd",
+];
+
+/// The io_uring opcodes in kernel order, each one is probed by a 'uring_<opcode>' test
+/// made from the 'io_uring_op' template.
+static URING_OPS: &[&str] = &[
+    "nop",
+    "readv",
+    "writev",
+    "fsync",
+    "read_fixed",
+    "write_fixed",
+    "poll_add",
+    "poll_remove",
+    "sync_file_range",
+    "sendmsg",
+    "recvmsg",
+    "timeout",
+    "timeout_remove",
+    "accept",
+    "async_cancel",
+    "link_timeout",
+    "connect",
+    "fallocate",
+    "openat",
+    "close",
+    "files_update",
+    "statx",
+    "read",
+    "write",
+    "fadvise",
+    "madvise",
+    "send",
+    "recv",
+    "openat2",
+    "epoll_ctl",
+    "splice",
+    "provide_buffers",
+    "remove_buffers",
+    "tee",
+    "shutdown",
+    "renameat",
+    "unlinkat",
+    "mkdirat",
+    "symlinkat",
+    "linkat",
+    "msg_ring",
+    "fsetxattr",
+    "setxattr",
+    "fgetxattr",
+    "getxattr",
+    "socket",
+    "uring_cmd",
+    "send_zc",
+    "sendmsg_zc",
+    "read_multishot",
+    "waitid",
+    "futex_wait",
+    "futex_wake",
+    "futex_waitv",
+    "fixed_fd_install",
+    "ftruncate",
+    "bind",
+    "listen",
+];
+
+static URING_OP_TEMPLATE: &str = include_str!("../catalog/io_uring_op.rs");
+
+/// The OpenSSL flavor tests made from the 'openssl_flavor' template: the feature, its
+/// description, the word the version string of libcrypto starts with and the minimal
+/// `OpenSSL_version_num()`.
+static OPENSSL_FLAVORS: &[(&str, &str, &str, u32)] = &[
+    ("boringssl", "libcrypto is BoringSSL", "BoringSSL", 0),
+    ("libressl", "libcrypto is LibreSSL", "LibreSSL", 0),
+    (
+        "openssl3",
+        "libcrypto is OpenSSL 3.0 or later",
+        "OpenSSL",
+        0x3000_0000,
+    ),
+];
+
+static OPENSSL_FLAVOR_TEMPLATE: &str = include_str!("../catalog/openssl_flavor.rs");
+
+/// The libcrypto functions probed by 'evp_<name>' tests made from the 'openssl_evp'
+/// template, the name is the function without the 'EVP_' prefix in lower case.
+static EVP_FUNCTIONS: &[&str] = &[
+    "EVP_PKEY_derive",
+    "EVP_chacha20_poly1305",
+    "EVP_aes_128_ocb",
+    "EVP_DigestSign",
+    "EVP_sha3_256",
+    "EVP_PKEY_new_raw_private_key",
+    "EVP_PKEY_get_raw_public_key",
+    "EVP_MD_fetch",
+    "EVP_CIPHER_fetch",
+    "EVP_MAC_fetch",
+    "EVP_KDF_fetch",
+    "EVP_PKEY_fromdata",
+    "EVP_PKEY_Q_keygen",
+    "EVP_default_properties_enable_fips",
+];
+
+static OPENSSL_EVP_TEMPLATE: &str = include_str!("../catalog/openssl_evp.rs");
+
+/// A named collection of tests for features, registered with
+/// `ConfTestBuilder::register_catalog()`. Organizations can share their own tests across
+/// projects this way:
+///
+/// ```rust,ignore
+/// let mut catalog = conf_test::Catalog::new("acme");
+/// catalog.add("acme_hsm", 2, include_str!("acme_hsm.rs"));
+///
+/// conf_test::ConfTest::builder()
+///     .register_catalog(catalog)
+///     .run();
+/// ```
+///
+/// Each test carries a version. When several catalogs, including the built-in one, have a
+/// test for the same feature, the one with the highest version is used, on equal versions
+/// the catalog registered last wins. The built-in tests have version 1.
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    name: String,
+    tests: BTreeMap<String, (u32, Cow<'static, str>)>,
+}
+
+impl Catalog {
+    /// Creates an empty catalog, 'name' shows up in the log.
+    pub fn new(name: &str) -> Catalog {
+        Catalog {
+            name: name.to_string(),
+            tests: BTreeMap::new(),
+        }
+    }
+
+    /// The catalog shipped with conf_test.
+    pub fn builtin() -> Catalog {
+        let mut catalog = Catalog::new("builtin");
+        for (feature, source) in CATALOG {
+            catalog.add(feature, BUILTIN_VERSION, *source);
+        }
+        for (opcode, name) in URING_OPS.iter().enumerate() {
+            catalog.add(
+                &format!("uring_{}", name),
+                BUILTIN_VERSION,
+                format!(
+                    "// conf_test: describe=io_uring supports `IORING_OP_{}`\nconst OPCODE: u8 = {};\n{}",
+                    name.to_uppercase(),
+                    opcode,
+                    URING_OP_TEMPLATE
+                ),
+            );
+        }
+        for (feature, description, flavor, min_version) in OPENSSL_FLAVORS {
+            catalog.add(
+                feature,
+                BUILTIN_VERSION,
+                format!(
+                    "// conf_test: describe={}\nconst FLAVOR: &str = {:?};\nconst MIN_VERSION: std::os::raw::c_ulong = {:#x};\n{}",
+                    description, flavor, min_version, OPENSSL_FLAVOR_TEMPLATE
+                ),
+            );
+        }
+        for function in EVP_FUNCTIONS {
+            catalog.add(
+                &format!("evp_{}", function["EVP_".len()..].to_lowercase()),
+                BUILTIN_VERSION,
+                format!(
+                    "// conf_test: describe=libcrypto provides `{}()`\nmacro_rules! function {{\n    () => {{\n        {:?}\n    }};\n}}\n{}",
+                    function, function, OPENSSL_EVP_TEMPLATE
+                ),
+            );
+        }
+        catalog
+    }
+
+    /// Adds the test 'source' for 'feature' in 'version', replacing a test for the same
+    /// feature that was added before.
+    pub fn add(
+        &mut self,
+        feature: &str,
+        version: u32,
+        source: impl Into<Cow<'static, str>>,
+    ) -> &mut Self {
+        self.tests
+            .insert(feature.to_string(), (version, source.into()));
+        self
+    }
+
+    /// Adds the test from the file at 'path' for 'feature' in 'version'.
+    pub fn add_file<P: AsRef<Path>>(&mut self, feature: &str, version: u32, path: P) -> &mut Self {
+        let source = fs::read_to_string(path.as_ref()).unwrap_or_else(|err| {
+            panic!(
+                "Failed to read catalog test {}: {}",
+                path.as_ref().display(),
+                err
+            )
+        });
+        self.add(feature, version, source)
+    }
+
+    /// The name of the catalog.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The features this catalog has tests for, in sort order.
+    pub fn features(&self) -> impl Iterator<Item = &str> {
+        self.tests.keys().map(String::as_str)
+    }
+
+    /// The version of the test for 'feature'.
+    pub fn version(&self, feature: &str) -> Option<u32> {
+        self.tests.get(feature).map(|(version, _)| *version)
+    }
+}
+
+/// Contributes tests from another crate, registered with
+/// `ConfTestBuilder::probe_provider()`. This lets crates like 'conf-test-probes-foo' ship
+/// tests for a domain without forking conf_test:
+///
+/// ```rust,ignore
+/// #[derive(Debug)]
+/// pub struct GpuProbes;
+///
+/// impl conf_test::ProbeProvider for GpuProbes {
+///     fn catalog(&self) -> conf_test::Catalog {
+///         let mut catalog = conf_test::Catalog::new("gpu");
+///         catalog.add("gpu_vulkan", 1, include_str!("gpu_vulkan.rs"));
+///         catalog
+///     }
+///
+///     fn applies(&self, _feature: &str, target_os: &str) -> bool {
+///         target_os != "ios"
+///     }
+/// }
+/// ```
+///
+/// The tests of a provider compete with the other catalogs by version, see `Catalog`.
+pub trait ProbeProvider: fmt::Debug + Send + Sync {
+    /// The tests of this provider, the name of the catalog shows up in the log.
+    fn catalog(&self) -> Catalog;
+
+    /// Directives for the test of 'feature' as `key=value`, they are added to its source as
+    /// `// conf_test: key=value` lines. None by default.
+    fn directives(&self, _feature: &str) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Whether the test for 'feature' applies when building for 'target_os' (the value of
+    /// `CARGO_CFG_TARGET_OS`). Features whose test does not apply fall back to the other
+    /// catalogs. All tests apply by default.
+    fn applies(&self, _feature: &str, _target_os: &str) -> bool {
+        true
+    }
+}
+
+/// The catalogs of the 'providers' with their directives added and the tests that do not
+/// apply to 'target_os' left out.
+pub(crate) fn provided(providers: &[Arc<dyn ProbeProvider>], target_os: &str) -> Vec<Catalog> {
+    providers
+        .iter()
+        .map(|provider| {
+            let catalog = provider.catalog();
+            let mut provided = Catalog::new(&catalog.name);
+            for (feature, (version, source)) in catalog.tests {
+                if !provider.applies(&feature, target_os) {
+                    continue;
+                }
+                let mut directives: String = provider
+                    .directives(&feature)
+                    .iter()
+                    .map(|directive| format!("// conf_test: {}\n", directive))
+                    .collect();
+                if directives.is_empty() {
+                    provided.add(&feature, version, source);
+                } else {
+                    directives.push_str(&source);
+                    provided.add(&feature, version, directives);
+                }
+            }
+            provided
+        })
+        .collect()
+}
+
+/// Adds catalog tests for the 'features' which have no test of their own in 'tests'. The
+/// test is picked from the built-in catalog and the 'registered' ones. Their sources are
+/// written to 'dir', files that are already up to date are left alone. Returns the catalog
+/// and version of each added test for the log.
+pub(crate) fn add_tests<'a>(
+    tests: &mut BTreeMap<String, PathBuf>,
+    features: impl IntoIterator<Item = &'a String>,
+    registered: &[Catalog],
+    dir: &Path,
+) -> BTreeMap<String, String> {
+    let builtin = Catalog::builtin();
+    let mut origins = BTreeMap::new();
+    for feature in features {
+        if tests.contains_key(feature) {
+            continue;
+        }
+        // max_by_key() returns the last of equal versions
+        let (catalog, (version, source)) = match std::iter::once(&builtin)
+            .chain(registered)
+            .filter_map(|catalog| Some((catalog, catalog.tests.get(feature)?)))
+            .max_by_key(|(_, (version, _))| *version)
+        {
+            Some(found) => found,
+            None => continue,
+        };
+
+        fs::create_dir_all(dir).expect("Failed to create catalog directory");
+        let path = dir.join(format!("{}.rs", feature));
+        if fs::read_to_string(&path).ok().as_deref() != Some(source) {
+            fs::write(&path, source.as_bytes()).expect("Failed to write catalog test");
+        }
+        tests.insert(feature.clone(), path);
+        origins.insert(feature.clone(), format!("{} v{}", catalog.name, version));
+    }
+    origins
+}
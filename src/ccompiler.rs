@@ -0,0 +1,51 @@
+//! Discovery of the C compiler.
+
+use std::path::PathBuf;
+use std::process::Command;
+
+/// A working C compiler found by ConfTest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CCompiler {
+    /// The path or command name of the compiler.
+    pub path: PathBuf,
+    /// The first line of the `--version` output of the compiler.
+    pub version: String,
+}
+
+impl CCompiler {
+    /// Finds the C compiler the same way the 'cc' crate does when conf_test is build with the
+    /// 'cc' feature, otherwise from `$CC` defaulting to 'cc'. Returns `None` when the compiler
+    /// does not work.
+    pub(crate) fn discover() -> Option<CCompiler> {
+        let (path, mut command) = Self::command()?;
+        let output = command.arg("--version").output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+
+        let version = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+
+        Some(CCompiler { path, version })
+    }
+
+    #[cfg(feature = "cc")]
+    fn command() -> Option<(PathBuf, Command)> {
+        let compiler = cc::Build::new()
+            .cargo_metadata(false)
+            .try_get_compiler()
+            .ok()?;
+        Some((compiler.path().to_path_buf(), compiler.to_command()))
+    }
+
+    #[cfg(not(feature = "cc"))]
+    fn command() -> Option<(PathBuf, Command)> {
+        let path = PathBuf::from(std::env::var_os("CC").unwrap_or_else(|| "cc".into()));
+        let command = Command::new(&path);
+        Some((path, command))
+    }
+}
@@ -0,0 +1,73 @@
+//! How discovered features are passed as cfg to rustc.
+
+/// The form of the cfg emitted for a discovered feature, set by
+/// `ConfTestBuilder::cfg_style()`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum CfgStyle {
+    /// `feature="o_path"`, the default. Note that these may collide with real cargo features.
+    #[default]
+    Feature,
+    /// A key/value cfg under the given key, `CfgStyle::Key("conf_test".into())` emits
+    /// `conf_test="o_path"`.
+    Key(String),
+    /// A plain cfg with the given prefix, `CfgStyle::Prefix("conf_".into())` emits `conf_o_path`.
+    /// Characters of the feature name which are not valid in identifiers become `_`, the
+    /// feature 'o-path' becomes `conf_o_path` as well.
+    Prefix(String),
+}
+
+impl CfgStyle {
+    /// The cfg for 'feature' as passed to `rustc --cfg`.
+    pub(crate) fn cfg(&self, feature: &str) -> String {
+        match self {
+            CfgStyle::Feature => format!("feature=\"{}\"", feature),
+            CfgStyle::Key(key) => format!("{}=\"{}\"", key, feature),
+            CfgStyle::Prefix(prefix) => format!("{}{}", prefix, ident(feature)),
+        }
+    }
+
+    /// The cargo instruction enabling 'feature'.
+    pub(crate) fn rustc_cfg(&self, feature: &str) -> String {
+        format!("cargo:rustc-cfg={}\n", self.cfg(feature))
+    }
+
+    /// The cargo instructions declaring the expected cfgs for 'features'. Features are already
+    /// known to cargo and need no declaration.
+    pub(crate) fn rustc_check_cfg<'a>(
+        &self,
+        features: impl IntoIterator<Item = &'a String>,
+    ) -> Vec<String> {
+        match self {
+            CfgStyle::Feature => Vec::new(),
+            CfgStyle::Key(key) => {
+                let values: Vec<String> = features
+                    .into_iter()
+                    .map(|feature| format!("\"{}\"", feature))
+                    .collect();
+                vec![format!(
+                    "cargo:rustc-check-cfg=cfg({}, values({}))\n",
+                    key,
+                    values.join(", ")
+                )]
+            }
+            CfgStyle::Prefix(prefix) => features
+                .into_iter()
+                .map(|feature| format!("cargo:rustc-check-cfg=cfg({}{})\n", prefix, ident(feature)))
+                .collect(),
+        }
+    }
+}
+
+/// 'feature' with all characters which are not valid in identifiers replaced by `_`.
+fn ident(feature: &str) -> String {
+    feature
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,337 @@
+//! Parsing of the `// conf_test: ...` directives embedded in probe sources.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The crate type a probe gets compiled as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrateType {
+    /// Compiled as binary and executed, the default.
+    Bin,
+    /// Compiled as library, success of the compilation alone decides.
+    Lib,
+}
+
+/// The builds a test runs in, from `profile=debug|release` or the package metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Profile {
+    /// Unoptimized builds.
+    Debug,
+    /// Optimized builds.
+    Release,
+}
+
+impl Profile {
+    /// Parses 'debug' or 'release'.
+    pub(crate) fn parse(profile: &str) -> Option<Profile> {
+        Some(match profile {
+            "debug" => Profile::Debug,
+            "release" => Profile::Release,
+            _ => return None,
+        })
+    }
+
+    /// The profile of the crate being built. Builds with an `OPT_LEVEL` other than 0 count as
+    /// release builds, without it `PROFILE` tells.
+    pub(crate) fn current() -> Profile {
+        match std::env::var("OPT_LEVEL") {
+            Ok(level) if level == "0" => Profile::Debug,
+            Ok(_) => Profile::Release,
+            Err(_) if std::env::var("PROFILE").as_deref() == Ok("release") => Profile::Release,
+            Err(_) => Profile::Debug,
+        }
+    }
+
+    /// 'debug' or 'release'.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Profile::Debug => "debug",
+            Profile::Release => "release",
+        }
+    }
+}
+
+/// What an exit code of a test means, from `on-exit=CODE:ACTION` or the package metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitAction {
+    /// The test succeeded, the feature gets enabled. The default for exit code 0.
+    Pass,
+    /// The test failed, the feature is disabled. The default for all other exit codes.
+    Fail,
+    /// The test decided that it does not apply, the feature is disabled.
+    Skip,
+    /// The test could not decide, the feature is disabled as inconclusive.
+    Inconclusive,
+}
+
+impl ExitAction {
+    /// Parses 'pass', 'fail', 'skip' or 'inconclusive'.
+    pub(crate) fn parse(action: &str) -> Option<ExitAction> {
+        Some(match action {
+            "pass" => ExitAction::Pass,
+            "fail" => ExitAction::Fail,
+            "skip" => ExitAction::Skip,
+            "inconclusive" => ExitAction::Inconclusive,
+            _ => return None,
+        })
+    }
+}
+
+/// Directives found in a probe source.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Directives {
+    /// From `crate-type=bin|lib`.
+    pub crate_type: CrateType,
+    /// Exit codes of the test selecting a variant feature, from `exit=CODE:FEATURE`.
+    pub variants: BTreeMap<i32, String>,
+    /// What other exit codes of the test mean, from `on-exit=CODE:ACTION`.
+    pub exit_actions: BTreeMap<i32, ExitAction>,
+    /// Time limit for compiling and for executing the test, from `timeout=SECONDS`.
+    pub timeout: Option<Duration>,
+    /// The minimum Android API level the test needs, from `android-api=LEVEL`.
+    pub android_api: Option<u32>,
+    /// What the test detects, from `describe=TEXT`.
+    pub description: Option<String>,
+    /// Files the test reads, relative to the crate directory, from `fixture=PATH`.
+    pub fixtures: Vec<PathBuf>,
+    /// The only builds the test runs in, from `profile=debug|release`.
+    pub profile: Option<Profile>,
+    /// The group whose setup runs before the test, from `group=NAME`.
+    pub group: Option<String>,
+    /// Features that must be enabled for the test to be run, from `requires=FEATURE`.
+    pub requires: Vec<String>,
+    /// The source has `@NAME@` placeholders to be substituted, from `template`.
+    pub template: bool,
+}
+
+impl Default for Directives {
+    fn default() -> Self {
+        Directives {
+            crate_type: CrateType::Bin,
+            variants: BTreeMap::new(),
+            exit_actions: BTreeMap::new(),
+            timeout: None,
+            android_api: None,
+            description: None,
+            fixtures: Vec::new(),
+            profile: None,
+            group: None,
+            requires: Vec::new(),
+            template: false,
+        }
+    }
+}
+
+impl Directives {
+    /// Parses the directives from the probe source at 'src'. Directives are line comments in
+    /// the form of `// conf_test: key=value`.
+    pub(crate) fn parse(src: &Path) -> Directives {
+        let mut directives = Directives::default();
+
+        let source = match fs::read_to_string(src) {
+            Ok(source) => source,
+            // compilation will fail and report the problem later
+            Err(_) => return directives,
+        };
+
+        for line in source.lines() {
+            let directive = match line.trim().strip_prefix("//") {
+                Some(comment) => match comment.trim().strip_prefix("conf_test:") {
+                    Some(directive) => directive.trim(),
+                    None => continue,
+                },
+                None => continue,
+            };
+
+            let (key, value) = match directive.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => (directive, ""),
+            };
+
+            match key {
+                "crate-type" => {
+                    directives.crate_type = match value {
+                        "bin" => CrateType::Bin,
+                        "lib" => CrateType::Lib,
+                        _ => panic!("Unknown crate-type in {}: {:?}", src.display(), value),
+                    }
+                }
+                "exit" => {
+                    let (code, feature) = value
+                        .split_once(':')
+                        .and_then(|(code, feature)| Some((code.trim().parse().ok()?, feature)))
+                        .unwrap_or_else(|| {
+                            panic!("Invalid exit directive in {}: {:?}", src.display(), value)
+                        });
+                    if directives
+                        .variants
+                        .insert(code, feature.trim().to_string())
+                        .is_some()
+                    {
+                        panic!("Exit code {} mapped twice in {}", code, src.display());
+                    }
+                }
+                "on-exit" => {
+                    let (code, action) = value
+                        .split_once(':')
+                        .and_then(|(code, action)| {
+                            Some((code.trim().parse().ok()?, ExitAction::parse(action.trim())?))
+                        })
+                        .unwrap_or_else(|| {
+                            panic!(
+                                "Invalid on-exit directive in {}: {:?}",
+                                src.display(),
+                                value
+                            )
+                        });
+                    directives.exit_actions.insert(code, action);
+                }
+                "timeout" => {
+                    let seconds = value
+                        .parse()
+                        .ok()
+                        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                        .unwrap_or_else(|| {
+                            panic!("Invalid timeout in {}: {:?}", src.display(), value)
+                        });
+                    directives.timeout = Some(seconds);
+                }
+                "describe" => directives.description = Some(value.to_string()),
+                "fixture" => directives.fixtures.push(PathBuf::from(value)),
+                "profile" => {
+                    directives.profile = Some(Profile::parse(value).unwrap_or_else(|| {
+                        panic!("Invalid profile in {}: {:?}", src.display(), value)
+                    }))
+                }
+                "group" => {
+                    if value.is_empty() || value.contains(['/', '\\', '.']) {
+                        panic!("Invalid group in {}: {:?}", src.display(), value);
+                    }
+                    directives.group = Some(value.to_string());
+                }
+                "requires" => directives.requires.extend(
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|feature| !feature.is_empty())
+                        .map(String::from),
+                ),
+                "template" => directives.template = true,
+                "android-api" => {
+                    let level = value.parse().unwrap_or_else(|_| {
+                        panic!("Invalid android-api in {}: {:?}", src.display(), value)
+                    });
+                    directives.android_api = Some(level);
+                }
+                _ => panic!(
+                    "Unknown conf_test directive in {}: {:?}",
+                    src.display(),
+                    key
+                ),
+            }
+        }
+
+        if let Some(code) = directives
+            .exit_actions
+            .keys()
+            .find(|code| directives.variants.contains_key(code))
+        {
+            panic!("Exit code {} mapped twice in {}", code, src.display());
+        }
+
+        directives
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A source file removed again when dropped, also when parsing it panicked.
+    struct Source(PathBuf);
+
+    impl Drop for Source {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    /// Parses 'source' from a file of its own, named after the test.
+    fn parse(name: &str, source: &str) -> Directives {
+        let path = Source(std::env::temp_dir().join(format!(
+            "conf_test_directives_{}_{}.rs",
+            std::process::id(),
+            name
+        )));
+        fs::write(&path.0, source).expect("Failed to write test source");
+        Directives::parse(&path.0)
+    }
+
+    #[test]
+    fn defaults_without_directives() {
+        let directives = parse("defaults", "// just a comment\nfn main() {}\n");
+        assert_eq!(directives.crate_type, CrateType::Bin);
+        assert!(directives.variants.is_empty());
+        assert!(directives.requires.is_empty());
+        assert!(!directives.template);
+    }
+
+    #[test]
+    fn missing_source() {
+        let directives = Directives::parse(Path::new("/nonexistent/conf_test/probe.rs"));
+        assert_eq!(directives.crate_type, CrateType::Bin);
+    }
+
+    #[test]
+    fn directives() {
+        let directives = parse(
+            "directives",
+            "// conf_test: crate-type=lib\n\
+             //conf_test:timeout=1.5\n\
+             \x20   // conf_test: describe = Linux io_uring \n\
+             // conf_test: requires=a, b,\n\
+             // conf_test: requires=c\n\
+             // conf_test: exit=3:variant\n\
+             // conf_test: on-exit=4:skip\n\
+             // conf_test: profile=release\n\
+             // conf_test: group=net\n\
+             // conf_test: template\n\
+             // conf_test: android-api=26\n\
+             fn main() {}\n",
+        );
+        assert_eq!(directives.crate_type, CrateType::Lib);
+        assert_eq!(directives.timeout, Some(Duration::from_millis(1500)));
+        assert_eq!(directives.description.as_deref(), Some("Linux io_uring"));
+        assert_eq!(directives.requires, ["a", "b", "c"]);
+        assert_eq!(directives.variants[&3], "variant");
+        assert_eq!(directives.exit_actions[&4], ExitAction::Skip);
+        assert_eq!(directives.profile, Some(Profile::Release));
+        assert_eq!(directives.group.as_deref(), Some("net"));
+        assert!(directives.template);
+        assert_eq!(directives.android_api, Some(26));
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown conf_test directive")]
+    fn unknown_directive() {
+        parse("unknown", "// conf_test: crate_type=lib\n");
+    }
+
+    #[test]
+    #[should_panic(expected = "Exit code 3 mapped twice")]
+    fn exit_code_mapped_twice() {
+        parse(
+            "twice",
+            "// conf_test: exit=3:variant\n// conf_test: on-exit=3:fail\n",
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid group")]
+    fn group_with_path() {
+        parse("group", "// conf_test: group=../net\n");
+    }
+}
@@ -0,0 +1,55 @@
+//! Export of the features and their relations as Graphviz DOT graph.
+
+use std::collections::BTreeSet;
+
+use crate::report::{Report, Status};
+
+/// Renders 'report' as DOT graph. Features are colored by their outcome, tests that are not
+/// features themselves (variant selectors) are drawn as boxes.
+pub(crate) fn render(report: &Report) -> String {
+    let mut dot = String::from("digraph conf_test {\n    node [style=filled];\n");
+
+    for (feature, status) in &report.features {
+        let color = match status {
+            Status::Manual => "lightblue",
+            Status::Enabled | Status::Assumed(true) => "palegreen",
+            Status::CompileFailed | Status::RunFailed => "salmon",
+            Status::Inconclusive | Status::Sandboxed => "khaki",
+            Status::Untested
+            | Status::Unselected
+            | Status::Assumed(false)
+            | Status::Unsupported
+            | Status::Skipped
+            | Status::OtherProfile
+            | Status::RequirementUnmet => "lightgray",
+        };
+        let tooltip = match report.descriptions.get(feature) {
+            Some(description) => format!("{}: {}", description, status.description()),
+            None => status.description().to_string(),
+        };
+        dot.push_str(&format!(
+            "    {:?} [fillcolor={}, tooltip={:?}];\n",
+            feature, color, tooltip
+        ));
+    }
+
+    let tests: BTreeSet<&String> = report
+        .edges
+        .iter()
+        .map(|(from, _, _)| from)
+        .filter(|from| !report.features.contains_key(*from))
+        .collect();
+    for test in tests {
+        dot.push_str(&format!("    {:?} [shape=box, fillcolor=white];\n", test));
+    }
+
+    for (from, to, label) in &report.edges {
+        dot.push_str(&format!(
+            "    {:?} -> {:?} [label={:?}];\n",
+            from, to, label
+        ));
+    }
+
+    dot.push_str("}\n");
+    dot
+}
@@ -0,0 +1,31 @@
+//! Machine-parsable progress events in the commentary.
+
+/// Marker and version of the event schema. The version is bumped whenever existing fields
+/// change their meaning, new fields may be added at any time.
+pub(crate) const SCHEMA: &str = "conf_test/1";
+
+/// Formats an event as comment line `# conf_test/1 {"event":"...",...}`.
+pub(crate) fn line(event: &str, fields: &[(&str, &str)]) -> String {
+    let mut json = format!("{{\"event\":{}", json_string(event));
+    for (key, value) in fields {
+        json.push_str(&format!(",{}:{}", json_string(key), json_string(value)));
+    }
+    format!("# {} {}}}\n", SCHEMA, json)
+}
+
+/// Quotes 'text' as JSON string.
+pub(crate) fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
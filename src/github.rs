@@ -0,0 +1,49 @@
+//! Writing the results to the step outputs and the job summary of GitHub Actions.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::markdown;
+use crate::report::Report;
+
+/// Appends the results of 'package' to the files named by `GITHUB_OUTPUT` and
+/// `GITHUB_STEP_SUMMARY`, when set. Returns the files written to.
+pub(crate) fn write(report: &Report, package: &str) -> Vec<PathBuf> {
+    let mut written = Vec::new();
+
+    if let Some(path) = std::env::var_os("GITHUB_OUTPUT").map(PathBuf::from) {
+        let mut outputs = format!(
+            "conf_test_enabled={}\n",
+            report.enabled().collect::<Vec<_>>().join(",")
+        );
+        for (feature, status) in &report.features {
+            outputs.push_str(&format!("conf_test_{}={}\n", feature, status.is_enabled()));
+        }
+        append(&path, &outputs);
+        written.push(path);
+    }
+
+    if let Some(path) = std::env::var_os("GITHUB_STEP_SUMMARY").map(PathBuf::from) {
+        append(
+            &path,
+            &format!(
+                "### ConfTest results for {}\n\n{}\n",
+                package,
+                markdown::render(report)
+            ),
+        );
+        written.push(path);
+    }
+
+    written
+}
+
+fn append(path: &Path, text: &str) {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(text.as_bytes()))
+        .unwrap_or_else(|err| panic!("Failed to write to {}: {}", path.display(), err));
+}
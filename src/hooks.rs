@@ -0,0 +1,85 @@
+//! Callbacks around the tests of a run, set with `ConfTestBuilder::on_probe_start()` and
+//! friends.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::Outcome;
+
+/// A test about to be run or that ran, passed to the hooks.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ProbeContext<'a> {
+    /// The feature the test decides.
+    pub feature: &'a str,
+    /// The source of the test.
+    pub source: &'a Path,
+    /// The features enabled before, the test is compiled with their cfgs.
+    pub enabled: &'a [String],
+}
+
+/// The outcome of a test, passed to the `on_probe_end` hooks.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ProbeOutcome<'a> {
+    /// The feature got enabled.
+    pub enabled: bool,
+    /// Whether the feature is enabled, disabled or could not be decided.
+    pub outcome: Outcome,
+    /// Human readable description of what happened, 'test failed to compile' for example.
+    pub status: &'static str,
+    /// The result came from the cache, the test was not compiled in this run.
+    pub cached: bool,
+    pub compile_time: Option<Duration>,
+    pub run_time: Option<Duration>,
+    /// The exit code when the test was executed.
+    pub exit_code: Option<i32>,
+    /// The stderr of rustc.
+    pub diagnostics: &'a str,
+}
+
+type StartHook = dyn Fn(&ProbeContext) -> Option<bool> + Send + Sync;
+type EndHook = dyn Fn(&ProbeContext, &ProbeOutcome) + Send + Sync;
+type FinishHook = dyn Fn(&BTreeMap<String, bool>) + Send + Sync;
+
+/// The hooks registered with the builder.
+#[derive(Clone, Default)]
+pub(crate) struct Hooks {
+    pub(crate) probe_start: Vec<Arc<StartHook>>,
+    pub(crate) probe_end: Vec<Arc<EndHook>>,
+    pub(crate) finish: Vec<Arc<FinishHook>>,
+}
+
+impl Hooks {
+    /// Calls the `on_probe_start` hooks until one of them decides the feature.
+    pub(crate) fn probe_start(&self, probe: &ProbeContext) -> Option<bool> {
+        self.probe_start.iter().find_map(|hook| hook(probe))
+    }
+
+    /// Calls the `on_probe_end` hooks.
+    pub(crate) fn probe_end(&self, probe: &ProbeContext, outcome: &ProbeOutcome) {
+        for hook in &self.probe_end {
+            hook(probe, outcome);
+        }
+    }
+
+    /// Calls the `on_finish` hooks.
+    pub(crate) fn finish(&self, features: &BTreeMap<String, bool>) {
+        for hook in &self.finish {
+            hook(features);
+        }
+    }
+}
+
+impl fmt::Debug for Hooks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Hooks")
+            .field("probe_start", &self.probe_start.len())
+            .field("probe_end", &self.probe_end.len())
+            .field("finish", &self.finish.len())
+            .finish()
+    }
+}
@@ -0,0 +1,101 @@
+//! Rendering of the report as standalone HTML page.
+
+use crate::report::Report;
+
+/// Renders 'report' as HTML page with a results table, timings and the rustc diagnostics of
+/// each test. The page is stamped with 'timestamp' when given, the timings are left out
+/// unless 'timings' is set.
+pub(crate) fn render(report: &Report, timestamp: Option<&str>, timings: bool) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html>\n\
+         <html>\n\
+         <head>\n\
+         <meta charset=\"utf-8\">\n\
+         <title>ConfTest report</title>\n\
+         <style>\n\
+         body { font-family: sans-serif; }\n\
+         table { border-collapse: collapse; }\n\
+         td, th { border: 1px solid #ccc; padding: 0.2em 0.5em; text-align: left; vertical-align: top; }\n\
+         .enabled { background: #dfd; }\n\
+         .disabled { background: #fdd; }\n\
+         </style>\n\
+         </head>\n\
+         <body>\n\
+         <h1>ConfTest report</h1>\n",
+    );
+
+    match timestamp {
+        Some(timestamp) => html.push_str(&format!("<p>Generated at {}", escape(timestamp))),
+        None => html.push_str("<p>Generated by ConfTest"),
+    }
+    if let Some(c_compiler) = &report.c_compiler {
+        html.push_str(&format!(
+            ", C compiler: {} ({})",
+            escape(&c_compiler.path.display().to_string()),
+            escape(&c_compiler.version)
+        ));
+    }
+    if let Some(seed) = report.seed {
+        html.push_str(&format!(", seed: {}", seed));
+    }
+    html.push_str("</p>\n");
+
+    html.push_str(
+        "<table>\n<tr><th>Feature</th><th>Status</th><th>Compile</th><th>Run</th>\
+         <th>Diagnostics</th></tr>\n",
+    );
+    for (feature, status) in &report.features {
+        let details = report.details.get(feature);
+        let cached = details.is_some_and(|details| details.cached);
+        let seconds = |duration: Option<std::time::Duration>| match duration {
+            _ if cached => String::from("cached"),
+            _ if !timings => String::new(),
+            Some(duration) => format!("{:.3}s", duration.as_secs_f64()),
+            None => String::new(),
+        };
+        let diagnostics = match details.map(|details| details.diagnostics.trim()) {
+            Some(diagnostics) if !diagnostics.is_empty() => format!(
+                "<details><summary>rustc diagnostics</summary><pre>{}</pre></details>",
+                escape(diagnostics)
+            ),
+            _ => String::new(),
+        };
+        let feature = match report.descriptions.get(feature) {
+            Some(description) => format!(
+                "{}<br><small>{}</small>",
+                escape(feature),
+                escape(description)
+            ),
+            None => escape(feature),
+        };
+        html.push_str(&format!(
+            "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            if status.is_enabled() {
+                "enabled"
+            } else {
+                "disabled"
+            },
+            feature,
+            status.description(),
+            seconds(details.and_then(|details| details.compile_time)),
+            seconds(details.and_then(|details| details.run_time)),
+            diagnostics
+        ));
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,199 @@
+//! Parsing of the `conf_test:` instructions tests print on stdout.
+
+use std::path::PathBuf;
+
+/// The stdout of a successful test, split into the part forwarded to cargo and the
+/// instructions for ConfTest.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct TestOutput {
+    /// Forwarded to cargo.
+    pub(crate) cargo: String,
+    /// Include directories for C compilation, from `conf_test:include=PATH`.
+    pub(crate) include_dirs: Vec<PathBuf>,
+    /// Environment variables for the crate, from `conf_test:set-env=KEY=VALUE`.
+    pub(crate) env: Vec<(String, String)>,
+    /// Environment variables for the following tests, from `conf_test:export=KEY=VALUE`.
+    pub(crate) exports: Vec<(String, String)>,
+    /// Link arguments as (directive, argument), from `cargo:rustc-link-arg*=ARG` and
+    /// `cargo:rustc-cdylib-link-arg=ARG`.
+    pub(crate) link_args: Vec<(&'static str, String)>,
+}
+
+impl TestOutput {
+    /// Splits the stdout of a test, lines starting with `conf_test:` are instructions for
+    /// ConfTest and link arguments are collected, everything else goes to cargo.
+    pub(crate) fn parse(stdout: &str) -> TestOutput {
+        let mut output = TestOutput::default();
+
+        for line in stdout.lines() {
+            let instruction = match line.strip_prefix("conf_test:") {
+                Some(instruction) => instruction,
+                None => {
+                    match link_arg(line) {
+                        Some(link_arg) => output.link_args.push(link_arg),
+                        None => {
+                            output.cargo.push_str(line);
+                            output.cargo.push('\n');
+                        }
+                    }
+                    continue;
+                }
+            };
+
+            match instruction.split_once('=') {
+                Some(("include", path)) => output.include_dirs.push(PathBuf::from(path)),
+                Some(("set-env", assignment)) => match assignment.split_once('=') {
+                    Some((key, value)) if is_env_key(key) => {
+                        output.env.push((key.to_string(), value.to_string()))
+                    }
+                    _ => panic!("Invalid conf_test set-env: {:?}", line),
+                },
+                Some(("export", assignment)) => match assignment.split_once('=') {
+                    Some((key, value)) if is_env_key(key) => {
+                        output.exports.push((key.to_string(), value.to_string()))
+                    }
+                    _ => panic!("Invalid conf_test export: {:?}", line),
+                },
+                // Bail on any unknown instruction to catch typos
+                _ => panic!("Unknown conf_test instruction: {:?}", line),
+            }
+        }
+
+        output
+    }
+}
+
+/// Parses a link argument directive into the directive and its argument. The
+/// `rustc-link-arg-cdylib` alias becomes `rustc-cdylib-link-arg`. Panics on invalid ones.
+fn link_arg(line: &str) -> Option<(&'static str, String)> {
+    let (key, arg) = line
+        .strip_prefix("cargo::")
+        .or_else(|| line.strip_prefix("cargo:"))?
+        .split_once('=')?;
+    let directive = match key {
+        "rustc-link-arg" => "rustc-link-arg",
+        "rustc-link-arg-bin" => "rustc-link-arg-bin",
+        "rustc-link-arg-bins" => "rustc-link-arg-bins",
+        "rustc-link-arg-tests" => "rustc-link-arg-tests",
+        "rustc-link-arg-examples" => "rustc-link-arg-examples",
+        "rustc-link-arg-benches" => "rustc-link-arg-benches",
+        "rustc-cdylib-link-arg" | "rustc-link-arg-cdylib" => "rustc-cdylib-link-arg",
+        _ => return None,
+    };
+    // rustc-link-arg-bin takes 'BIN=ARG'
+    let valid = match arg.split_once('=') {
+        _ if arg.trim().is_empty() => false,
+        Some((bin, arg)) if directive == "rustc-link-arg-bin" => {
+            !bin.is_empty() && !arg.trim().is_empty()
+        }
+        None if directive == "rustc-link-arg-bin" => false,
+        _ => true,
+    };
+    if !valid {
+        panic!("Invalid link argument from a test: {:?}", line);
+    }
+    Some((directive, arg.to_string()))
+}
+
+/// Decodes the stdout of a test for forwarding, invalid UTF-8 is replaced and terminal escape
+/// sequences and control characters other than newlines and tabs are removed.
+pub(crate) fn sanitize(stdout: &[u8]) -> String {
+    let mut sanitized = String::with_capacity(stdout.len());
+    let decoded = String::from_utf8_lossy(stdout);
+    let mut chars = decoded.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            // CSI sequences like colors end at the first character in '@'..='~'
+            '\u{1b}' if chars.peek() == Some(&'[') => {
+                for c in chars.by_ref().skip(1) {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            '\n' | '\t' => sanitized.push(c),
+            c if c.is_control() => {}
+            c => sanitized.push(c),
+        }
+    }
+    sanitized
+}
+
+/// Returns true when 'key' is a valid environment variable name.
+fn is_env_key(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_keeps_text() {
+        assert_eq!(sanitize(b"line 1\n\tline 2\n"), "line 1\n\tline 2\n");
+        assert_eq!(sanitize("äöü €".as_bytes()), "äöü €");
+    }
+
+    #[test]
+    fn sanitize_strips_escapes() {
+        assert_eq!(sanitize(b"\x1b[1;31mred\x1b[0m\n"), "red\n");
+        assert_eq!(sanitize(b"a\x1b[2Kb"), "ab");
+        // a lone escape is dropped as control character, the rest stays
+        assert_eq!(sanitize(b"a\x1b]0;title\x07b"), "a]0;titleb");
+    }
+
+    #[test]
+    fn sanitize_strips_control_characters() {
+        assert_eq!(sanitize(b"a\rb\x00c\x08d\n"), "abcd\n");
+        assert_eq!(sanitize(b"bad \xff utf-8"), "bad \u{fffd} utf-8");
+    }
+
+    #[test]
+    fn instructions() {
+        let output = TestOutput::parse(
+            "cargo:rustc-cfg=foo\n\
+             conf_test:include=/opt/foo/include\n\
+             conf_test:set-env=FOO_VERSION=1.2=3\n\
+             conf_test:export=FOO_PREFIX=/opt/foo\n\
+             cargo::rustc-link-arg-cdylib=-Wl,-z,now\n\
+             cargo:rustc-link-arg-bin=tool=-static\n",
+        );
+        assert_eq!(output.cargo, "cargo:rustc-cfg=foo\n");
+        assert_eq!(output.include_dirs, [PathBuf::from("/opt/foo/include")]);
+        assert_eq!(
+            output.env,
+            [("FOO_VERSION".to_string(), "1.2=3".to_string())]
+        );
+        assert_eq!(
+            output.exports,
+            [("FOO_PREFIX".to_string(), "/opt/foo".to_string())]
+        );
+        assert_eq!(
+            output.link_args,
+            [
+                ("rustc-cdylib-link-arg", "-Wl,-z,now".to_string()),
+                ("rustc-link-arg-bin", "tool=-static".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown conf_test instruction")]
+    fn unknown_instruction() {
+        TestOutput::parse("conf_test:includes=/opt\n");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid conf_test set-env")]
+    fn invalid_env_key() {
+        TestOutput::parse("conf_test:set-env=1FOO=bar\n");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid link argument")]
+    fn link_arg_bin_without_bin() {
+        TestOutput::parse("cargo:rustc-link-arg-bin=-static\n");
+    }
+}
@@ -9,6 +9,99 @@
 //! 'conf_tests/' exists. This test is then compiled and build. When that succeeds the
 //! feature becomes enabled automatically.
 //!
+//! Finally a summary is printed which lists the features enabled manually, enabled by a test,
+//! disabled by a failed test and those which are not tested at all. When features that were
+//! enabled by their test in the previous run are disabled by their test now, a warning is
+//! emitted. This typically signals a regression of the toolchain or build environment.
+//!
+//! A test that could not be run at all, because rustc crashed with an internal compiler
+//! error, was missing or a process failed to spawn, tells nothing about the platform. Its
+//! feature is disabled as 'inconclusive' with a warning instead of counting as a failed test.
+//! `ConfTestBuilder::fail_on_inconclusive(true)` makes the build fail then.
+//!
+//! Containerized CI often runs builds in a sandbox. A test that is not permitted to run
+//! (`EPERM` or `EACCES` when spawning it), gets killed by a seccomp filter (`SIGSYS`) or
+//! panics with such a permission error does not prove the platform lacks its feature either.
+//! Its feature is disabled as 'sandboxed', reported as indeterminate due to sandbox.
+//! `ConfTestBuilder::warn_sandboxed(true)` adds a warning listing these features.
+//!
+//! Crate authors can run the tests in strict mode in their own CI with `ConfTest::check_all()`,
+//! `ConfTestBuilder::strict(true)` or by setting `CONF_TEST_STRICT`. Every test in
+//! 'conf_tests/' must compile then, the build fails listing the ones that did not or could
+//! not be run and their rustc errors are shown as warnings. This catches tests that rotted
+//! before users silently lose features. Catalog tests are exempt, they fail to compile where
+//! the platform lacks what they detect. Tests for platform specific APIs are best checked
+//! strictly only on the platforms they target.
+//!
+//! All output is logged to 'OUT_DIR/conf_test/conf_test.log' together with timestamps and the
+//! durations of the phases (reading the manifest, resolving the dependencies, compiling
+//! and executing each test) to diagnose a slow configure step. This file is overwritten by
+//! every run. With `ConfTestBuilder::log_history()` the logs of the most recent runs are
+//! additionally kept in 'OUT_DIR/conf_test/logs/', so the log of a failed CI run isn't
+//! clobbered by a retry.
+//!
+//! Cargo hides the output of successful build scripts. Setting `CONF_TEST_VERBOSE` in the
+//! environment makes the first lines of the rustc errors of tests which failed to compile
+//! show up as warnings in the `cargo build` output, 10 lines by default or as many as the
+//! variable is set to. `ConfTestBuilder::verbose()` does the same from the build script.
+//!
+//! Besides the cargo directives, the build script prints '#' comments telling what it does.
+//! Build pipelines which parse the output of build scripts strictly may choke on these, with
+//! `ConfTestBuilder::directives_only(true)` the comments only go to the log. Tools which
+//! want to follow the progress instead can enable `ConfTestBuilder::progress_events(true)`,
+//! which adds comments in a versioned schema, one JSON object per line:
+//!
+//! ```text
+//! # conf_test/1 {"event":"start"}
+//! # conf_test/1 {"event":"probe","feature":"o_path","status":"enabled","outcome":"enabled"}
+//! # conf_test/1 {"event":"finish","enabled":"o_path"}
+//! ```
+//!
+//! The status is one of 'manual', 'enabled', 'compile_failed', 'run_failed', 'untested',
+//! 'unselected', 'assumed_enabled', 'assumed_disabled', 'unsupported', 'skipped',
+//! 'other_profile', 'requirement_unmet', 'inconclusive' or 'sandboxed'. The outcome
+//! condenses it to 'enabled', 'disabled' or 'indeterminate', the latter when the test could
+//! not decide. The same `Outcome` is passed to hooks and report sinks.
+//!
+//! With `ConfTestBuilder::html_report(true)` the results are also rendered as standalone HTML
+//! page to 'OUT_DIR/conf_test/report.html', with the timings and rustc diagnostics of each
+//! test. Tests whose result came from the cache are marked as 'cached' there. This is
+//! friendlier than the log when published as CI artifact.
+//!
+//! CI jobs that don't know cargo's hashed output directories can set `CONF_TEST_REPORT` to a
+//! path (or use `ConfTestBuilder::json_report()`) where the results get written as JSON
+//! document, one record per feature as delivered to [report sinks](#hooks):
+//!
+//! ```text
+//! {"schema":"conf_test/1","features":[{"feature":"o_path","status":"enabled",...}]}
+//! ```
+//!
+//! In GitHub Actions `ConfTestBuilder::github_actions(true)` appends the results to the
+//! step outputs, `conf_test_enabled` lists the enabled features and `conf_test_FEATURE` is
+//! 'true' or 'false' for each feature, and adds their table to the job summary. Later steps
+//! can branch on them without parsing logs:
+//!
+//! ```yaml
+//! - id: build
+//!   run: cargo build
+//! - if: steps.build.outputs.conf_test_io_uring == 'true'
+//!   run: cargo test --test uring
+//! ```
+//!
+//! The names of these files change with every step, so the build script runs again in each
+//! step building the crate. Combine it with the [cache](#caching) to keep this cheap.
+//!
+//! `ConfTestBuilder::dot_graph(true)` exports the features and the relations between tests
+//! and features in Graphviz DOT format to 'OUT_DIR/conf_test/features.dot'. Features are
+//! colored by their outcome, 'compiled with' edges show which features were already
+//! configured when a test was compiled and 'requires' edges the features a test
+//! [requires](#directives). Render it with `dot -Tsvg features.dot > features.svg`.
+//!
+//! The files generated from the results are recorded in 'OUT_DIR/conf_test/report.toml'.
+//! When a later run does not write one of them again, because the report got disabled or
+//! the JSON report moved, it is removed. Otherwise it would still claim the results of the
+//! earlier run, features that were enabled back then included.
+//!
 //! ## Special case for 'docs.rs'
 //!
 //! When a packages is build for documentation on 'docs.rs' then conf_test detects and checks
@@ -61,6 +154,16 @@
 //! o_path = []
 //! ```
 //!
+//! A test that fails to compile because it uses a crate which is not a build-dependency
+//! fails the build with an error telling which crate to add, instead of silently disabling
+//! its feature. The remaining tests still run and the log and reports are written first.
+//! This covers crates named by `extern crate` and dependencies of the package used by path.
+//!
+//! In a workspace only the features, edition and build-dependencies of the package being
+//! built are used, found by its 'Cargo.toml' in `CARGO_MANIFEST_DIR` or else by
+//! `CARGO_PKG_NAME`. Members of virtual workspaces work the same, the 'Cargo.lock' at the
+//! workspace root is used.
+//!
 //! And as final step the crate directory 'conf_tests/' need to be created which contain rust
 //! files named after the features to be probed. Containing a single `fn main()` which shall
 //! probe one single thing.
@@ -81,16 +184,123 @@
 //! Later in the crate implementation source code one uses conditional compilation as usual
 //! with `#[cfg(feature = "o_path")]`.
 //!
+//! Large sets of tests can be organized in category subdirectories. The directory names
+//! become a prefix of the feature name, 'conf_tests/net/so_reuseport.rs' probes the feature
+//! 'net_so_reuseport'. The separator used to join the names can be changed with
+//! `ConfTestBuilder::category_separator()`.
+//!
+//! A missing 'conf_tests/' directory is only logged by default. Crates that rely on their
+//! tests can make it a warning or an error with
+//! `ConfTestBuilder::missing_tests_policy(MissingTestsPolicy::Error)`, catching a directory
+//! that was left out of the published package. Crates that only use
+//! [catalog tests](#catalog-tests) disable looking for it at all with
+//! `ConfTestBuilder::filesystem_probes(false)`.
+//!
 //! ## Test depending on other Features
 //!
-//! Tests may depend on features that are discovered by other tests or set manually. For
-//! simplicity there is no dependency resolver about this but tests are run in sort order of
-//! the feature name. Every subsequent test is compiled with the the feature flags already
-//! discovered so far. To leverage this functionality one rarely needs to change the feature
-//! names. For example when 'bar' depends on 'foo' it is required to enforce the sort order by
-//! renaming these features to 'aa_foo' and 'bb_bar'. Only features that get discovered are
-//! used for the test compilations features set by printing cargo instructions from the test
-//! scripts are not used.
+//! Tests may depend on features that are discovered by other tests. For simplicity there is
+//! no dependency resolver about this but tests are run in sort order of the feature name.
+//! Every subsequent test is compiled with the the feature flags already discovered so far.
+//! To leverage this functionality one rarely needs to change the feature names. For example
+//! when 'bar' depends on 'foo' it is required to enforce the sort order by renaming these
+//! features to 'aa_foo' and 'bb_bar'. Only features that get enabled by their
+//! test or by an assumption are used for the test compilations, features whose test failed
+//! and features set by printing cargo instructions from the test scripts are not used.
+//! Features enabled manually with `--features` are used as well when
+//! `ConfTestBuilder::manual_features_in_tests(true)` is set.
+//!
+//! Tools and tests can list the tests with `ConfTest::discover()` without compiling anything.
+//!
+//! Tests that don't map to a feature, for example measuring a value for an environment
+//! variable, can be run from anywhere with `ConfTest::run_probe()` which returns the result to
+//! 'build.rs' instead of emitting anything to cargo.
+//!
+//! ## Using the results in 'build.rs'
+//!
+//! Later steps in 'build.rs' (code generation, building C sources) can query the results of
+//! the run without parsing the log file:
+//!
+//! ```rust,ignore
+//! fn main() {
+//!     conf_test::ConfTest::run();
+//!
+//!     if conf_test::ConfTest::is_enabled("o_path") {
+//!         // ...
+//!     }
+//! }
+//! ```
+//!
+//! Values the tests captured with `conf_test:set-env` or `conf_test:export` can be queried
+//! typed with `ConfTest::value_bool()` and `ConfTest::value_int()`, or as string with
+//! `ConfTest::value()`, to drive code generation from measurements. Hexadecimal integers
+//! like `OPENSSL_VERSION_NUMBER=0x30000000` are understood.
+//!
+//! `ConfTest::run_report()` runs the tests like `ConfTest::run()` and returns a `Report`
+//! telling for each feature what happened: which features got enabled, whose tests failed to
+//! compile or failed when executed, which were skipped and which were set manually or
+//! assumed. The build script can emit further cfgs or abort based on it:
+//!
+//! ```rust,ignore
+//! fn main() {
+//!     let report = conf_test::ConfTest::run_report();
+//!     if !report.is_enabled("o_path") && report.overridden().is_empty() {
+//!         panic!("O_PATH is required: {:?}", report.status("o_path"));
+//!     }
+//! }
+//! ```
+//!
+//! Build scripts which need the features and dependencies of the package for other purposes
+//! can use `ConfTest::manifest()` instead of querying cargo metadata themselves. ConfTest
+//! parses 'Cargo.toml' directly and only runs `cargo metadata` for manifests which inherit
+//! their edition from the workspace, saving a subprocess on every run.
+//!
+//! ## Running outside of 'build.rs'
+//!
+//! Developer tooling like an xtask can run the tests for a crate up front, before attempting
+//! a full build. Without cargo's build script environment the crate and output directories
+//! must be given explicitly. `ConfTestBuilder::check()` returns the results instead of
+//! printing cargo directives:
+//!
+//! ```rust,ignore
+//! let features = conf_test::ConfTest::builder()
+//!     .manifest_dir("crates/netio")
+//!     .out_dir("target/conf_test")
+//!     .check();
+//! for (feature, enabled) in features {
+//!     println!("{}: {}", feature, enabled);
+//! }
+//! ```
+//!
+//! `ConfTestBuilder::outcomes()` tells apart the features which were checked to be absent
+//! from those whose test could not decide.
+//!
+//! `ConfTest::print_matrix()` does all of this for a `cargo xtask check-platform` command,
+//! printing a table of the features, whether they are supported and why.
+//!
+//! ## Multiple instances
+//!
+//! A build script can run several differently configured instances of ConfTest, for
+//! example one probing the OS from 'conf_tests/os/' emitting prefixed cfgs and another one
+//! probing the compiler emitting features:
+//!
+//! ```rust,ignore
+//! fn main() {
+//!     conf_test::ConfTest::builder()
+//!         .instance("os")
+//!         .tests_dir("conf_tests/os")
+//!         .cfg_style(conf_test::CfgStyle::Prefix("os_".into()))
+//!         .run();
+//!     conf_test::ConfTest::builder()
+//!         .instance("compiler")
+//!         .tests_dir("conf_tests/compiler")
+//!         .run();
+//! }
+//! ```
+//!
+//! Each instance keeps its log, cache and reports in 'OUT_DIR/conf_test/NAME/'. The results
+//! of the instances are merged for `ConfTest::enabled()` and the other queries about the
+//! last run. A feature not tested by an instance takes the result of the one that tested
+//! it, the build fails when two instances come to different results for the same feature.
 //!
 //!
 //! # Detailed Control
@@ -99,6 +309,259 @@
 //! These become only effective when the test exits successful.
 //! See https://doc.rust-lang.org/cargo/reference/build-scripts.html#outputs-of-the-build-script
 //!
+//! Invalid UTF-8 in the output is replaced, terminal escape sequences and control
+//! characters are removed before it is forwarded. A test may print at most 1 MiB, one that
+//! prints more is killed and fails the build. `ConfTestBuilder::max_output()` changes the
+//! limit.
+//!
+//! Directives may be written in either the old `cargo:KEY=VALUE` or the new
+//! `cargo::KEY=VALUE` form. ConfTest emits all directives, including the ones forwarded from
+//! tests, in the form the cargo running the build understands. The new form is used with
+//! cargo 1.77 and later unless the package declares an older 'rust-version'. The version of
+//! cargo is queried once per run and recorded in the log.
+//!
+//! Link arguments are validated and collected from all enabled features, each one is passed
+//! to cargo once. Besides `rustc-link-arg` this works for the directives adjusting the link
+//! flags of specific artifact kinds: `rustc-link-arg-bins`, `rustc-link-arg-bin=BIN=FLAG`,
+//! `rustc-link-arg-tests`, `rustc-link-arg-examples`, `rustc-link-arg-benches` and
+//! `rustc-cdylib-link-arg` (or its alias `rustc-link-arg-cdylib`). Other directives are
+//! forwarded as printed.
+//!
+//! Lines starting with `conf_test:` are instructions for ConfTest itself and are not
+//! forwarded to cargo. Unknown instructions make the script panic. Currently supported are:
+//! * **conf_test:include=PATH**
+//!   Records an include directory for C compilations configured by
+//!   `ConfTest::apply_to_cc()`.
+//! * **conf_test:set-env=KEY=VALUE**
+//!   Sets an environment variable for compiling the crate, values measured by a test become
+//!   accessible with `env!("KEY")`. Unlike printing `cargo:rustc-env` directly, the key is
+//!   validated and each variable is emitted only once. When tests set a variable to
+//!   different values a warning is emitted and the first test in sort order wins.
+//! * **conf_test:export=KEY=VALUE**
+//!   Publishes a value to the tests that run after this one, they get it as environment
+//!   variable when compiled and executed. This allows multi-stage detection, for example a
+//!   first test finds the prefix of an SDK and later tests probe features inside it.
+//!
+//! Tests run with environment variables describing their context, so a single generic test
+//! can adapt its behavior:
+//! * **CONF_TEST_FEATURE**
+//!   The feature being probed.
+//! * **CONF_TEST_FEATURES**
+//!   Comma separated list of the features the test was compiled with.
+//! * **CONF_TEST_TARGET**
+//!   The target triple of the build.
+//! * **CONF_TEST_OUT_DIR**
+//!   The directory where ConfTest keeps its files, 'OUT_DIR/conf_test' or the directory of
+//!   the [instance](#multiple-instances).
+//! * **CONF_TEST_SEED**
+//!   A seed for tests using randomized inputs, for example stress tests detecting racy
+//!   platform behavior. All tests of a run get the same seed, it is logged and recorded in
+//!   the reports. Setting `CONF_TEST_SEED` for the build repeats a run with the given seed,
+//!   otherwise each run gets a fresh one (0 in [reproducible](#reproducible-output) runs).
+//! * **CONF_TEST_GROUP_DIR**
+//!   The directory with the artifacts of the setup of the [group](#directives) of the test.
+//! * **CONF_TEST_CONTEXT**
+//!   The path of a TOML file with the whole context of the test, for tests needing more
+//!   than the variables above. Besides `feature`, `features`, `target`, `profile` and `seed`
+//!   (as string) it has the `os`, `arch` and whether the build is `cross` compiling, the
+//!   `android_api` level, the `c_compiler` and the `group_dir` when known and a `values`
+//!   table with the [local configuration](#local-configuration) and the values exported by
+//!   the tests before:
+//!
+//! ```toml
+//! feature = "io_uring_sqpoll"
+//! features = ["io_uring"]
+//! target = "x86_64-unknown-linux-gnu"
+//! os = "linux"
+//! arch = "x86_64"
+//! cross = false
+//! profile = "debug"
+//! seed = "8123947512"
+//! c_compiler = "cc"
+//!
+//! [values]
+//! IO_URING_VERSION = "2.5"
+//! ```
+//!
+//! * **CONF_TEST_WORK_DIR**
+//!   The directory the test runs in. Each run of a test gets a fresh one,
+//!   'OUT_DIR/conf_test/work/FEATURE.N', so tests creating files can't interfere with each
+//!   other. It is removed after the test unless `ConfTestBuilder::keep_work_dirs(true)` is
+//!   set.
+//!
+//! ## C compilation
+//!
+//! With the 'cc' feature of conf_test, `ConfTest::apply_to_cc()` configures a `cc::Build`
+//! from the same test run. Every enabled feature becomes a `HAVE_<FEATURE>=1` define and the
+//! include directories of enabled features are added.
+//!
+//! Before running the tests ConfTest looks for a working C compiler, the same way the 'cc'
+//! crate does when the 'cc' feature is enabled, otherwise from `$CC`. The compiler and its
+//! version are recorded in the log and tests get its path in the `CONF_TEST_CC` environment
+//! variable. Later 'build.rs' steps can query it with `ConfTest::c_compiler()`.
+//!
+//! For bindgen `ConfTest::clang_args()` returns the same defines and include directories as
+//! clang arguments.
+//!
+//! ## Directives
+//!
+//! Tests can carry directives for ConfTest in line comments of the form
+//! `// conf_test: key=value`. Unknown directives make the script panic. Currently supported
+//! are:
+//! * **crate-type=bin|lib**
+//!   By default tests are compiled as binary and executed. With `crate-type=lib` the test is
+//!   compiled as library and not executed, no `fn main()` is needed then. This is useful for
+//!   pure compile checks (trait impls, type layouts, nightly features). A successful
+//!   compilation enables the feature.
+//!
+//! ```rust,ignore
+//! // This goes into conf_tests/nightly_never_type.rs
+//! // conf_test: crate-type=lib
+//! #![feature(never_type)]
+//!
+//! pub fn never() -> ! {
+//!     loop {}
+//! }
+//! ```
+//!
+//! * **exit=CODE:FEATURE**
+//!   Maps an exit code of the test to a variant feature. A single runtime detection test can
+//!   select among several variant features this way instead of needing one test per variant.
+//!   Tests with exit directives run once and enable only the feature mapped to their exit
+//!   code, the other variants are disabled. Exit codes that are not mapped enable nothing. The
+//!   variant features need no tests of their own.
+//!
+//! ```rust,ignore
+//! // This goes into conf_tests/simd.rs, 'simd' itself needs not to be a feature
+//! // conf_test: exit=0:simd_avx2
+//! // conf_test: exit=10:simd_sse42
+//! fn main() {
+//!     if is_x86_feature_detected!("avx2") {
+//!         std::process::exit(0);
+//!     } else if is_x86_feature_detected!("sse4.2") {
+//!         std::process::exit(10);
+//!     }
+//!     std::process::exit(1);
+//! }
+//! ```
+//!
+//! * **on-exit=CODE:ACTION**
+//!   Tells what an exit code of the test means, one of 'pass', 'fail', 'skip' or
+//!   'inconclusive'. Without it exit code 0 passes and all others fail. Skipping disables the
+//!   feature as not applicable, inconclusive results are described above. This lets tests
+//!   written for other conventions be used as they are, autoconf tests exit with 77 to skip
+//!   for example. The codes of variant tests which are mapped by `exit=` can not be given.
+//!   A table for a single test in the package metadata takes precedence over the
+//!   directive, the table for all tests applies to the codes neither of them maps:
+//!
+//! ```toml
+//! [package.metadata.conf_test.exit-codes]
+//! 77 = "skip"
+//!
+//! [package.metadata.conf_test.probes.net_so_reuseport.exit-codes]
+//! 126 = "inconclusive"
+//! 127 = "inconclusive"
+//! ```
+//!
+//! * **timeout=SECONDS**
+//!   Limits the time compiling the test and the time executing it may take. A test that does
+//!   not finish in time is killed and counts as failed. Tests stay in the process group of
+//!   the build, so Ctrl-C reaches them too. Processes a test spawns are left running (on
+//!   windows they are killed with the test). The limit can also be set (and tightened for CI
+//!   without touching the test) in the package metadata, which takes precedence over the
+//!   directive:
+//!
+//! ```toml
+//! [package.metadata.conf_test.probes.net_so_reuseport]
+//! timeout = 10
+//! ```
+//!
+//!   Setting `CONF_TEST_TIMEOUT=SECONDS` in the environment gives all tests without a limit of
+//!   their own a default limit. This is a safety net for shared build farms where a single
+//!   wedged test shouldn't stall the queue.
+//!
+//! * **describe=TEXT**
+//!   Tells what the test detects. The description shows up in the log, in warnings about the
+//!   feature and in the reports, making the build output understandable to people unfamiliar
+//!   with the crate:
+//!
+//! ```text
+//! # checking for io_uring (kernel >= 5.6 async I/O)... no, test failed when executed
+//! ```
+//!
+//!   With `ConfTestBuilder::markdown_report(true)` a Markdown table of the features, their
+//!   descriptions and results is written to 'OUT_DIR/conf_test/features.md'. A crate can
+//!   include it in its documentation so that it does not drift from the tests:
+//!
+//! ```rust,ignore
+//! #![doc = include_str!(concat!(env!("OUT_DIR"), "/conf_test/features.md"))]
+//! ```
+//!
+//!   The catalog tests come with descriptions.
+//!
+//! * **android-api=LEVEL**
+//!   The test needs at least the given Android API level. When targeting a lower level the
+//!   feature is disabled without compiling the test, see [Android](#android). Ignored for
+//!   other targets.
+//!
+//! * **fixture=PATH**
+//!   A file the test reads, relative to the crate directory. Tests run in a
+//!   [directory of their own](#detailed-control), they find the crate directory in
+//!   `CARGO_MANIFEST_DIR`. Can be given more than once. The build script reruns when a
+//!   fixture changes and, with the [cache](#caching) enabled, only the tests using that
+//!   fixture are run again.
+//!
+//! * **profile=debug|release**
+//!   The test runs only in builds of the given profile, in the other ones its feature is
+//!   disabled without compiling the test. Builds with an `OPT_LEVEL` other than 0 count as
+//!   release builds, so expensive tests whose results only matter for optimized code, like
+//!   picking the fastest implementation by benchmark, can be left out of debug builds. The
+//!   package metadata takes precedence over the directive:
+//!
+//! ```toml
+//! [package.metadata.conf_test.probes.simd_bench]
+//! profile = "release"
+//! ```
+//!
+//! * **requires=FEATURE**
+//!   The test is only run when the given feature is enabled, otherwise its feature is
+//!   disabled as skipped without compiling the test. Can be given more than once or as comma
+//!   separated list. Unlike depending on the sort order alone this keeps tests that only
+//!   make sense on top of another feature from running, `uring_sqpoll` is pointless when
+//!   `io_uring` failed. The required features must be tested before, features enabled
+//!   manually count as well. A required feature that sorts after the test or has no test
+//!   leaves it skipped, a name that is not a feature at all fails the build.
+//!
+//! * **group=NAME**
+//!   The test belongs to a group of tests sharing an expensive setup, like compiling a
+//!   helper library or generating fixture data. The setup is a program in
+//!   'conf_test_groups/NAME.rs' which is compiled and executed once, before the first test of
+//!   the group that is not [cached](#caching). It gets `CONF_TEST_GROUP`, `CONF_TEST_TARGET`,
+//!   `CONF_TEST_CC` and a fresh directory for its artifacts in `CONF_TEST_GROUP_DIR`, the
+//!   tests of the group get this directory as `CONF_TEST_GROUP_DIR` too and are compiled
+//!   with it as library search path (`-L`). After all tests ran the setup is executed once
+//!   more for its teardown and the directory is removed, `CONF_TEST_GROUP_PHASE` is 'setup'
+//!   or 'teardown'. When the setup fails the tests of the group are inconclusive. Changing
+//!   the setup runs the tests of the group again.
+//!
+//! ```rust,ignore
+//! // This goes into conf_test_groups/helper.rs, the tests use `// conf_test: group=helper`
+//! fn main() {
+//!     let dir = std::env::var("CONF_TEST_GROUP_DIR").unwrap();
+//!     if std::env::var("CONF_TEST_GROUP_PHASE").unwrap() == "setup" {
+//!         let status = std::process::Command::new("rustc")
+//!             .args(["--crate-type", "rlib", "--out-dir", &dir, "helper/lib.rs"])
+//!             .status()
+//!             .unwrap();
+//!         std::process::exit(status.code().unwrap_or(1));
+//!     }
+//! }
+//! ```
+//!
+//! * **template**
+//!   The test is a [template](#templates), its `@NAME@` placeholders are substituted before
+//!   it is compiled.
+//!
 //! One can control ConfTest by setting the environment variable `CONF_TEST_INHIBIT` to one of
 //! the following:
 //! * **skip**
@@ -110,13 +573,437 @@
 //!
 //! Any other value will make the script panic.
 //!
+//! Features that are set manually are not probed. When the environment variable
+//! `CONF_TEST_CHECK_MANUAL` is set, their tests are still compiled and executed in a dry run
+//! where the test outputs are discarded. The log then records what the test would have
+//! decided and a warning is emitted when a manually set feature contradicts its failing
+//! test.
+//!
+//! ## Android
+//!
+//! When building for Android, ConfTest determines the API level the crate is built against
+//! from `CONF_TEST_ANDROID_API`, `ANDROID_PLATFORM` ('android-24' or '24') or
+//! `ANDROID_NATIVE_API_LEVEL`, in this order, or else from the name of the NDK compiler
+//! wrapper used as linker or C compiler ('aarch64-linux-android24-clang'). Tests with an
+//! `android-api` directive above that level are disabled without compiling them. The catalog
+//! tests for libc functions carry the level bionic introduced them in.
+//!
+//! Cross builds can not execute the tests. Instead of skipping ConfTest entirely, tests are
+//! compiled and linked for the target with the configured linker, a successful link enables
+//! the feature. Linking against the NDK's libc of the targeted level catches the functions
+//! that are missing there. In this mode tests can not use the dependencies of the crate and
+//! tests with `exit=` directives fail since they can not select a variant.
+//!
+//! ## Niche targets
+//!
+//! Cross builds for Haiku, Redox and UEFI are handled the same way: the tests are compiled
+//! and linked for the target but not executed, results taken on the host would only describe
+//! the host. With `ConfTestBuilder::niche_target_policy(NicheTargetPolicy::AssumeDisabled)`
+//! the tests are not compiled at all and their features are assumed to be disabled. The log
+//! notes which of both happened for each test.
+//!
+//! ## Assumptions
+//!
+//! The outcome of tests can be assumed without running them. This is useful for CI matrices
+//! that want to simulate platforms the runner can't exercise. Assumptions are given by the
+//! environment variable `CONF_TEST_ASSUME` as comma separated list of `feature=yes` or
+//! `feature=no` or configured with the builder.
+//!
+//! ```rust,ignore
+//! fn main() {
+//!     conf_test::ConfTest::builder()
+//!         .assume("io_uring", true)
+//!         .assume("avx512", false)
+//!         .run();
+//! }
+//! ```
+//!
+//! Packagers can ship an overrides file in their build recipes. It is read from
+//! 'conf_test.overrides.toml' in the crate directory when present. Another path can be set by
+//! the `CONF_TEST_OVERRIDES` environment variable or with the builder. Each feature is either
+//! forced to a boolean or to a table which also gives the output that is forwarded to cargo in
+//! place of the output of the test:
+//!
+//! ```toml
+//! o_path = false
+//!
+//! [io_uring]
+//! enabled = true
+//! output = ["cargo:rustc-env=URING_ENTRIES=256"]
+//! ```
+//!
+//! Cross builds can not run most tests. A crate can ship defaults for them in
+//! 'conf_test.cross.toml' in the crate directory, another path can be set with
+//! `ConfTestBuilder::cross_assumptions_file()`. It is only read when the target differs from
+//! the host. Its sections are keyed by target triples, which may contain `*` wildcards, or by
+//! `target_os=OS` or `target_arch=ARCH` and give the features in the same form as the
+//! overrides file. Triples take precedence over architectures and these over operating
+//! systems, of several matching triples the longest pattern wins. Features that are not
+//! listed are handled as without the table.
+//!
+//! ```toml
+//! ["target_os=linux"]
+//! io_uring = true
+//!
+//! ["aarch64-*-linux-android"]
+//! io_uring = false
+//! ```
+//!
+//! `ConfTestBuilder::include_features()` and `ConfTestBuilder::exclude_features()` narrow the
+//! features that are probed at all, by names or prefixes like `net_*`. The features left out
+//! are reported as not tested, assumptions still apply to them:
+//!
+//! ```rust,ignore
+//! ConfTest::builder()
+//!     .exclude_features(&["gpu_*"])
+//!     .extern_crates(false)
+//!     .run();
+//! ```
+//!
+//! Tests that use no other crates don't need their build-dependencies resolved, which runs
+//! cargo once more. `ConfTestBuilder::extern_crates(false)` skips this.
+//!
+//! ## Local configuration
+//!
+//! Developers can force features and set environment variables for compiling and running the
+//! tests on their machine in a 'conf_test.local.toml' in the crate directory, similar to
+//! autotools' 'config.site'. This file should be listed in '.gitignore'. Features are given in
+//! the same form as in the overrides file:
+//!
+//! ```toml
+//! [features]
+//! o_path = false
+//!
+//! [env]
+//! LIBFOO_PREFIX = "/opt/foo"
+//! ```
+//!
+//! `CONF_TEST_ASSUME` takes precedence over the local configuration which takes precedence
+//! over the overrides file which takes precedence over the builder which takes precedence
+//! over the table for cross builds.
+//!
+//! ## Hooks
+//!
+//! `ConfTestBuilder::on_probe_start()`, `on_probe_end()` and `on_finish()` register callbacks
+//! around the tests for custom logging, metrics or deciding features without running their
+//! test. A start hook returning `Some(enabled)` decides the feature, which then counts as
+//! assumed.
+//!
+//! For build observability, `ConfTestBuilder::report_sink()` registers a `ReportSink` that
+//! receives the status and timings of every feature at the end of the run. `FileSink` writes
+//! them as JSON lines to a file, `StdoutSink` prints them as `# conf_test/1` events.
+//!
+//! ## Caching
+//!
+//! With `ConfTestBuilder::cache(true)` the results of successful tests are cached in
+//! 'OUT_DIR/conf_test/cache.toml'. When the build script runs again, such a test is only
+//! compiled and executed when its source, its fixtures, its timeout from the package
+//! metadata, the features enabled before it or the dependencies changed. Each test is
+//! tracked on its own, changing one test reruns the build script but not the other tests.
+//! Failed tests always run again, they may succeed once a missing library got installed.
+//! The cache is recorded together with a fingerprint of the environment the tests may
+//! depend on: the rustc version and commit hash, the kernel release, the libc version, the
+//! target and `RUSTFLAGS` and the environment variables from the local configuration. When
+//! this fingerprint changes, all cached results are discarded. Setting `CONF_TEST_NO_CACHE`
+//! disables the cache.
+//!
+//! The cache, the log and the reports are written to a temporary file first which then
+//! replaces the old one. A build interrupted while probing, by Ctrl-C or a crash, leaves the
+//! files of the previous run intact rather than half written.
+//!
+//! ## Reproducible output
+//!
+//! Distributions aiming for reproducible builds need byte-identical logs and reports from
+//! identical inputs. `ConfTestBuilder::reproducible(true)` or setting `CONF_TEST_REPRODUCIBLE`
+//! in the environment leaves the wall-clock time and the durations out of the log and the
+//! HTML report. Paths below 'OUT_DIR' are written as '$OUT_DIR/...' and paths below the crate
+//! directory relative to it.
+//!
+//! All timestamps conf_test writes into the log and the reports are taken from
+//! `SOURCE_DATE_EPOCH` when it is set, reproducible or not. The generated values module
+//! contains no timestamps at all.
+//!
+//! ## Cfg style
+//!
+//! By default discovered features are emitted as `feature="o_path"` cfgs. These may collide
+//! with real cargo features and are hard to tell apart from features set manually. With
+//! `ConfTestBuilder::cfg_style()` the cfgs can be emitted in a namespace instead, either as
+//! key/value cfg (`conf_test="o_path"`) or as prefixed cfg (`conf_o_path`). Manually set
+//! features are emitted in that namespace as well and the matching `rustc-check-cfg`
+//! declarations are emitted for all features.
+//!
+//! ```rust,ignore
+//! fn main() {
+//!     conf_test::ConfTest::builder()
+//!         .cfg_style(conf_test::CfgStyle::Key("conf_test".into()))
+//!         .run();
+//! }
+//! ```
+//!
+//! The crate then uses `#[cfg(conf_test = "o_path")]`.
+//!
+//! During a migration from features to cfgs both can be emitted at the same time with
+//! `ConfTestBuilder::add_cfg_style()`. Tests are compiled with the cfgs of all styles.
+//!
+//! ## Generated values
+//!
+//! Each run writes a module with constants to 'OUT_DIR/conf_test/values.rs' which the crate
+//! can include. When the tests are inhibited by `CONF_TEST_INHIBIT` the module is empty:
+//!
+//! ```rust,ignore
+//! mod conf_values {
+//!     include!(concat!(env!("OUT_DIR"), "/conf_test/values.rs"));
+//! }
+//! ```
+//!
+//! With `ConfTestBuilder::host_info(true)` it contains the details of the host the tests ran
+//! on, so binaries can report the environment they were configured against: `HOST_OS`,
+//! `HOST_ARCH`, `HOST_KERNEL_RELEASE`, `HOST_KERNEL_VERSION` and `HOST_DISTRO`. Details that
+//! are not available on the host are empty strings.
+//!
+//! `ConfTestBuilder::host_parallelism(true)` adds `HOST_CPUS`, the CPUs online on the build
+//! host, and `HOST_DEFAULT_THREADS`, the threads it can run in parallel with CPU affinity and
+//! quotas applied. These describe the build host only. They are meant for embedded and
+//! appliance builds made on the same hardware they run on, which bake in a thread pool size.
+//!
+//! ## Templates
+//!
+//! Families of nearly identical tests can be generated from a single template. A template is
+//! a test with `@NAME@` placeholders which are substituted before it gets compiled, `@@`
+//! stands for a single `@`. Tests in 'conf_tests/' become templates with the
+//! [template](#directives) directive, more features can be probed by the same template file
+//! with `ConfTestBuilder::template_probe()`:
+//!
+//! ```rust,ignore
+//! // This goes into conf_templates/header.rs
+//! fn main() {
+//!     assert!(std::path::Path::new("@PREFIX@/include/@HEADER@").exists());
+//! }
+//! ```
+//!
+//! ```rust,ignore
+//! ConfTest::builder()
+//!     .template_var("PREFIX", "/usr")
+//!     .template_probe("have_zlib_h", "conf_templates/header.rs", &[("HEADER", "zlib.h")])
+//!     .template_probe("have_lzma_h", "conf_templates/header.rs", &[("HEADER", "lzma.h")])
+//!     .run();
+//! ```
+//!
+//! The variables are the values exported by the tests before and the
+//! [local configuration](#local-configuration), overridden by the ones set with
+//! `ConfTestBuilder::template_var()` and these by the ones of the template probe. `FEATURE`
+//! is the feature being probed. An undefined variable makes the script panic. The expanded
+//! test is left next to the compiled one in 'OUT_DIR/conf_test/' for inspection.
+//!
+//! ## Feature matrix
+//!
+//! Some features only work when combined in specific ways, an allocator shim may need
+//! `nightly` to compile or break when `simd` is enabled. `ConfTestBuilder::probe_matrix()`
+//! probes a feature once more for each combination of some other features being enabled or
+//! not, regardless of whether they are enabled in this build:
+//!
+//! ```rust,ignore
+//! ConfTest::builder()
+//!     .probe_matrix("fast_alloc", &["nightly", "simd"])
+//!     .run();
+//! ```
+//!
+//! Whether the feature is enabled is still decided by its regular probe. The results of the
+//! combinations are logged and listed by `ConfTestBuilder::matrix()` and in the report of
+//! `ConfTestBuilder::markdown_report()`. The number of combinations doubles with each
+//! feature, matrices of more than 8 features are refused. Combinations are probed on every
+//! run, they are not [cached](#caching).
+//!
+//! ## Catalog tests
+//!
+//! conf_test ships tests for capabilities many crates need. A feature declared in
+//! 'Cargo.toml' that has no test in 'conf_tests/' is probed by the catalog test of the same
+//! name when it is opted in, in the package metadata or with
+//! `ConfTestBuilder::catalog_features()`. A test in 'conf_tests/' always takes precedence
+//! over the catalog.
+//!
+//! ```toml
+//! [features]
+//! fs_symlinks = []
+//!
+//! [package.metadata.conf_test]
+//! catalog = ["fs_symlinks"]
+//! ```
+//!
+//! Catalog tests are opt-in because their names are common ones. A feature named after an
+//! optional dependency like `zstd` or `openssl` must enable that dependency, not be probed,
+//! so features enabling a dependency can not be opted in.
+//!
+//! Further catalogs can be registered with `ConfTestBuilder::register_catalog()`, extending
+//! the built-in one or overriding its tests with higher versions, see `Catalog`. The log
+//! tells which catalog and version each catalog test came from. Crates can contribute tests
+//! by implementing `ProbeProvider`, registered with `ConfTestBuilder::probe_provider()`.
+//!
+//! Filesystem of the build directory:
+//! * **fs_case_sensitive** File names differing only in case are distinct files.
+//! * **fs_symlinks** Symbolic links can be created and followed.
+//! * **fs_xattr** Extended attributes can be set and read back (Linux, Android, macOS).
+//! * **fs_sparse_files** Files can have holes which take no disk space (Unix).
+//!
+//! Clocks and timers:
+//! * **clock_monotonic_raw** `clock_gettime()` supports `CLOCK_MONOTONIC_RAW` (Linux,
+//!   Android, macOS).
+//! * **clock_boottime** `clock_gettime()` supports `CLOCK_BOOTTIME` (Linux, Android).
+//! * **clock_nanosleep** `clock_nanosleep()` works on `CLOCK_MONOTONIC`, relative and
+//!   absolute (Linux, Android, FreeBSD).
+//! * **timerfd_highres** timerfd timers fire with sub-millisecond resolution (Linux,
+//!   Android).
+//!
+//! Socket options, verified on real sockets so the running kernel must support them:
+//! * **so_reuseport** Two UDP sockets with `SO_REUSEPORT` bind the same port (Linux,
+//!   Android, macOS, BSDs).
+//! * **tcp_fastopen** A listening socket accepts `TCP_FASTOPEN` (Linux, Android, macOS,
+//!   FreeBSD).
+//! * **so_incoming_cpu** `SO_INCOMING_CPU` can be queried (Linux, Android).
+//! * **udp_gro** UDP generic receive offload can be enabled (Linux, Android).
+//! * **udp_gso** UDP generic segmentation offload can be set (Linux, Android).
+//!
+//! Memory management (Linux, Android unless noted):
+//! * **madv_free** `madvise()` accepts `MADV_FREE` (also macOS, FreeBSD).
+//! * **madv_cold** `madvise()` accepts `MADV_COLD`.
+//! * **mremap** `mremap()` grows a mapping, moving it when needed.
+//! * **memfd_create** `memfd_create()` creates anonymous in-memory files.
+//! * **memfd_seals** memfd files can be sealed against shrinking, growing and writing.
+//! * **transparent_hugepages** Transparent hugepages are enabled and `madvise()` accepts
+//!   `MADV_HUGEPAGE`.
+//! * **hugepages** Memory can be backed by hugepages, reserved ones mapped with
+//!   `MAP_HUGETLB` or else transparent ones. The `HUGEPAGES` environment variable is set for
+//!   the crate to 'hugetlb' or 'transparent' telling which.
+//!
+//! File descriptor based primitives (Linux, Android):
+//! * **eventfd** `eventfd()` counters work as wakeup channels.
+//! * **timerfd** `timerfd_create()` creates timers on `CLOCK_MONOTONIC`.
+//! * **signalfd** `signalfd()` receives signals through a file descriptor.
+//! * **pidfd_open** `pidfd_open()` refers to processes by file descriptor.
+//! * **epoll_exclusive** `epoll_ctl()` accepts `EPOLLEXCLUSIVE`.
+//!
+//! Zero-copy I/O, exercised on temporary files in the build directory (Linux, Android):
+//! * **copy_file_range** `copy_file_range()` copies between files.
+//! * **splice** `splice()` moves data from a file through a pipe to another file.
+//! * **sendfile** `sendfile()` copies from a file to another file.
+//! * **preadv2_nowait** `preadv2()` accepts `RWF_NOWAIT`.
+//!
+//! io_uring (Linux):
+//! * **io_uring** io_uring instances can be set up, it may be compiled out, disabled by the
+//!   'kernel.io_uring_disabled' sysctl or blocked by seccomp in containers.
+//! * **uring_OPCODE** The running kernel supports the io_uring opcode, as reported
+//!   by `io_uring_register(IORING_REGISTER_PROBE)` (Linux 5.6). The opcodes are named after
+//!   the `IORING_OP_*` constants in lower case, for example `uring_send_zc` or
+//!   `uring_read_multishot`. Their availability varies widely between kernel versions.
+//!
+//! Randomness:
+//! * **getrandom** `getrandom()` is available (Linux, Android, FreeBSD).
+//! * **getentropy** `getentropy()` is available.
+//! * **dev_urandom** '/dev/urandom' is a readable character device, it may be missing in
+//!   chroots and minimal containers (Unix).
+//! * **grnd_insecure** `getrandom()` accepts `GRND_INSECURE` (Linux 5.6, Android).
+//!
+//! BSD:
+//! * **kqueue** `kqueue()` is available (FreeBSD, OpenBSD, NetBSD, DragonFly, macOS).
+//! * **evfilt_user** kqueue supports user triggered `EVFILT_USER` events (FreeBSD, macOS).
+//! * **kevent64** `kevent64()` is available (macOS).
+//! * **capsicum** the kernel supports Capsicum capability mode (FreeBSD).
+//! * **pledge** `pledge()` is available (OpenBSD).
+//! * **unveil** `unveil()` is available (OpenBSD).
+//!
+//! illumos and Solaris:
+//! * **event_ports** event ports (`port_create()`) are available.
+//! * **doors** doors (`door_create()`) are available.
+//! * **dev_poll** '/dev/poll' polls registered file descriptors.
+//!
+//! On illumos and Solaris the socket functions live in 'libsocket' and 'libnsl', tests are
+//! linked against both there.
+//!
+//! macOS:
+//! * **f_fullfsync** `fcntl(F_FULLFSYNC)` flushes files to permanent storage.
+//! * **f_preallocate** `fcntl(F_PREALLOCATE)` reserves disk space.
+//! * **evfilt_machport** kqueue waits for Mach port messages with `EVFILT_MACHPORT`.
+//! * **os_unfair_lock** `os_unfair_lock` is available (macOS 10.12).
+//! * **grand_central_dispatch** Grand Central Dispatch runs work on its global queues.
+//!
+//! Windows I/O:
+//! * **iocp** I/O completion ports queue and dequeue completion packets.
+//! * **iocp_ex** `GetQueuedCompletionStatusEx()` dequeues many packets at once (Vista).
+//! * **file_skip_completion_port** `SetFileCompletionNotificationModes()` skips packets for
+//!   operations that complete immediately (Vista).
+//! * **file_flag_no_buffering** files in the build directory can be written with
+//!   `FILE_FLAG_NO_BUFFERING | FILE_FLAG_WRITE_THROUGH`.
+//! * **registered_io** Winsock provides the Registered I/O extension (Windows 8).
+//!
+//! The Windows tests declare the few functions they need with the same signatures as
+//! 'windows-sys', the crate does not need to depend on it.
+//!
+//! System topology (Linux):
+//! * **numa** The kernel exposes NUMA nodes in '/sys' and libnuma can be loaded at runtime.
+//!   The node count of the build host is set for the crate as `NUMA_NODES` environment
+//!   variable, read it with `env!("NUMA_NODES")`.
+//!
+//! GPU and accelerator toolkits, for crates with optional compute backends. The location
+//! of the toolkit is set for the crate as environment variables and exported to the tests
+//! running later, which can probe the toolkit further:
+//! * **cuda** The CUDA toolkit, `nvcc` runs and the CUDA runtime library is present. It is
+//!   looked up in `CUDA_PATH` or `CUDA_HOME`, else in '/usr/local/cuda'. Sets `CUDA_ROOT`
+//!   and `CUDA_LIB_DIR`.
+//! * **rocm** ROCm, `hipcc` runs and the HIP runtime library is present (Linux). It is
+//!   looked up in `ROCM_PATH`, else in '/opt/rocm'. Sets `ROCM_ROOT` and `ROCM_LIB_DIR`.
+//! * **oneapi** Intel oneAPI, the DPC++ compiler runs and the SYCL runtime library is
+//!   present. It is looked up in `ONEAPI_ROOT`, else in '/opt/intel/oneapi'. Sets
+//!   `ONEAPI_ROOT` and `ONEAPI_LIB_DIR`.
+//!
+//! The library directories are not added to the linker search path, pass them on with
+//! `cargo:rustc-link-search` in 'build.rs' when the backend gets enabled.
+//!
+//! TLS and cryptography, the libraries are looked up on the default search path of the
+//! linker:
+//! * **openssl** An OpenSSL compatible libssl and libcrypto can be linked: OpenSSL 1.1.0 or
+//!   later, LibreSSL or BoringSSL. The crate gets linked against both. `OPENSSL_FLAVOR` is
+//!   set to 'openssl', 'libressl' or 'boringssl' and `OPENSSL_VERSION_NUMBER` to the value
+//!   of `OpenSSL_version_num()`. `OPENSSL_VERSION`, the version in the version string, is
+//!   set when the library tells it, read it with `option_env!("OPENSSL_VERSION")`.
+//! * **openssl3**, **libressl**, **boringssl** libcrypto is OpenSSL 3.0 or later, LibreSSL
+//!   or BoringSSL.
+//! * **evp_NAME** libcrypto provides the `EVP_*` function, named in lower case without the
+//!   prefix. Available up to OpenSSL 1.1.1 are `evp_pkey_derive`, `evp_chacha20_poly1305`,
+//!   `evp_aes_128_ocb`, `evp_digestsign`, `evp_sha3_256`, `evp_pkey_new_raw_private_key`
+//!   and `evp_pkey_get_raw_public_key`, OpenSSL 3.0 added `evp_md_fetch`,
+//!   `evp_cipher_fetch`, `evp_mac_fetch`, `evp_kdf_fetch`, `evp_pkey_fromdata`,
+//!   `evp_pkey_q_keygen` and `evp_default_properties_enable_fips`.
+//!
+//! Compression libraries, looked up on the default search path of the linker. The crate
+//! gets linked against the libraries that are found, so it can pick the fastest backend
+//! available on the system:
+//! * **zlib** libz can be linked, the original zlib or zlib-ng in compatibility mode.
+//!   `ZLIB_FLAVOR` is set to 'zlib' or 'zlib-ng' and `ZLIB_VERSION` to its version.
+//! * **zlib_ng** libz-ng with the native zlib-ng API can be linked, `ZLIB_NG_VERSION` is set
+//!   to its version.
+//! * **libdeflate** libdeflate can be linked, it does not tell its version at runtime.
+//! * **zstd** libzstd can be linked, `ZSTD_VERSION` is set to its version.
+//!
+//! Allocators, for crates that wrap or introspect the allocator:
+//! * **malloc_usable_size** `malloc_usable_size()` reports at least the requested size
+//!   (Linux, Android, FreeBSD).
+//! * **aligned_alloc** `aligned_alloc()` returns memory aligned up to the page size.
+//! * **aligned_alloc_any_size** `aligned_alloc()` accepts sizes that are not a multiple of
+//!   the alignment, as C17 allows. Some C11 implementations reject them.
+//! * **jemalloc** libjemalloc can be linked, `JEMALLOC_VERSION` is set to its version and
+//!   the crate gets linked against it. Builds with a symbol prefix are not detected.
+//! * **tcmalloc** libtcmalloc from gperftools can be linked, `TCMALLOC_VERSION` is set to
+//!   its version and the crate gets linked against it.
+//!
 //!
 //! # Limitations
 //!
 //! * The tests running on the machine where the software is build, using the
 //!   build-dependencies. This will be a problem when Software gets cross-compiled. For cross
 //!   compilation set 'CONF_TEST_INHIBIT=skip' and set the desired features manually with the
-//!   '--features' option.
+//!   '--features' option. Android and niche target cross builds are the exception, their
+//!   tests are compiled for the target but not executed, see [Android](#android) and
+//!   [Niche targets](#niche-targets).
 //!
 //! * Features can only be set, not unset. This is deliberate and not a limitation. Do only
 //!   positive tests checking for the presence of a feature.
@@ -132,231 +1019,2516 @@
 //!
 
 use std::ffi::{OsStr, OsString};
-use std::fs::{DirBuilder, File};
+use std::fs::DirBuilder;
+
+use std::env::var_os as env;
+use std::path::{Path, PathBuf};
+use std::str;
+
+use cargo_metadata::Message;
+use std::process::{Command, Output, Stdio};
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::Duration;
+
+mod atomic;
+
+mod directives;
+pub use directives::{CrateType, Directives, ExitAction, Profile};
+
+mod report;
+pub use report::{Outcome, Report};
+use report::{ProbeDetails, Status};
+
+mod builder;
+pub use builder::{ConfTestBuilder, MissingTestsPolicy};
+
+mod overrides;
+use overrides::{Forced, LocalConfig};
+
+mod cfg;
+pub use cfg::CfgStyle;
+
+mod instructions;
+use instructions::TestOutput;
+
+mod ccompiler;
+pub use ccompiler::CCompiler;
+
+mod cache;
+use cache::Cache;
+
+mod values;
+use values::Values;
+
+mod timing;
+use timing::Phase;
+
+mod cargo;
+
+mod catalog;
+pub use catalog::{Catalog, ProbeProvider};
+
+mod process;
+
+mod hooks;
+pub use hooks::{ProbeContext, ProbeOutcome};
+
+mod target;
+pub use target::NicheTargetPolicy;
+use target::Target;
+
+mod event;
+
+mod sink;
+pub use sink::{FileSink, ProbeRecord, ReportSink, StdoutSink};
+
+mod html;
+
+mod dot;
+
+mod markdown;
+
+mod github;
+
+mod matrix;
+
+mod template;
+
+mod manifest;
+pub use manifest::Manifest;
+
+mod probe;
+pub use probe::{ProbeOptions, ProbeResult};
+
+/// A test found by `ConfTest::discover()`.
+#[derive(Debug, Clone)]
+pub struct ProbeInfo {
+    /// The feature probed by the test, derived from its path.
+    pub feature: String,
+    /// The source of the test.
+    pub path: PathBuf,
+    /// The directives in the source of the test.
+    pub directives: Directives,
+    /// True when the feature or one of its variants is defined in 'Cargo.toml', tests for
+    /// undefined features are never run.
+    pub applicable: bool,
+}
+
+/// State shared by all tests of a run.
+struct Context<'a> {
+    builder: &'a ConfTestBuilder,
+    edition: String,
+    extern_libs: BTreeMap<OsString, (String, PathBuf)>,
+    /// The dependencies and build-dependencies of the package, to tell why a test could not
+    /// use a crate.
+    dependencies: BTreeSet<String>,
+    build_dependencies: BTreeSet<String>,
+    c_compiler: Option<CCompiler>,
+    /// Environment variables for compiling and running tests.
+    env: BTreeMap<String, String>,
+    /// Where the compiled tests are placed.
+    out_dir: PathBuf,
+    /// Time limits for single tests from the package metadata.
+    probe_timeouts: BTreeMap<String, Duration>,
+    /// What exit codes mean for all tests and for single tests, from the package metadata.
+    exit_codes: BTreeMap<i32, ExitAction>,
+    probe_exit_codes: BTreeMap<String, BTreeMap<i32, ExitAction>>,
+    /// The only builds single tests run in, from the package metadata.
+    probe_profiles: BTreeMap<String, Profile>,
+    /// The profile of the crate being built.
+    profile: Profile,
+    target: Target,
+    /// Passed to the tests as `CONF_TEST_SEED`.
+    seed: u64,
+    /// The group setups run so far, their directory and binary or why they failed.
+    groups: BTreeMap<String, Result<(PathBuf, PathBuf), Status>>,
+}
+
+/// The expensive parts of a context, resolved once and shared by `ConfTest::run()` and
+/// `ConfTest::run_probe()`.
+#[derive(Debug, Clone)]
+struct Resolved {
+    edition: String,
+    extern_libs: BTreeMap<OsString, (String, PathBuf)>,
+    dependencies: BTreeSet<String>,
+    build_dependencies: BTreeSet<String>,
+    c_compiler: Option<CCompiler>,
+    probe_timeouts: BTreeMap<String, Duration>,
+    exit_codes: BTreeMap<i32, ExitAction>,
+    probe_exit_codes: BTreeMap<String, BTreeMap<i32, ExitAction>>,
+    probe_profiles: BTreeMap<String, Profile>,
+}
+
+static RESOLVED: Mutex<Option<Resolved>> = Mutex::new(None);
+
+/// The report of the last run, queried by `ConfTest::enabled()` and `ConfTest::is_enabled()`.
+static LAST_REPORT: Mutex<Option<Report>> = Mutex::new(None);
+
+/// Libraries tests are linked against on hosts where parts of libc live in separate
+/// libraries. A C compiler there needs them on the command line as well, extern declarations
+/// in tests would otherwise fail to link although the functions exist.
+#[cfg(any(target_os = "illumos", target_os = "solaris"))]
+const SYSTEM_LIBS: &[&str] = &["socket", "nsl"];
+#[cfg(not(any(target_os = "illumos", target_os = "solaris")))]
+const SYSTEM_LIBS: &[&str] = &[];
+
+/// Crates of the sysroot tests can name with `extern crate` without a build-dependency.
+const SYSROOT_CRATES: &[&str] = &["std", "core", "alloc", "proc_macro", "test"];
+
+// Empty Type for now, In future this may be extended without breaking existing code.
+/// Implements the conf_test API
+pub enum ConfTest {}
+
+impl ConfTest {
+    /// Run the configuration tests in 'conf_tests/'.
+    #[allow(dead_code)]
+    pub fn run() {
+        Self::builder().run()
+    }
+
+    /// Run the configuration tests in 'conf_tests/' like `run()` and return their results, so
+    /// the build script can act on them.
+    pub fn run_report() -> Report {
+        Self::builder().run_report()
+    }
+
+    /// Run the tests in strict mode, for the CI of the crate author. Every test in
+    /// 'conf_tests/' must at least compile, see `ConfTestBuilder::strict()`.
+    pub fn check_all() {
+        Self::builder().strict(true).run()
+    }
+
+    /// Create a builder to configure the configuration tests before running them.
+    pub fn builder() -> ConfTestBuilder {
+        ConfTestBuilder::default()
+    }
+
+    /// Returns the features enabled by the last run in sort order. This includes features set
+    /// manually and assumed features. Lets later 'build.rs' steps branch on the discoveries.
+    /// Returns an empty list when no run happened yet.
+    pub fn enabled() -> Vec<String> {
+        LAST_REPORT
+            .lock()
+            .expect("ConfTest report poisoned")
+            .as_ref()
+            .map(|report| report.enabled().map(String::from).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns true when 'feature' was enabled by the last run.
+    pub fn is_enabled(feature: &str) -> bool {
+        LAST_REPORT
+            .lock()
+            .expect("ConfTest report poisoned")
+            .as_ref()
+            .map(|report| report.is_enabled(feature))
+            .unwrap_or(false)
+    }
+
+    /// Returns the value a test enabled by the last run captured for 'key' with
+    /// `conf_test:set-env=KEY=VALUE` or else `conf_test:export=KEY=VALUE`. When several tests
+    /// set it the first enabled feature in sort order wins.
+    pub fn value(key: &str) -> Option<String> {
+        LAST_REPORT
+            .lock()
+            .expect("ConfTest report poisoned")
+            .as_ref()
+            .and_then(|report| report.value(key).map(String::from))
+    }
+
+    /// Returns the value for 'key' as bool, see `value()`. Accepts 'true', 'yes', 'on' and
+    /// '1' or 'false', 'no', 'off' and '0' in any case, panics on other values.
+    pub fn value_bool(key: &str) -> Option<bool> {
+        let value = Self::value(key)?;
+        Some(match value.trim().to_ascii_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => true,
+            "false" | "no" | "off" | "0" => false,
+            _ => panic!("ConfTest value {} is not a bool: {:?}", key, value),
+        })
+    }
+
+    /// Returns the value for 'key' as integer, see `value()`. Accepts decimal numbers and
+    /// hexadecimal ones prefixed by '0x', panics on other values.
+    ///
+    /// ```rust,ignore
+    /// fn main() {
+    ///     conf_test::ConfTest::run();
+    ///
+    ///     if let Some(page_size) = conf_test::ConfTest::value_int("PAGE_SIZE") {
+    ///         let out_dir = std::env::var("OUT_DIR").unwrap();
+    ///         let code = format!("pub const PAGE_SIZE: usize = {};\n", page_size);
+    ///         std::fs::write(format!("{}/page_size.rs", out_dir), code).unwrap();
+    ///     }
+    /// }
+    /// ```
+    pub fn value_int(key: &str) -> Option<i64> {
+        let value = Self::value(key)?;
+        let trimmed = value.trim();
+        let parsed = match trimmed
+            .strip_prefix("0x")
+            .or_else(|| trimmed.strip_prefix("0X"))
+        {
+            Some(hex) => i64::from_str_radix(hex, 16),
+            None => trimmed.parse(),
+        };
+        Some(
+            parsed.unwrap_or_else(|_| {
+                panic!("ConfTest value {} is not an integer: {:?}", key, value)
+            }),
+        )
+    }
+
+    /// Applies the results of the last run to a C compilation. Defines `HAVE_<FEATURE>=1` for
+    /// each enabled feature and adds the include directories tests printed with
+    /// `conf_test:include=PATH`. Needs the 'cc' feature of conf_test.
+    ///
+    /// ```rust,ignore
+    /// fn main() {
+    ///     conf_test::ConfTest::run();
+    ///
+    ///     let mut build = cc::Build::new();
+    ///     conf_test::ConfTest::apply_to_cc(&mut build);
+    ///     build.file("src/helper.c").compile("helper");
+    /// }
+    /// ```
+    #[cfg(feature = "cc")]
+    pub fn apply_to_cc(build: &mut cc::Build) {
+        if let Some(report) = LAST_REPORT
+            .lock()
+            .expect("ConfTest report poisoned")
+            .as_ref()
+        {
+            for feature in report.enabled() {
+                build.define(&have_define(feature), "1");
+            }
+            for dir in report.include_dirs() {
+                build.include(dir);
+            }
+        }
+    }
+
+    /// Returns the clang arguments for the results of the last run, the counterpart of
+    /// `ConfTest::apply_to_cc()` for bindgen. Every enabled feature becomes a
+    /// `-DHAVE_<FEATURE>=1` define and the include directories of enabled features become
+    /// `-I` arguments. Thus the generated bindings match the detected platform capabilities.
+    ///
+    /// ```rust,ignore
+    /// fn main() {
+    ///     conf_test::ConfTest::run();
+    ///
+    ///     let bindings = bindgen::Builder::default()
+    ///         .header("wrapper.h")
+    ///         .clang_args(conf_test::ConfTest::clang_args())
+    ///         .generate()
+    ///         .expect("Unable to generate bindings");
+    /// }
+    /// ```
+    pub fn clang_args() -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(report) = LAST_REPORT
+            .lock()
+            .expect("ConfTest report poisoned")
+            .as_ref()
+        {
+            for feature in report.enabled() {
+                args.push(format!("-D{}=1", have_define(feature)));
+            }
+            for dir in report.include_dirs() {
+                args.push(format!("-I{}", dir.display()));
+            }
+        }
+        args
+    }
+
+    /// Returns the working C compiler found by the last run.
+    pub fn c_compiler() -> Option<CCompiler> {
+        LAST_REPORT
+            .lock()
+            .expect("ConfTest report poisoned")
+            .as_ref()
+            .and_then(|report| report.c_compiler.clone())
+    }
+
+    /// Runs the tests of the crate in 'manifest_dir' and prints a table telling which features
+    /// are supported on this machine. Meant for developer tooling such as a
+    /// `cargo xtask check-platform` command, run before attempting a full build on a new
+    /// machine. The files of the run go to 'target/conf_test/' in the crate directory.
+    ///
+    /// ```rust,ignore
+    /// // xtask/src/main.rs
+    /// fn main() {
+    ///     conf_test::ConfTest::print_matrix("crates/netio");
+    /// }
+    /// ```
+    pub fn print_matrix<P: AsRef<Path>>(manifest_dir: P) {
+        let manifest_dir = manifest_dir.as_ref();
+        print!(
+            "{}",
+            Self::builder()
+                .manifest_dir(manifest_dir)
+                .out_dir(manifest_dir.join("target").join("conf_test"))
+                .matrix()
+        );
+    }
+
+    /// Returns the features, dependencies and edition of the package from 'Cargo.toml', read
+    /// the same way ConfTest does.
+    pub fn manifest() -> Manifest {
+        Manifest::query(None)
+    }
+
+    /// Compiles and runs the test at 'path' which does not need to follow the feature name
+    /// convention, for probing things that don't map to a feature. The result is only
+    /// returned, nothing is emitted to cargo. Directives in the test and the environment from
+    /// 'conf_test.local.toml' are honored. The dependencies and C compiler resolved by a
+    /// previous `ConfTest::run()` are reused. With
+    /// `CONF_TEST_INHIBIT=skip` nothing is run and a default (failed) result is returned.
+    pub fn run_probe<P: AsRef<Path>>(path: P, options: &ProbeOptions) -> ProbeResult {
+        Self::builder().run_probe(path, options)
+    }
+
+    pub(crate) fn run_probe_with(
+        builder: &ConfTestBuilder,
+        path: &Path,
+        options: &ProbeOptions,
+    ) -> ProbeResult {
+        // the dependency resolution runs 'build.rs' again, inhibited
+        if Self::inhibited() {
+            return ProbeResult::default();
+        }
+
+        let name = match &options.name {
+            Some(name) => name.clone(),
+            None => path
+                .file_stem()
+                .expect("invalid probe file name")
+                .to_string_lossy()
+                .into_owned(),
+        };
+
+        // kept apart from the tests of features which may have the same name
+        let mut out_dir = PathBuf::new();
+        out_dir.push(builder.conf_test_dir());
+        out_dir.push("probes");
+        DirBuilder::new()
+            .recursive(true)
+            .create(&out_dir)
+            .expect("Failed to create output directory");
+
+        let resolved = RESOLVED
+            .lock()
+            .expect("ConfTest resolution poisoned")
+            .get_or_insert_with(|| {
+                let manifest = Manifest::query(builder.manifest_dir.as_deref());
+                Resolved {
+                    edition: manifest.edition,
+                    extern_libs: Self::get_extern_libs(builder, &manifest.build_dependencies),
+                    dependencies: manifest.dependencies,
+                    build_dependencies: manifest.build_dependencies,
+                    c_compiler: CCompiler::discover(),
+                    probe_timeouts: manifest.probe_timeouts,
+                    exit_codes: manifest.exit_codes,
+                    probe_exit_codes: manifest.probe_exit_codes,
+                    probe_profiles: manifest.probe_profiles,
+                }
+            })
+            .clone();
+
+        let local_config = Self::local_config_path(builder);
+        let mut env = if local_config.exists() {
+            overrides::load_local(&local_config).env
+        } else {
+            BTreeMap::new()
+        };
+        env.extend(options.env.clone());
+
+        let mut context = Context {
+            builder,
+            edition: resolved.edition,
+            extern_libs: resolved.extern_libs,
+            dependencies: resolved.dependencies,
+            build_dependencies: resolved.build_dependencies,
+            c_compiler: resolved.c_compiler,
+            env,
+            out_dir,
+            probe_timeouts: resolved.probe_timeouts,
+            exit_codes: resolved.exit_codes,
+            probe_exit_codes: resolved.probe_exit_codes,
+            probe_profiles: resolved.probe_profiles,
+            profile: Profile::current(),
+            target: Target::detect(builder.niche_target_policy),
+            seed: Self::seed(builder),
+            groups: BTreeMap::new(),
+        };
+
+        let mut details = ProbeDetails::default();
+        let mut outputs = Vec::new();
+        let result = Self::probe(
+            &mut context,
+            &name,
+            path,
+            &options.features,
+            &mut details,
+            &mut outputs,
+        );
+        Self::group_teardown(&context, &mut outputs);
+
+        ProbeResult {
+            compiled: result != Err(Status::CompileFailed),
+            succeeded: result.is_ok(),
+            exit_code: details.exit_code,
+            variant: details.variant,
+            stdout: result.unwrap_or_default(),
+            diagnostics: details.diagnostics,
+            log: outputs.concat(),
+        }
+    }
+
+    /// Lists the tests in 'conf_tests/' without compiling or running anything. Useful for
+    /// tools and tests that validate the test inventory.
+    pub fn discover() -> Vec<ProbeInfo> {
+        Self::builder().discover()
+    }
+
+    pub(crate) fn discover_with(builder: &ConfTestBuilder) -> Vec<ProbeInfo> {
+        let manifest = Manifest::query(builder.manifest_dir.as_deref());
+        let catalog_features =
+            Self::catalog_features(builder, &manifest.features, &manifest.catalog);
+        let features = manifest.features;
+        let mut tests = Self::conf_tests(builder);
+        // validates the variant directives
+        let variants = Self::find_variants(&tests);
+
+        let mut catalog_dir = PathBuf::new();
+        catalog_dir.push(builder.conf_test_dir());
+        catalog_dir.push("catalog");
+        catalog::add_tests(
+            &mut tests,
+            catalog_features
+                .iter()
+                .filter(|feature| !variants.contains_key(*feature)),
+            &builder.catalogs(),
+            &catalog_dir,
+        );
+
+        tests
+            .into_iter()
+            .map(|(feature, path)| {
+                let directives = Directives::parse(&path);
+                let applicable = features.contains_key(&feature)
+                    || directives
+                        .variants
+                        .values()
+                        .any(|variant| features.contains_key(variant));
+                ProbeInfo {
+                    feature,
+                    path,
+                    directives,
+                    applicable,
+                }
+            })
+            .collect()
+    }
+
+    /// The path of 'conf_test.local.toml' in the crate directory.
+    fn local_config_path(builder: &ConfTestBuilder) -> PathBuf {
+        builder.crate_dir().join("conf_test.local.toml")
+    }
+
+    /// Handles `CONF_TEST_INHIBIT`, exits on 'stop' and 'fail', returns true on 'skip'.
+    fn inhibited() -> bool {
+        if let Some(inhibit) = env("CONF_TEST_INHIBIT") {
+            if inhibit == "skip" {
+                println!("cargo:warning=Skipping ConfTest via CONF_TEST_INHIBIT");
+                true
+            } else if inhibit == "stop" {
+                std::process::exit(0);
+            } else if inhibit == "fail" {
+                println!("cargo:warning=Requested ConfTest failure via CONF_TEST_INHIBIT");
+                std::process::exit(1)
+            } else {
+                // Bail on any unknown value to catch 'undefined' states/typos
+                panic!("Unknown CONF_TEST_INHIBIT value: {:?}", inhibit)
+            }
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn run_with(builder: &ConfTestBuilder) {
+        Self::execute(builder, true)
+    }
+
+    /// Runs the tests, the log goes to stdout as well when 'emit' is set.
+    pub(crate) fn execute(builder: &ConfTestBuilder, emit: bool) {
+        // the crate includes the values module unconditionally, it must exist even when
+        // inhibited
+        let mut values_file = PathBuf::new();
+        values_file.push(builder.build_dir());
+        values_file.push("conf_test");
+        DirBuilder::new()
+            .recursive(true)
+            .create(&values_file)
+            .expect("Failed to create output directory");
+        values_file.push("values.rs");
+        Values::default().write(&values_file);
+
+        if Self::inhibited() {
+            // queries about the last run must not see the results of an earlier one
+            *LAST_REPORT.lock().expect("ConfTest report poisoned") = None;
+            return;
+        }
+
+        let reproducible = builder.is_reproducible();
+        let mut outputs = Vec::new();
+        let run_phase = Phase::start("ConfTest run");
+        let started = if reproducible {
+            timing::source_date()
+        } else {
+            Some(timing::timestamp())
+        };
+        match &started {
+            Some(started) => outputs.push(format!("# [{}] ConfTest started\n", started)),
+            None => outputs.push("# ConfTest started\n".to_string()),
+        }
+        if builder.progress_events {
+            outputs.push(event::line("start", &[]));
+        }
+        outputs.push("cargo:rerun-if-env-changed=CONF_TEST_REPRODUCIBLE\n".to_string());
+        outputs.push("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH\n".to_string());
+
+        let target = Target::detect(builder.niche_target_policy);
+        let cross_file = match builder.cross_file.clone() {
+            Some(path) => Some(path),
+            None => {
+                let mut path = PathBuf::new();
+                path.push(builder.crate_dir());
+                path.push("conf_test.cross.toml");
+                Some(path).filter(|path| path.exists())
+            }
+        };
+        let mut assume = BTreeMap::new();
+        if let (true, Some(cross_file)) = (target.cross, cross_file) {
+            outputs.push(format!(
+                "# loading assumptions for {} from {}\n",
+                target.triple,
+                Self::shown_path(builder, &cross_file)
+            ));
+            outputs.push(format!(
+                "cargo:rerun-if-changed={}\n",
+                Self::relative_path(builder, &cross_file).display()
+            ));
+            assume = overrides::load_cross(&cross_file, &target);
+        }
+        assume.extend(
+            builder
+                .assume
+                .iter()
+                .map(|(feature, &enabled)| (feature.clone(), Forced::new(enabled, "build.rs"))),
+        );
+
+        outputs.push("cargo:rerun-if-env-changed=CONF_TEST_OVERRIDES\n".to_string());
+        let overrides_file = match env("CONF_TEST_OVERRIDES") {
+            Some(path) => Some(PathBuf::from(path)),
+            None => builder.overrides_file.clone(),
+        };
+        let overrides_file = match overrides_file {
+            Some(path) => Some(path),
+            None => {
+                let mut path = PathBuf::new();
+                path.push(builder.crate_dir());
+                path.push("conf_test.overrides.toml");
+                Some(path).filter(|path| path.exists())
+            }
+        };
+        if let Some(overrides_file) = overrides_file {
+            outputs.push(format!(
+                "# loading overrides from {}\n",
+                Self::shown_path(builder, &overrides_file)
+            ));
+            outputs.push(format!(
+                "cargo:rerun-if-changed={}\n",
+                Self::relative_path(builder, &overrides_file).display()
+            ));
+            assume.extend(overrides::load(&overrides_file));
+        }
+
+        let local_config = Self::local_config_path(builder);
+        let local_config = if local_config.exists() {
+            outputs.push(format!(
+                "# loading local config from {}\n",
+                Self::shown_path(builder, &local_config)
+            ));
+            outputs.push(format!(
+                "cargo:rerun-if-changed={}\n",
+                Self::relative_path(builder, &local_config).display()
+            ));
+            overrides::load_local(&local_config)
+        } else {
+            LocalConfig::default()
+        };
+        assume.extend(local_config.features);
+
+        outputs.push("cargo:rerun-if-env-changed=CONF_TEST_ASSUME\n".to_string());
+        outputs.push("cargo:rerun-if-env-changed=CONF_TEST_CHECK_MANUAL\n".to_string());
+        outputs.push("cargo:rerun-if-env-changed=CONF_TEST_TIMEOUT\n".to_string());
+        outputs.push("cargo:rerun-if-env-changed=CONF_TEST_VERBOSE\n".to_string());
+        outputs.push("cargo:rerun-if-env-changed=CONF_TEST_STRICT\n".to_string());
+        if let Some(assumptions) = env("CONF_TEST_ASSUME") {
+            assume.extend(overrides::parse_assume(&assumptions));
+        }
+
+        outputs.push(format!(
+            "# OUT_DIR is '{}'\n",
+            Self::shown_path(builder, &builder.build_dir())
+        ));
+
+        // make our output dir
+        let mut out_dir = PathBuf::new();
+        out_dir.push(builder.conf_test_dir());
+        DirBuilder::new()
+            .recursive(true)
+            .create(&out_dir)
+            .expect("Failed to create output directory");
+
+        let mut log_path = PathBuf::new();
+        log_path.push(builder.conf_test_dir());
+        log_path.push("conf_test.log");
+
+        match cargo::capabilities().version {
+            Some((major, minor)) => outputs.push(format!("# cargo version {}.{}\n", major, minor)),
+            None => outputs.push("# cargo version unknown\n".to_string()),
+        }
+
+        let metadata_phase = Phase::start("manifest");
+        let Manifest {
+            features,
+            dependencies,
+            build_dependencies,
+            edition,
+            probe_timeouts,
+            exit_codes,
+            probe_exit_codes,
+            probe_profiles,
+            workspace_root,
+            catalog,
+            ..
+        } = Manifest::query(builder.manifest_dir.as_deref());
+        let catalog_features = Self::catalog_features(builder, &features, &catalog);
+        let features: BTreeSet<String> = features.into_keys().collect();
+        outputs.push(metadata_phase.finish(reproducible));
+
+        let mut report = Report {
+            instances: builder.instance.iter().cloned().collect(),
+            ..Report::default()
+        };
+        // tests of the crate which failed to compile or could not be run, in strict mode
+        let mut strict_failures = Vec::new();
+        // tests using crates which are not build-dependencies, these fail the build
+        let mut missing_dependencies = Vec::new();
+
+        if env("DOCS_RS").is_some() {
+            outputs.push("# running on DOCS.RS\n".to_string());
+            if features.contains("docs_rs") {
+                outputs.extend(builder.rustc_cfgs("docs_rs"));
+                report.record("docs_rs", Status::Assumed(true));
+            }
+        } else {
+            // members of a workspace share the lockfile at its root
+            let mut lockfile = workspace_root;
+            lockfile.push("Cargo.lock");
+            let lockfile_exists = lockfile.exists();
+
+            outputs.push(format!(
+                "# Lockfile '{}' present: {}\n",
+                Self::shown_path(builder, &lockfile),
+                lockfile_exists
+            ));
+
+            if builder.skip_extern_crates {
+                outputs.push("# extern crates disabled\n".to_string());
+            }
+            let extern_phase = Phase::start("extern resolution");
+            let extern_libs = Self::get_extern_libs(builder, &build_dependencies);
+            outputs.push(extern_phase.finish(reproducible));
+
+            if !lockfile_exists {
+                outputs.push(format!(
+                    "# Delete Lockfile: '{}', {}\n",
+                    Self::shown_path(builder, &lockfile),
+                    std::fs::remove_file(&lockfile).is_ok()
+                ));
+            }
+
+            let c_compiler = CCompiler::discover();
+            match &c_compiler {
+                Some(c_compiler) => outputs.push(format!(
+                    "# C compiler: {} ({})\n",
+                    c_compiler.path.display(),
+                    c_compiler.version
+                )),
+                None => outputs.push("# no working C compiler found\n".to_string()),
+            }
+            report.c_compiler = c_compiler.clone();
+
+            *RESOLVED.lock().expect("ConfTest resolution poisoned") = Some(Resolved {
+                edition: edition.clone(),
+                extern_libs: extern_libs.clone(),
+                dependencies: dependencies.clone(),
+                build_dependencies: build_dependencies.clone(),
+                c_compiler: c_compiler.clone(),
+                probe_timeouts: probe_timeouts.clone(),
+                exit_codes: exit_codes.clone(),
+                probe_exit_codes: probe_exit_codes.clone(),
+                probe_profiles: probe_profiles.clone(),
+            });
+
+            for var in target::ANDROID_API_VARS {
+                outputs.push(format!("cargo:rerun-if-env-changed={}\n", var));
+            }
+            if target.os == "android" {
+                match target.android_api {
+                    Some(level) => outputs.push(format!("# Android API level {}\n", level)),
+                    None => outputs.push("# Android API level unknown\n".to_string()),
+                }
+            }
+            if target.niche {
+                outputs.push(format!("# {} is a niche target\n", target.triple));
+            }
+            if target.compile_only {
+                outputs.push(format!(
+                    "# cross building for {}, tests are compiled but not executed\n",
+                    target.triple
+                ));
+            } else if target.assume_disabled {
+                outputs.push(format!(
+                    "# cross building for {}, tests are not run, their features are assumed disabled\n",
+                    target.triple
+                ));
+            }
+
+            let profile = Profile::current();
+            outputs.push(format!("# {} build\n", profile.name()));
+            if let Some(instance) = &builder.instance {
+                outputs.push(format!("# instance {}\n", instance));
+            }
+
+            outputs.push("cargo:rerun-if-env-changed=CONF_TEST_SEED\n".to_string());
+            let seed = Self::seed(builder);
+            outputs.push(format!(
+                "# seed for randomized tests {}, set CONF_TEST_SEED={} to repeat\n",
+                seed, seed
+            ));
+            report.seed = Some(seed);
+
+            let mut context = Context {
+                builder,
+                edition,
+                extern_libs,
+                dependencies,
+                build_dependencies,
+                c_compiler,
+                env: local_config.env,
+                out_dir: out_dir.clone(),
+                probe_timeouts,
+                exit_codes,
+                probe_exit_codes,
+                probe_profiles,
+                profile,
+                target,
+                seed,
+                groups: BTreeMap::new(),
+            };
+
+            outputs.push("cargo:rerun-if-env-changed=CONF_TEST_NO_CACHE\n".to_string());
+            let mut cache_file = PathBuf::new();
+            cache_file.push(builder.conf_test_dir());
+            cache_file.push("cache.toml");
+            let mut cache = if !builder.cache {
+                None
+            } else if env("CONF_TEST_NO_CACHE").is_some() {
+                outputs.push("# cache disabled\n".to_string());
+                None
+            } else {
+                let fingerprint = cache::fingerprint(&context.env);
+                let (cache, invalidated) = Cache::load(&cache_file, fingerprint);
+                if invalidated {
+                    outputs.push("# fingerprint changed, cached results discarded\n".to_string());
+                }
+                Some(cache)
+            };
+
+            let mut test_features = Vec::new();
+
+            for feature in assume.keys() {
+                if !features.contains(feature) {
+                    outputs.push(format!(
+                        "cargo:warning=Assumed feature '{}' is not defined\n",
+                        feature
+                    ));
+                }
+            }
+
+            let conf_tests_dir = builder.conf_tests_dir();
+            if !builder.skip_conf_tests && !conf_tests_dir.is_dir() {
+                let shown = Self::relative_path(builder, &conf_tests_dir);
+                match builder.missing_tests_policy {
+                    MissingTestsPolicy::Silent => {
+                        outputs.push(format!("# {} does not exist\n", shown.display()))
+                    }
+                    MissingTestsPolicy::Warn => outputs.push(format!(
+                        "cargo:warning=ConfTest directory {} does not exist\n",
+                        shown.display()
+                    )),
+                    MissingTestsPolicy::Error => panic!(
+                        "ConfTest directory {} does not exist, see ConfTestBuilder::missing_tests_policy()",
+                        shown.display()
+                    ),
+                }
+            }
+            let mut tests = Self::conf_tests(builder);
+
+            let variants = Self::find_variants(&tests);
+            let catalog_origins = catalog::add_tests(
+                &mut tests,
+                catalog_features
+                    .iter()
+                    .filter(|feature| !variants.contains_key(*feature)),
+                &builder.catalogs(),
+                &out_dir.join("catalog"),
+            );
+            for (name, test_src) in &tests {
+                for (variant, _) in variants.iter().filter(|(_, src)| *src == test_src) {
+                    report.add_edge(name, variant, "variant");
+                }
+            }
+
+            let mut variant_results = BTreeMap::new();
+
+            for feature in features.iter().cloned() {
+                let test_src = tests.get(&feature).or_else(|| variants.get(&feature));
+                if let Some(description) =
+                    test_src.and_then(|test_src| Directives::parse(test_src).description)
+                {
+                    report.descriptions.insert(feature.clone(), description);
+                }
+
+                if env(format!("CARGO_FEATURE_{}", feature.to_uppercase())).is_none() {
+                    outputs.push(format!("# checking for {}\n", report.described(&feature)));
+                    if let Some(forced) = assume.get(&feature) {
+                        outputs.push(format!(
+                            "# test for '{}' assumed to be {} by {}\n",
+                            &feature,
+                            if forced.enabled {
+                                "enabled"
+                            } else {
+                                "disabled"
+                            },
+                            forced.origin
+                        ));
+                        if forced.enabled {
+                            outputs.extend(builder.rustc_cfgs(&feature));
+                            let output = TestOutput::parse(&forced.output);
+                            outputs.push(output.cargo.clone());
+                            Self::export(&mut context, &feature, &output, &mut outputs);
+                            report.record_output(&feature, output);
+                            test_features.push(feature.clone());
+                        }
+                        report.record(&feature, Status::Assumed(forced.enabled));
+                    } else if !builder.is_selected(&feature) {
+                        outputs.push(format!(
+                            "# test for '{}' not selected by the feature filters\n",
+                            &feature
+                        ));
+                        report.record(&feature, Status::Untested);
+                    } else if let Some(enabled) = test_src.and_then(|test_src| {
+                        builder.hooks.probe_start(&ProbeContext {
+                            feature: &feature,
+                            source: test_src,
+                            enabled: &test_features,
+                        })
+                    }) {
+                        outputs.push(format!(
+                            "# test for '{}' decided to be {} by an on_probe_start hook\n",
+                            &feature,
+                            if enabled { "enabled" } else { "disabled" }
+                        ));
+                        if enabled {
+                            outputs.extend(builder.rustc_cfgs(&feature));
+                            test_features.push(feature.clone());
+                        }
+                        report.record(&feature, Status::Assumed(enabled));
+                    } else if let Some(test_src) = test_src {
+                        if test_src.starts_with(&out_dir) {
+                            // written by us, changes only with conf_test itself
+                            outputs.push(format!(
+                                "# using catalog test for {} from {}\n",
+                                &feature, catalog_origins[&feature]
+                            ));
+                        } else {
+                            outputs.push(format!("# {} exists\n", test_src.display()));
+                            outputs
+                                .push(format!("cargo:rerun-if-changed={}\n", test_src.display()));
+                        }
+                        Self::rerun_if_fixtures_changed(builder, test_src, &mut outputs);
+                        let hash = Self::test_hash(&context, &feature, test_src, &test_features);
+                        let enabled_before = test_features.len();
+                        // tests see the features that come before them as '--cfg'
+                        for before in &test_features {
+                            report.add_edge(before, &feature, "compiled with");
+                        }
+                        for required in Directives::parse(test_src).requires {
+                            if !features.contains(&required) {
+                                panic!(
+                                    "ConfTest for {} requires '{}' which is not a feature defined in Cargo.toml",
+                                    feature, required
+                                );
+                            }
+                            if !report.features.contains_key(&required) {
+                                // probe() reports the test as skipped, unless cargo enabled it
+                                outputs.push(format!(
+                                    "# ConfTest for {} requires {} which is not tested before it\n",
+                                    feature, required
+                                ));
+                            }
+                            report.add_edge(&required, &feature, "requires");
+                        }
+                        let result = if !Directives::parse(test_src).variants.is_empty() {
+                            // variant tests run once and select one of their variants
+                            let (result, details) =
+                                variant_results.entry(test_src.clone()).or_insert_with(|| {
+                                    let name = tests
+                                        .iter()
+                                        .find(|(_, src)| *src == test_src)
+                                        .map(|(name, _)| name.as_str())
+                                        .expect("variant test not found");
+                                    let mut details = ProbeDetails::default();
+                                    let result = Self::probe(
+                                        &mut context,
+                                        name,
+                                        test_src,
+                                        &test_features,
+                                        &mut details,
+                                        &mut outputs,
+                                    );
+                                    (result, details)
+                                });
+                            report.details.insert(feature.clone(), details.clone());
+                            match result {
+                                Ok(_) if details.variant.as_deref() != Some(&feature) => {
+                                    Err(Status::Unselected)
+                                }
+                                result => result.clone(),
+                            }
+                        } else if let Some(stdout) =
+                            cache.as_ref().and_then(|c| c.get(&feature, &hash))
+                        {
+                            outputs.push(format!("# using cached result for {}\n", &feature));
+                            report.details.insert(
+                                feature.clone(),
+                                ProbeDetails {
+                                    cached: true,
+                                    ..ProbeDetails::default()
+                                },
+                            );
+                            Ok(stdout)
+                        } else {
+                            let mut details = ProbeDetails::default();
+                            let result = Self::probe(
+                                &mut context,
+                                &feature,
+                                test_src,
+                                &test_features,
+                                &mut details,
+                                &mut outputs,
+                            );
+                            // compiled again until the dependency is added
+                            if let (Some(cache), None) = (&mut cache, &details.missing_dependency) {
+                                cache.insert(&feature, hash, &result);
+                            }
+                            report.details.insert(feature.clone(), details);
+                            result
+                        };
+                        if let Some(name) = report
+                            .details
+                            .get(&feature)
+                            .and_then(|details| details.missing_dependency.as_ref())
+                        {
+                            missing_dependencies.push(format!("{} uses '{}'", feature, name));
+                        }
+                        match result {
+                            Ok(stdout) => {
+                                let output = TestOutput::parse(&stdout);
+                                outputs.extend(builder.rustc_cfgs(&feature));
+                                outputs.push(output.cargo.clone());
+                                Self::export(&mut context, &feature, &output, &mut outputs);
+                                report.record_output(&feature, output);
+                                test_features.push(feature.clone());
+                                report.record(&feature, Status::Enabled);
+                            }
+                            Err(status) => report.record(&feature, status),
+                        }
+                        if let Some(matrix) = builder.matrices.get(&feature) {
+                            let combinations = Self::probe_matrix(
+                                &mut context,
+                                &feature,
+                                test_src,
+                                &test_features[..enabled_before],
+                                matrix,
+                                &mut outputs,
+                            );
+                            report.combinations.insert(feature.clone(), combinations);
+                        }
+                        let status = report.features[&feature];
+                        let details = report.details.get(&feature);
+                        builder.hooks.probe_end(
+                            &ProbeContext {
+                                feature: &feature,
+                                source: test_src,
+                                enabled: &test_features[..enabled_before],
+                            },
+                            &ProbeOutcome {
+                                enabled: status.is_enabled(),
+                                outcome: status.outcome(),
+                                status: status.description(),
+                                cached: details.is_some_and(|details| details.cached),
+                                compile_time: details.and_then(|details| details.compile_time),
+                                run_time: details.and_then(|details| details.run_time),
+                                exit_code: details.and_then(|details| details.exit_code),
+                                diagnostics: details
+                                    .map(|details| details.diagnostics.as_str())
+                                    .unwrap_or_default(),
+                            },
+                        );
+                    } else {
+                        outputs.push(format!("# test for '{}' does not exist\n", &feature));
+                        report.record(&feature, Status::Untested);
+                    }
+                } else {
+                    outputs.push(format!("# test for '{}' manually overridden\n", &feature));
+                    for style in builder.cfg_styles() {
+                        if *style != CfgStyle::Feature {
+                            outputs.push(style.rustc_cfg(&feature));
+                        }
+                    }
+                    report.record(&feature, Status::Manual);
+                    if builder.manual_features_in_tests {
+                        test_features.push(feature.clone());
+                    }
+                    if let (Some(_), Some(test_src)) = (env("CONF_TEST_CHECK_MANUAL"), test_src) {
+                        // dry run, the outputs of the test are discarded
+                        outputs.push(format!("cargo:rerun-if-changed={}\n", test_src.display()));
+                        Self::rerun_if_fixtures_changed(builder, test_src, &mut outputs);
+                        let mut details = ProbeDetails::default();
+                        let result = Self::probe(
+                            &mut context,
+                            &feature,
+                            test_src,
+                            &test_features,
+                            &mut details,
+                            &mut outputs,
+                        );
+                        let selected = details
+                            .variant
+                            .as_ref()
+                            .is_none_or(|variant| *variant == feature);
+                        if result.is_ok() && selected {
+                            outputs.push(format!(
+                                "# ConfTest for manual '{}' would have been enabled\n",
+                                &feature
+                            ));
+                        } else {
+                            outputs.push(format!(
+                                "# ConfTest for manual '{}' would have been disabled\n",
+                                &feature
+                            ));
+                            outputs.push(format!(
+                                "cargo:warning=Feature '{}' is set manually but its ConfTest fails\n",
+                                report.described(&feature)
+                            ));
+                        }
+                    }
+                }
+                if let (Some(description), Some(status)) = (
+                    report.descriptions.get(&feature),
+                    report.features.get(&feature),
+                ) {
+                    outputs.push(format!(
+                        "# checking for {} ({})... {}, {}\n",
+                        &feature,
+                        description,
+                        status.outcome().answer(),
+                        status.description()
+                    ));
+                }
+                if let (true, Some(status)) =
+                    (builder.progress_events, report.features.get(&feature))
+                {
+                    outputs.push(event::line(
+                        "probe",
+                        &[
+                            ("feature", &feature),
+                            ("status", status.name()),
+                            ("outcome", status.outcome().name()),
+                        ],
+                    ));
+                }
+                outputs.push(String::from("\n"));
+            }
+
+            Self::group_teardown(&context, &mut outputs);
+
+            if builder.is_strict() {
+                strict_failures = report
+                    .with_status(&[Status::CompileFailed, Status::Inconclusive])
+                    .into_iter()
+                    .filter(|feature| !catalog_origins.contains_key(*feature))
+                    .map(String::from)
+                    .collect();
+            }
+
+            if let Some(cache) = &cache {
+                cache.save();
+            }
+
+            builder.hooks.finish(
+                &report
+                    .features
+                    .iter()
+                    .map(|(feature, status)| (feature.clone(), status.is_enabled()))
+                    .collect(),
+            );
+
+            outputs.extend(report.rustc_env());
+            outputs.extend(report.link_args());
+
+            for style in builder.cfg_styles() {
+                outputs.extend(style.rustc_check_cfg(report.features.keys()));
+            }
+            outputs.extend(report.summary());
+            outputs.extend(report.inconclusive());
+            if builder.warn_sandboxed {
+                outputs.extend(report.sandboxed());
+            }
+
+            let mut report_file = PathBuf::new();
+            report_file.push(builder.conf_test_dir());
+            report_file.push("report.toml");
+            outputs.extend(report.regressions(&Report::load_previous(&report_file)));
+            // files generated from the report, files of the previous run not written again
+            // are stale
+            let mut artifacts = Vec::new();
+
+            if builder.markdown_report {
+                let mut markdown_file = PathBuf::new();
+                markdown_file.push(builder.conf_test_dir());
+                markdown_file.push("features.md");
+                atomic::write(&markdown_file, markdown::render(&report))
+                    .expect("Failed to write Markdown report");
+                outputs.push(format!(
+                    "# Markdown report written to {}\n",
+                    Self::shown_path(builder, &markdown_file)
+                ));
+                artifacts.push(markdown_file);
+            }
+
+            outputs.push("cargo:rerun-if-env-changed=CONF_TEST_REPORT\n".to_string());
+            if let Some(json_file) = builder.json_report_path() {
+                if let Some(parent) = json_file.parent() {
+                    DirBuilder::new()
+                        .recursive(true)
+                        .create(parent)
+                        .expect("Failed to create JSON report directory");
+                }
+                let records: Vec<String> = Self::probe_records(&report, reproducible)
+                    .iter()
+                    .map(ProbeRecord::json)
+                    .collect();
+                atomic::write(
+                    &json_file,
+                    format!(
+                        "{{\"schema\":{},\"seed\":{},\"features\":[{}]}}\n",
+                        event::json_string(event::SCHEMA),
+                        context.seed,
+                        records.join(",")
+                    ),
+                )
+                .unwrap_or_else(|err| {
+                    panic!(
+                        "Failed to write JSON report to {}: {}",
+                        json_file.display(),
+                        err
+                    )
+                });
+                outputs.push(format!(
+                    "# JSON report written to {}\n",
+                    Self::shown_path(builder, &json_file)
+                ));
+                artifacts.push(json_file);
+            }
+
+            if builder.github_actions {
+                // the files change with every step, the results go to each of them
+                outputs.push("cargo:rerun-if-env-changed=GITHUB_OUTPUT\n".to_string());
+                outputs.push("cargo:rerun-if-env-changed=GITHUB_STEP_SUMMARY\n".to_string());
+                let package = std::env::var("CARGO_PKG_NAME").unwrap_or_default();
+                for file in github::write(&report, &package) {
+                    outputs.push(format!(
+                        "# GitHub Actions results written to {}\n",
+                        file.display()
+                    ));
+                }
+            }
+
+            if builder.html_report {
+                let mut html_file = PathBuf::new();
+                html_file.push(builder.conf_test_dir());
+                html_file.push("report.html");
+                atomic::write(
+                    &html_file,
+                    html::render(&report, started.as_deref(), !reproducible),
+                )
+                .expect("Failed to write HTML report");
+                outputs.push(format!(
+                    "# HTML report written to {}\n",
+                    Self::shown_path(builder, &html_file)
+                ));
+                artifacts.push(html_file);
+            }
+
+            if builder.dot_graph {
+                let mut dot_file = PathBuf::new();
+                dot_file.push(builder.conf_test_dir());
+                dot_file.push("features.dot");
+                atomic::write(&dot_file, dot::render(&report)).expect("Failed to write DOT graph");
+                outputs.push(format!(
+                    "# DOT graph written to {}\n",
+                    Self::shown_path(builder, &dot_file)
+                ));
+                artifacts.push(dot_file);
+            }
+
+            Self::remove_stale_artifacts(builder, &report_file, &artifacts, &mut outputs);
+            report.save(&report_file, &artifacts);
+        }
+
+        let mut values = Values::default();
+        if builder.host_info {
+            values.add_host_info();
+        }
+        if builder.host_parallelism {
+            values.add_host_parallelism();
+        }
+        values.write(&values_file);
 
-use std::io::prelude::*;
+        if builder.progress_events {
+            let enabled = report.enabled().collect::<Vec<_>>().join(",");
+            outputs.push(event::line("finish", &[("enabled", &enabled)]));
+        }
+        outputs.push(run_phase.finish(reproducible));
 
-use std::env::var_os as env;
-use std::path::{Path, PathBuf};
-use std::str;
+        let capabilities = cargo::capabilities();
+        let mut log = String::new();
+        // errors cargo does not understand end up as warnings, failing the build is left to us
+        let mut errors = Vec::new();
+        for line in outputs.iter().flat_map(|output| output.lines()) {
+            if let (false, Some(message)) = (capabilities.error, cargo::error_message(line)) {
+                errors.push(message.to_string());
+            }
+            let line = cargo::directive(line, &capabilities);
+            log.push_str(&line);
+            log.push('\n');
+            if emit && (!builder.directives_only || line.starts_with("cargo:")) {
+                println!("{}", line);
+            }
+        }
+        atomic::write(&log_path, log).expect("Failed to write logfile");
 
-use cargo_metadata::{Edition, Message, MetadataCommand};
-use std::process::{Command, Stdio};
+        if builder.log_history > 0 {
+            Self::keep_log_history(&log_path, builder.log_history);
+        }
 
-use std::collections::{BTreeMap, BTreeSet};
+        for sink in &builder.sinks {
+            for record in Self::probe_records(&report, reproducible) {
+                sink.record(&record);
+            }
+            sink.finish();
+        }
 
-// Empty Type for now, In future this may be extended without breaking existing code.
-/// Implements the conf_test API
-pub enum ConfTest {}
+        let inconclusive = report.with_status(&[Status::Inconclusive]).join(", ");
+        Self::store_report(report);
+        if builder.fail_on_inconclusive && !inconclusive.is_empty() {
+            panic!("ConfTest could not run the tests for: {}", inconclusive);
+        }
+        if emit && !errors.is_empty() {
+            panic!("ConfTest tests reported errors: {}", errors.join("; "));
+        }
+        if !missing_dependencies.is_empty() {
+            panic!(
+                "ConfTest tests use crates which are not build-dependencies, add them to [build-dependencies] in Cargo.toml: {}",
+                missing_dependencies.join(", ")
+            );
+        }
+        if !strict_failures.is_empty() {
+            panic!(
+                "ConfTest in strict mode, tests failed to compile or could not be run: {}",
+                strict_failures.join(", ")
+            );
+        }
+    }
 
-impl ConfTest {
-    /// Run the configuration tests in 'conf_tests/'.
-    #[allow(dead_code)]
-    pub fn run() {
-        if let Some(inhibit) = env("CONF_TEST_INHIBIT") {
-            if inhibit == "skip" {
-                println!("cargo:warning=Skipping ConfTest via CONF_TEST_INHIBIT");
-                return;
-            } else if inhibit == "stop" {
-                std::process::exit(0);
-            } else if inhibit == "fail" {
-                println!("cargo:warning=Requested ConfTest failure via CONF_TEST_INHIBIT");
-                std::process::exit(1)
-            } else {
-                // Bail on any unknown value to catch 'undefined' states/typos
-                panic!("Unknown CONF_TEST_INHIBIT value: {:?}", inhibit)
+    /// Keeps 'report' for the queries about the last run. The reports of named instances are
+    /// merged, unless the same instance ran again.
+    fn store_report(report: Report) {
+        let mut last = LAST_REPORT.lock().expect("ConfTest report poisoned");
+        match last.as_mut() {
+            Some(previous)
+                if !report.instances.is_empty()
+                    && !previous.instances.is_empty()
+                    && previous.instances.is_disjoint(&report.instances) =>
+            {
+                previous.merge(report)
             }
+            _ => *last = Some(report),
         }
+    }
 
-        let mut outputs = Vec::new();
+    /// The seed for randomized tests from `CONF_TEST_SEED`, else a fresh one for each run or
+    /// 0 in reproducible runs.
+    fn seed(builder: &ConfTestBuilder) -> u64 {
+        match std::env::var("CONF_TEST_SEED") {
+            Ok(seed) => seed
+                .parse()
+                .unwrap_or_else(|_| panic!("Invalid CONF_TEST_SEED: {:?}", seed)),
+            Err(_) if builder.is_reproducible() => 0,
+            Err(_) => std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|now| now.as_nanos() as u64)
+                .unwrap_or_default(),
+        }
+    }
 
-        outputs.push(format!(
-            "# OUT_DIR is '{:?}'\n",
-            env("OUT_DIR").expect("env var OUT_DIR is not set")
-        ));
+    /// The records of all features for report sinks and the JSON report, timings are left out
+    /// in reproducible runs.
+    fn probe_records(report: &Report, reproducible: bool) -> Vec<ProbeRecord> {
+        report
+            .features
+            .iter()
+            .map(|(feature, status)| {
+                let details = report.details.get(feature);
+                let timing = |duration: Option<Duration>| duration.filter(|_| !reproducible);
+                ProbeRecord {
+                    feature: feature.clone(),
+                    status: status.name(),
+                    enabled: status.is_enabled(),
+                    outcome: status.outcome(),
+                    cached: details.is_some_and(|details| details.cached),
+                    compile_time: timing(details.and_then(|details| details.compile_time)),
+                    run_time: timing(details.and_then(|details| details.run_time)),
+                }
+            })
+            .collect()
+    }
 
-        // make our output dir
-        let mut out_dir = PathBuf::new();
-        out_dir.push(env("OUT_DIR").unwrap());
-        out_dir.push("conf_test");
+    /// 'path' relative to the crate directory in reproducible runs, cargo and the tests resolve
+    /// relative paths from there.
+    fn relative_path(builder: &ConfTestBuilder, path: &Path) -> PathBuf {
+        if builder.is_reproducible() {
+            path.strip_prefix(builder.crate_dir())
+                .unwrap_or(path)
+                .to_path_buf()
+        } else {
+            path.to_path_buf()
+        }
+    }
+
+    /// How 'path' is shown in the log, reproducible runs show paths below 'OUT_DIR' as
+    /// '$OUT_DIR/...' and paths below the crate relative to it.
+    fn shown_path(builder: &ConfTestBuilder, path: &Path) -> String {
+        match builder.build_dir() {
+            out_dir if builder.is_reproducible() && path.starts_with(&out_dir) => {
+                match path.strip_prefix(&out_dir) {
+                    Ok(rest) if !rest.as_os_str().is_empty() => {
+                        format!("$OUT_DIR/{}", rest.display())
+                    }
+                    _ => "$OUT_DIR".to_string(),
+                }
+            }
+            _ => Self::relative_path(builder, path).display().to_string(),
+        }
+    }
+
+    /// Copies the log of this run to 'conf_test/logs/' and removes all but the 'history' most
+    /// recent logs there.
+    fn keep_log_history(log_path: &Path, history: usize) {
+        let logs_dir = log_path.with_file_name("logs");
         DirBuilder::new()
             .recursive(true)
-            .create(out_dir)
-            .expect("Failed to create output directory");
+            .create(&logs_dir)
+            .expect("Failed to create logs directory");
 
-        let mut logfile = PathBuf::new();
-        logfile.push(env("OUT_DIR").unwrap());
-        logfile.push("conf_test");
-        logfile.push("conf_test.log");
-        let mut logfile = File::create(logfile).expect("Failed to create logfile");
+        // timestamps sort chronologically, colons are not allowed in file names on windows,
+        // 'SOURCE_DATE_EPOCH' would give every run the same name
+        let name = format!("conf_test-{}.log", timing::wall_clock().replace(':', "-"));
+        std::fs::copy(log_path, logs_dir.join(name)).expect("Failed to copy logfile");
 
-        let metadata = MetadataCommand::new()
-            .other_options(["--frozen".to_string()])
-            .no_deps()
-            .exec()
-            .expect("Querying cargo metadata failed");
+        let mut logs: Vec<PathBuf> = std::fs::read_dir(&logs_dir)
+            .expect("Failed to read logs directory")
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| path.extension().and_then(OsStr::to_str) == Some("log"))
+            .collect();
+        logs.sort();
+        for old in &logs[..logs.len().saturating_sub(history)] {
+            std::fs::remove_file(old).expect("Failed to remove old logfile");
+        }
+    }
 
-        let mut features = BTreeSet::new();
-        let mut dependencies = BTreeSet::new();
-        let mut edition: Option<Edition> = None;
-        for package in metadata.packages {
-            if edition == None {
-                // just pick the first edition seen
-                edition = Some(package.edition);
-            }
-            for (feature, _) in package.features {
-                features.insert(feature);
+    /// Removes the files the previous run generated from its report which were not written
+    /// again by this run, they still claim the results of the previous run.
+    fn remove_stale_artifacts(
+        builder: &ConfTestBuilder,
+        report_file: &Path,
+        artifacts: &[PathBuf],
+        outputs: &mut Vec<String>,
+    ) {
+        for stale in Report::previous_artifacts(report_file) {
+            if artifacts.contains(&stale) {
+                continue;
             }
-            for dep in package.dependencies {
-                dependencies.insert(dep.name);
+            match std::fs::remove_file(&stale) {
+                Ok(()) => outputs.push(format!(
+                    "# removed stale {}\n",
+                    Self::shown_path(builder, &stale)
+                )),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => outputs.push(format!(
+                    "cargo:warning=Failed to remove stale {}: {}\n",
+                    stale.display(),
+                    err
+                )),
             }
         }
+    }
 
-        if env("DOCS_RS").is_some() {
-            outputs.push("# running on DOCS.RS\n".to_string());
-            if features.contains("docs_rs") {
-                outputs.push("cargo:rustc-cfg=feature=\"docs_rs\"\n".to_string());
+    /// The features opted in for catalog tests by the builder and by the package 'metadata'.
+    /// Panics for features that are not declared in 'Cargo.toml' or that enable a dependency,
+    /// enabling their cfg would claim a dependency that is not built.
+    fn catalog_features(
+        builder: &ConfTestBuilder,
+        features: &BTreeMap<String, Vec<String>>,
+        metadata: &BTreeSet<String>,
+    ) -> BTreeSet<String> {
+        let opted_in: BTreeSet<String> = builder
+            .catalog_features
+            .iter()
+            .chain(metadata)
+            .cloned()
+            .collect();
+        for feature in &opted_in {
+            match features.get(feature) {
+                None => panic!(
+                    "Feature '{}' opted in for catalog tests is not declared in Cargo.toml",
+                    feature
+                ),
+                Some(enables) if enables.iter().any(|enable| enable.starts_with("dep:")) => {
+                    panic!(
+                        "Feature '{}' enables a dependency, it can not be probed by a catalog test",
+                        feature
+                    )
+                }
+                Some(_) => {}
             }
+        }
+        opted_in
+    }
+
+    /// The tests in 'conf_tests/', none when filesystem probes are disabled, and the template
+    /// probes.
+    fn conf_tests(builder: &ConfTestBuilder) -> BTreeMap<String, PathBuf> {
+        let mut tests = if builder.skip_conf_tests {
+            BTreeMap::new()
         } else {
-            let edition = edition.unwrap_or_else(|| Edition::E2021);
+            Self::find_tests(
+                &builder.conf_tests_dir(),
+                builder.category_separator.as_deref().unwrap_or("_"),
+            )
+        };
+        // relative to the crate directory, which may be set after the probe
+        for (feature, (path, _)) in &builder.template_probes {
+            if let Some(other) = tests.insert(feature.clone(), builder.crate_dir().join(path)) {
+                panic!(
+                    "Feature '{}' has the test {} and a template probe",
+                    feature,
+                    other.display()
+                );
+            }
+        }
+        tests
+    }
 
-            let mut lockfile = PathBuf::new();
-            lockfile
-                .push(env("CARGO_MANIFEST_DIR").expect("env var CARGO_MANIFEST_DIR is not set"));
-            lockfile.push("Cargo.lock");
-            let lockfile_exists = lockfile.exists();
+    /// The variables for the template of the test for 'feature': the environment of the tests
+    /// with the values exported by the tests before, overridden by the variables of the
+    /// builder and these by the variables of the template probe. `FEATURE` is the feature.
+    /// None when the test is no template.
+    fn template_vars(
+        context: &Context,
+        feature: &str,
+        directives: &Directives,
+    ) -> Option<BTreeMap<String, String>> {
+        let probe = context.builder.template_probes.get(feature);
+        if !directives.template && probe.is_none() {
+            return None;
+        }
+        let mut vars = context.env.clone();
+        vars.extend(context.builder.template_vars.clone());
+        if let Some((_, probe_vars)) = probe {
+            vars.extend(probe_vars.clone());
+        }
+        vars.insert("FEATURE".to_string(), feature.to_string());
+        Some(vars)
+    }
+
+    /// Finds the tests in 'dir' and its subdirectories. Tests in subdirectories get the names
+    /// of the directories joined by 'separator' as prefix for their feature name.
+    fn find_tests(dir: &Path, separator: &str) -> BTreeMap<String, PathBuf> {
+        let mut tests = BTreeMap::new();
+        Self::find_tests_in(dir, "", separator, &mut tests);
+        tests
+    }
+
+    fn find_tests_in(
+        dir: &Path,
+        prefix: &str,
+        separator: &str,
+        tests: &mut BTreeMap<String, PathBuf>,
+    ) {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+
+        for entry in entries {
+            let path = entry.expect("Failed to read conf_tests directory").path();
+            let name = match path.file_stem().and_then(OsStr::to_str) {
+                Some(name) => format!("{}{}", prefix, name),
+                None => continue,
+            };
+
+            if path.is_dir() {
+                Self::find_tests_in(&path, &format!("{}{}", name, separator), separator, tests);
+            } else if path.extension().and_then(OsStr::to_str) == Some("rs") {
+                if let Some(other) = tests.insert(name.clone(), path.clone()) {
+                    panic!(
+                        "Tests {} and {} both define feature '{}'",
+                        other.display(),
+                        path.display(),
+                        name
+                    );
+                }
+            }
+        }
+    }
+
+    /// Finds the variant features selected by the exit codes of 'tests', mapped to the test
+    /// selecting them.
+    fn find_variants(tests: &BTreeMap<String, PathBuf>) -> BTreeMap<String, PathBuf> {
+        let mut variants = BTreeMap::new();
+        for (name, test_src) in tests {
+            for feature in Directives::parse(test_src).variants.into_values() {
+                if feature == *name {
+                    continue;
+                }
+                if let Some(other) = tests.get(&feature) {
+                    panic!(
+                        "Feature '{}' has its own test {} and is a variant in {}",
+                        feature,
+                        other.display(),
+                        test_src.display()
+                    );
+                }
+                if let Some(other) = variants.insert(feature.clone(), test_src.clone()) {
+                    if other != *test_src {
+                        panic!(
+                            "Tests {} and {} both select variant '{}'",
+                            other.display(),
+                            test_src.display(),
+                            feature
+                        );
+                    }
+                }
+            }
+        }
+        variants
+    }
+
+    /// Adds the variables exported by the test for 'feature' to the environment of the following
+    /// tests.
+    fn export(
+        context: &mut Context,
+        feature: &str,
+        output: &TestOutput,
+        outputs: &mut Vec<String>,
+    ) {
+        for (key, value) in &output.exports {
+            outputs.push(format!("# {} exports {}={}\n", feature, key, value));
+            context.env.insert(key.clone(), value.clone());
+        }
+    }
 
+    /// Lets cargo rerun the build script when one of the fixtures of a test or the setup of
+    /// its group changes.
+    fn rerun_if_fixtures_changed(
+        builder: &ConfTestBuilder,
+        test_src: &Path,
+        outputs: &mut Vec<String>,
+    ) {
+        let directives = Directives::parse(test_src);
+        for fixture in directives.fixtures {
+            outputs.push(format!("cargo:rerun-if-changed={}\n", fixture.display()));
+        }
+        if let Some(group) = directives.group {
             outputs.push(format!(
-                "# Lockfile '{:?}' present: {}\n",
-                lockfile, lockfile_exists
+                "cargo:rerun-if-changed={}\n",
+                Self::relative_path(builder, &Self::group_src(builder, &group)).display()
             ));
+        }
+    }
+
+    /// The source of the setup of 'group'.
+    fn group_src(builder: &ConfTestBuilder, group: &str) -> PathBuf {
+        builder.groups_dir().join(format!("{}.rs", group))
+    }
 
-            let extern_libs = Self::get_extern_libs(&dependencies);
+    /// Runs the setup of 'group' unless that happened already, returns the directory with its
+    /// artifacts and the setup binary. The directory is emptied before. When the setup fails
+    /// to compile or to run the tests of the group can not be run, it is not tried again.
+    fn group_setup(
+        context: &mut Context,
+        group: &str,
+        outputs: &mut Vec<String>,
+    ) -> Result<(PathBuf, PathBuf), Status> {
+        if let Some(result) = context.groups.get(group) {
+            return result.clone();
+        }
 
-            if !lockfile_exists {
+        let src = Self::group_src(context.builder, group);
+        if !src.exists() {
+            panic!(
+                "ConfTest group '{}' has no setup in {}",
+                group,
+                src.display()
+            );
+        }
+        let mut dir = PathBuf::new();
+        dir.push(context.builder.conf_test_dir());
+        dir.push("groups");
+        dir.push(group);
+        let _ = std::fs::remove_dir_all(&dir);
+        DirBuilder::new()
+            .recursive(true)
+            .create(&dir)
+            .expect("Failed to create group directory");
+
+        let directives = Directives::parse(&src);
+        let timeout = Self::timeout(context, group, &directives);
+        let phase = Phase::start(format!("compiling setup of group {}", group));
+        let mut diagnostics = String::new();
+        let binary = Self::compile_test(
+            context,
+            &format!("{}.group", group),
+            &src,
+            &directives,
+            &[],
+            timeout,
+            &mut diagnostics,
+        );
+        outputs.push(phase.finish(context.builder.is_reproducible()));
+        let result = match binary {
+            Ok(binary) if context.target.compile_only => {
                 outputs.push(format!(
-                    "# Delete Lockfile: '{:?}', {}\n",
-                    &lockfile,
-                    std::fs::remove_file(&lockfile).is_ok()
+                    "# setup of group {} compiled for {}, not executed\n",
+                    group, context.target.triple
                 ));
+                Ok((dir, binary))
+            }
+            Ok(binary) => {
+                let phase = Phase::start(format!("executing setup of group {}", group));
+                let output = Self::run_group(context, group, &binary, &dir, "setup", timeout);
+                outputs.push(phase.finish(context.builder.is_reproducible()));
+                match output {
+                    Ok(output) if output.status.success() => {
+                        outputs.push(format!("# setup of group {} success\n", group));
+                        Ok((dir, binary))
+                    }
+                    Ok(output) => {
+                        outputs.push(format!(
+                            "# setup of group {} failed: {}\n",
+                            group, output.status
+                        ));
+                        Err(Status::Inconclusive)
+                    }
+                    Err(err) => {
+                        outputs.push(format!("# setup of group {} failed: {}\n", group, err));
+                        Err(Status::Inconclusive)
+                    }
+                }
+            }
+            Err(_) => {
+                outputs.push(format!("# compiling setup of group {} failed\n", group));
+                Self::warn_diagnostics(
+                    context.builder,
+                    &format!("the setup of group {}", group),
+                    &diagnostics,
+                    context.builder.is_strict(),
+                    outputs,
+                );
+                Err(Status::Inconclusive)
             }
+        };
+        context.groups.insert(group.to_string(), result.clone());
+        result
+    }
 
-            let mut test_features = Vec::new();
+    /// Runs the setups of the groups once more for their teardown and removes their
+    /// directories.
+    fn group_teardown(context: &Context, outputs: &mut Vec<String>) {
+        for (group, result) in &context.groups {
+            let Ok((dir, binary)) = result else {
+                continue;
+            };
+            if !context.target.compile_only {
+                let directives = Directives::parse(&Self::group_src(context.builder, group));
+                let timeout = Self::timeout(context, group, &directives);
+                match Self::run_group(context, group, binary, dir, "teardown", timeout) {
+                    Ok(output) if output.status.success() => {
+                        outputs.push(format!("# teardown of group {} success\n", group));
+                    }
+                    Ok(output) => outputs.push(format!(
+                        "# teardown of group {} failed: {}\n",
+                        group, output.status
+                    )),
+                    Err(err) => {
+                        outputs.push(format!("# teardown of group {} failed: {}\n", group, err))
+                    }
+                }
+            }
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
 
-            for feature in features {
-                if env(format!("CARGO_FEATURE_{}", feature.to_uppercase())).is_none() {
-                    outputs.push(format!("# checking for {}\n", &feature));
-                    let mut test_src = PathBuf::from("conf_tests");
-                    test_src.push(&feature);
-                    test_src.set_extension("rs");
-                    if test_src.exists() {
-                        outputs.push(format!("# {} exists\n", test_src.display()));
-                        outputs.push(format!("cargo:rerun-if-changed={}\n", test_src.display()));
-                        if let Some(binary) =
-                            Self::compile_test(&test_src, &edition, &extern_libs, &test_features)
-                        {
-                            outputs
-                                .push(format!("# compiling ConfTest for {} success\n", &feature));
-                            if let Some(stdout) = Self::run_test(&binary) {
-                                outputs.push(format!(
-                                    "# executing ConfTest for {} success\n",
-                                    &feature
-                                ));
-                                outputs.push(format!("cargo:rustc-cfg=feature=\"{}\"\n", &feature));
-                                outputs.push(stdout);
-                                test_features.push(feature.clone());
-                            } else {
-                                outputs.push(format!(
-                                    "# executing ConfTest for {} failed\n",
-                                    &feature
-                                ));
-                            }
-                        } else {
-                            outputs.push(format!("# compiling ConfTest for {} failed\n", &feature));
+    /// Runs the setup 'binary' of 'group' in 'phase', 'setup' or 'teardown'.
+    fn run_group(
+        context: &Context,
+        group: &str,
+        binary: &Path,
+        dir: &Path,
+        phase: &str,
+        timeout: Option<Duration>,
+    ) -> std::io::Result<Output> {
+        let mut command = Command::new(binary);
+        command
+            .env("CONF_TEST_GROUP", group)
+            .env("CONF_TEST_GROUP_DIR", dir)
+            .env("CONF_TEST_GROUP_PHASE", phase)
+            .env("CONF_TEST_TARGET", env("TARGET").unwrap_or_default());
+        if let Some(c_compiler) = &context.c_compiler {
+            command.env("CONF_TEST_CC", &c_compiler.path);
+        }
+        command.envs(&context.env);
+        process::output(&mut command, timeout, Some(context.builder.stdout_limit()))
+    }
+
+    /// The directory of the group of a test, once its setup ran.
+    fn group_dir<'c>(context: &'c Context, directives: &Directives) -> Option<&'c Path> {
+        let (dir, _) = context
+            .groups
+            .get(directives.group.as_ref()?)?
+            .as_ref()
+            .ok()?;
+        Some(dir)
+    }
+
+    /// Hashes all inputs a test depends on, used to look up its cached result.
+    fn test_hash(
+        context: &Context,
+        feature: &str,
+        test_src: &Path,
+        test_features: &[String],
+    ) -> String {
+        let mut hasher = DefaultHasher::new();
+        std::fs::read(test_src)
+            .expect("Failed to read test")
+            .hash(&mut hasher);
+        let directives = Directives::parse(test_src);
+        Self::template_vars(context, feature, &directives).hash(&mut hasher);
+        for fixture in directives.fixtures {
+            // a missing fixture hashes as empty, the test decides what that means
+            std::fs::read(context.builder.crate_dir().join(&fixture))
+                .unwrap_or_default()
+                .hash(&mut hasher);
+        }
+        if let Some(group) = directives.group {
+            std::fs::read(Self::group_src(context.builder, &group))
+                .unwrap_or_default()
+                .hash(&mut hasher);
+        }
+        context.probe_timeouts.get(feature).hash(&mut hasher);
+        context.probe_profiles.get(feature).hash(&mut hasher);
+        context.profile.hash(&mut hasher);
+        test_features.hash(&mut hasher);
+        format!(
+            "{:?} {:?} {:?} {:?} {:?} {:?}",
+            context.builder.cfg_styles(),
+            context.edition,
+            context.extern_libs,
+            context.c_compiler,
+            context.env,
+            context.target
+        )
+        .hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+
+    /// Probes 'feature' once for each combination of the features in 'matrix' being enabled,
+    /// in place of whether they are in 'test_features'. The results are not cached.
+    fn probe_matrix(
+        context: &mut Context,
+        feature: &str,
+        test_src: &Path,
+        test_features: &[String],
+        matrix: &[String],
+        outputs: &mut Vec<String>,
+    ) -> Vec<(Vec<String>, Status)> {
+        if !Directives::parse(test_src).variants.is_empty() {
+            panic!(
+                "ConfTest for {} selects variants, it can not be probed in a matrix",
+                feature
+            );
+        }
+        let base: Vec<String> = test_features
+            .iter()
+            .filter(|enabled| !matrix.contains(enabled))
+            .cloned()
+            .collect();
+        (0..1usize << matrix.len())
+            .map(|mask| {
+                let combination: Vec<String> = matrix
+                    .iter()
+                    .enumerate()
+                    .filter(|(bit, _)| mask & 1 << bit != 0)
+                    .map(|(_, enabled)| enabled.clone())
+                    .collect();
+                let mut features = base.clone();
+                features.extend(combination.iter().cloned());
+                outputs.push(format!(
+                    "# checking for {} with [{}]\n",
+                    feature,
+                    combination.join(", ")
+                ));
+                let status = match Self::probe(
+                    context,
+                    feature,
+                    test_src,
+                    &features,
+                    &mut ProbeDetails::default(),
+                    outputs,
+                ) {
+                    Ok(_) => Status::Enabled,
+                    Err(status) => status,
+                };
+                outputs.push(format!(
+                    "# ConfTest for {} with [{}]: {}\n",
+                    feature,
+                    combination.join(", "),
+                    status.description()
+                ));
+                (combination, status)
+            })
+            .collect()
+    }
+
+    /// Compiles and runs a single test, returns its stdout on success or the status telling
+    /// in which stage it failed.
+    fn probe(
+        context: &mut Context,
+        feature: &str,
+        test_src: &Path,
+        test_features: &[String],
+        details: &mut ProbeDetails,
+        outputs: &mut Vec<String>,
+    ) -> Result<String, Status> {
+        if context.target.assume_disabled {
+            outputs.push(format!(
+                "# ConfTest for {} not run for {}, assumed disabled\n",
+                feature, context.target.triple
+            ));
+            return Err(Status::Assumed(false));
+        }
+        let directives = Directives::parse(test_src);
+        if let (Some(required), Some(level)) = (directives.android_api, context.target.android_api)
+        {
+            if level < required {
+                outputs.push(format!(
+                    "# ConfTest for {} needs Android API level {}, targeting {}\n",
+                    feature, required, level
+                ));
+                return Err(Status::Unsupported);
+            }
+        }
+        if let Some(profile) = context
+            .probe_profiles
+            .get(feature)
+            .copied()
+            .or(directives.profile)
+            .filter(|profile| *profile != context.profile)
+        {
+            outputs.push(format!(
+                "# ConfTest for {} runs only in {} builds\n",
+                feature,
+                profile.name()
+            ));
+            return Err(Status::OtherProfile);
+        }
+        if let Some(required) = directives.requires.iter().find(|required| {
+            !test_features.contains(required)
+                && env(format!("CARGO_FEATURE_{}", required.to_uppercase())).is_none()
+        }) {
+            outputs.push(format!(
+                "# ConfTest for {} skipped, requires {} which is not enabled\n",
+                feature, required
+            ));
+            return Err(Status::RequirementUnmet);
+        }
+        if let Some(group) = &directives.group {
+            if let Err(status) = Self::group_setup(context, group, outputs) {
+                outputs.push(format!(
+                    "# ConfTest for {} not run, the setup of group {} failed\n",
+                    feature, group
+                ));
+                return Err(status);
+            }
+        }
+        let context = &*context;
+        let timeout = Self::timeout(context, feature, &directives);
+        let compile_phase = Phase::start(format!("compiling ConfTest for {}", feature));
+        let binary = Self::compile_test(
+            context,
+            feature,
+            test_src,
+            &directives,
+            test_features,
+            timeout,
+            &mut details.diagnostics,
+        );
+        details.compile_time = Some(compile_phase.elapsed());
+        outputs.push(compile_phase.finish(context.builder.is_reproducible()));
+        // catalog tests may fail to compile on platforms lacking what they detect
+        let strict =
+            context.builder.is_strict() && !test_src.starts_with(context.out_dir.join("catalog"));
+        let binary = match binary {
+            Ok(binary) => binary,
+            Err(Status::Inconclusive) => {
+                outputs.push(format!(
+                    "# compiling ConfTest for {} inconclusive, rustc did not run properly\n",
+                    feature
+                ));
+                Self::warn_diagnostics(
+                    context.builder,
+                    feature,
+                    &details.diagnostics,
+                    strict,
+                    outputs,
+                );
+                return Err(Status::Inconclusive);
+            }
+            Err(Status::Sandboxed) => {
+                outputs.push(format!(
+                    "# compiling ConfTest for {} denied by a sandbox: {}",
+                    feature, details.diagnostics
+                ));
+                return Err(Status::Sandboxed);
+            }
+            Err(status) => {
+                outputs.push(format!("# compiling ConfTest for {} failed\n", feature));
+                // crates of a group may come from its setup
+                if let Some(name) = Self::missing_build_dependency(context, &details.diagnostics)
+                    .filter(|_| directives.group.is_none())
+                {
+                    outputs.push(format!(
+                        "# ConfTest for {} uses the crate '{}' which is not a build-dependency\n",
+                        feature, name
+                    ));
+                    details.missing_dependency = Some(name);
+                }
+                Self::warn_diagnostics(
+                    context.builder,
+                    feature,
+                    &details.diagnostics,
+                    strict,
+                    outputs,
+                );
+                return Err(status);
+            }
+        };
+        outputs.push(format!("# compiling ConfTest for {} success\n", feature));
+
+        if directives.crate_type == CrateType::Lib {
+            outputs.push(format!(
+                "# ConfTest for {} is a library, not executed\n",
+                feature
+            ));
+            return Ok(String::new());
+        }
+
+        if context.target.compile_only {
+            if !directives.variants.is_empty() {
+                outputs.push(format!(
+                    "# ConfTest for {} selects variants, it can not be decided without executing it\n",
+                    feature
+                ));
+                return Err(Status::RunFailed);
+            }
+            outputs.push(format!(
+                "# ConfTest for {} compiled for {}, not executed\n",
+                feature, context.target.triple
+            ));
+            return Ok(String::new());
+        }
+
+        let run_phase = Phase::start(format!("executing ConfTest for {}", feature));
+        let group_dir = Self::group_dir(context, &directives);
+        let output =
+            match Self::run_test(context, feature, &binary, test_features, timeout, group_dir) {
+                Ok(output) => Some(output),
+                Err(err)
+                    if err
+                        .get_ref()
+                        .is_some_and(|err| err.is::<process::OutputLimitExceeded>()) =>
+                {
+                    panic!(
+                        "ConfTest for {} {}, see ConfTestBuilder::max_output()",
+                        feature, err
+                    );
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::PermissionDenied => {
+                    outputs.push(format!(
+                        "# executing ConfTest for {} denied by a sandbox: {}\n",
+                        feature, err
+                    ));
+                    return Err(Status::Sandboxed);
+                }
+                Err(err) if err.kind() != std::io::ErrorKind::TimedOut => {
+                    outputs.push(format!(
+                        "# executing ConfTest for {} inconclusive: {}\n",
+                        feature, err
+                    ));
+                    return Err(Status::Inconclusive);
+                }
+                Err(err) => {
+                    outputs.push(format!("# executing ConfTest for {}: {}\n", feature, err));
+                    None
+                }
+            };
+        details.run_time = Some(run_phase.elapsed());
+        details.exit_code = output.as_ref().and_then(|output| output.status.code());
+        outputs.push(run_phase.finish(context.builder.is_reproducible()));
+        let code = output.as_ref().and_then(|output| output.status.code());
+        let action = match &output {
+            Some(output) if !directives.variants.is_empty() => {
+                let variant = code.and_then(|code| directives.variants.get(&code));
+                outputs.push(format!(
+                    "# ConfTest for {} {}, selecting variant {}\n",
+                    feature,
+                    output.status,
+                    variant.map(String::as_str).unwrap_or("none")
+                ));
+                details.variant = variant.cloned();
+                match (variant, code) {
+                    (Some(_), _) => ExitAction::Pass,
+                    // an unmapped code selects nothing, even when it counts as pass
+                    (None, Some(code)) => {
+                        match Self::exit_action(context, feature, &directives, code) {
+                            ExitAction::Pass => ExitAction::Fail,
+                            action => action,
                         }
-                    } else {
-                        outputs.push(format!("# test for '{}' does not exist\n", &feature));
                     }
-                } else {
-                    outputs.push(format!("# test for '{}' manually overridden\n", &feature));
+                    (None, None) => ExitAction::Fail,
                 }
-                outputs.push(String::from("\n"));
-                test_features.push(feature.clone());
+            }
+            // killed by a signal
+            Some(_) => code.map_or(ExitAction::Fail, |code| {
+                Self::exit_action(context, feature, &directives, code)
+            }),
+            None => ExitAction::Fail,
+        };
+
+        match (action, output) {
+            (ExitAction::Pass, Some(output)) => {
+                outputs.push(format!("# executing ConfTest for {} success\n", feature));
+                Ok(instructions::sanitize(&output.stdout))
+            }
+            (ExitAction::Skip, _) => {
+                outputs.push(format!("# ConfTest for {} skipped itself\n", feature));
+                Err(Status::Skipped)
+            }
+            (ExitAction::Inconclusive, _) => {
+                outputs.push(format!(
+                    "# executing ConfTest for {} inconclusive\n",
+                    feature
+                ));
+                Err(Status::Inconclusive)
+            }
+            (ExitAction::Fail, Some(output)) if process::denied_by_sandbox(&output) => {
+                outputs.push(format!(
+                    "# executing ConfTest for {} denied by a sandbox\n",
+                    feature
+                ));
+                Err(Status::Sandboxed)
+            }
+            _ => {
+                outputs.push(format!("# executing ConfTest for {} failed\n", feature));
+                Err(Status::RunFailed)
             }
         }
+    }
+
+    /// What exit 'code' of the test for 'feature' means. The package metadata for the test
+    /// takes precedence over its `on-exit` directives and these over the package wide table.
+    /// Without any mapping 0 passes and everything else fails.
+    fn exit_action(
+        context: &Context,
+        feature: &str,
+        directives: &Directives,
+        code: i32,
+    ) -> ExitAction {
+        context
+            .probe_exit_codes
+            .get(feature)
+            .and_then(|codes| codes.get(&code))
+            .or_else(|| directives.exit_actions.get(&code))
+            .or_else(|| context.exit_codes.get(&code))
+            .copied()
+            .unwrap_or(if code == 0 {
+                ExitAction::Pass
+            } else {
+                ExitAction::Fail
+            })
+    }
 
-        for output in outputs {
-            logfile.write_all(output.as_bytes()).unwrap();
-            print!("{}", output);
+    /// The crate a test failed to compile for because it is not a build-dependency, found in
+    /// the rustc 'diagnostics'. Crates named by `extern crate` are missing unless they are
+    /// part of the sysroot, unresolved paths are only attributed to dependencies of the
+    /// package. Tests compiled for another target can not use any crate, nothing is missing
+    /// then.
+    fn missing_build_dependency(context: &Context, diagnostics: &str) -> Option<String> {
+        if context.target.compile_only {
+            return None;
         }
+        let declared = |dependencies: &BTreeSet<String>, name: &str| {
+            dependencies
+                .iter()
+                .any(|dependency| dependency.replace('-', "_") == name)
+        };
+        diagnostics.lines().find_map(|line| {
+            // error[E0463]: can't find crate for `foo`
+            let extern_crate = line.starts_with("error[E0463]");
+            // error[E0432]: unresolved import `foo`, error[E0433]: ... `foo`
+            let path = line.starts_with("error[E0432]") || line.starts_with("error[E0433]");
+            let name = line.split('`').nth(1)?.split("::").next()?;
+            let missing = !declared(&context.build_dependencies, name)
+                && (extern_crate && !SYSROOT_CRATES.contains(&name)
+                    || path && declared(&context.dependencies, name));
+            missing.then(|| name.to_string())
+        })
     }
 
-    fn run_test(test_binary: &Path) -> Option<String> {
-        let command = Command::new(test_binary).output().ok()?;
-        if command.status.success() {
-            Some(String::from_utf8_lossy(&command.stdout).to_string())
-        } else {
-            None
+    /// Shows the first lines of the rustc errors as warnings when `CONF_TEST_VERBOSE` or
+    /// `ConfTestBuilder::verbose()` is set or the failure of the test is 'strict'.
+    fn warn_diagnostics(
+        builder: &ConfTestBuilder,
+        feature: &str,
+        diagnostics: &str,
+        strict: bool,
+        outputs: &mut Vec<String>,
+    ) {
+        let lines = match builder.verbose_lines() {
+            Some(lines) => lines,
+            None if strict => 10,
+            None => return,
+        };
+        outputs.push(format!(
+            "cargo:warning=ConfTest for {} failed to compile:\n",
+            feature
+        ));
+        for line in diagnostics
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .take(lines)
+        {
+            outputs.push(format!("cargo:warning={}\n", line));
+        }
+    }
+
+    /// The time limit for compiling and for executing the test for 'feature', the package
+    /// metadata takes precedence over the directive, `CONF_TEST_TIMEOUT` applies to all other
+    /// tests.
+    fn timeout(context: &Context, feature: &str, directives: &Directives) -> Option<Duration> {
+        context
+            .probe_timeouts
+            .get(feature)
+            .copied()
+            .or(directives.timeout)
+            .or_else(|| {
+                let seconds = env("CONF_TEST_TIMEOUT")?;
+                let seconds = seconds.to_string_lossy();
+                Some(
+                    seconds
+                        .trim()
+                        .parse()
+                        .ok()
+                        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                        .unwrap_or_else(|| panic!("Invalid CONF_TEST_TIMEOUT: {:?}", seconds)),
+                )
+            })
+    }
+
+    fn run_test(
+        context: &Context,
+        feature: &str,
+        test_binary: &Path,
+        test_features: &[String],
+        timeout: Option<Duration>,
+        group_dir: Option<&Path>,
+    ) -> std::io::Result<Output> {
+        let mut out_dir = PathBuf::new();
+        out_dir.push(context.builder.conf_test_dir());
+
+        let work_dir = Self::work_dir(context, feature);
+        let context_file = Self::write_context_file(context, feature, test_features, group_dir);
+        let mut command = Command::new(test_binary);
+        command
+            .current_dir(&work_dir)
+            .env("CONF_TEST_WORK_DIR", &work_dir)
+            .env("CONF_TEST_CONTEXT", context_file)
+            .env("CONF_TEST_FEATURE", feature)
+            .env("CONF_TEST_FEATURES", test_features.join(","))
+            .env("CONF_TEST_TARGET", env("TARGET").unwrap_or_default())
+            .env("CONF_TEST_OUT_DIR", out_dir)
+            .env("CONF_TEST_SEED", context.seed.to_string());
+        if let Some(c_compiler) = &context.c_compiler {
+            command.env("CONF_TEST_CC", &c_compiler.path);
+        }
+        if let Some(group_dir) = group_dir {
+            command.env("CONF_TEST_GROUP_DIR", group_dir);
+        }
+        command.envs(&context.env);
+        let output = process::output(&mut command, timeout, Some(context.builder.stdout_limit()));
+        if !context.builder.keep_work_dirs {
+            let _ = std::fs::remove_dir_all(&work_dir);
+        }
+        output
+    }
+
+    /// Writes the context of the test for 'feature' to 'FEATURE.context.toml' next to the
+    /// compiled test, returns its path.
+    fn write_context_file(
+        context: &Context,
+        feature: &str,
+        test_features: &[String],
+        group_dir: Option<&Path>,
+    ) -> PathBuf {
+        let mut table = toml::value::Table::new();
+        table.insert("feature".into(), toml::Value::from(feature));
+        table.insert("features".into(), toml::Value::from(test_features.to_vec()));
+        table.insert(
+            "target".into(),
+            toml::Value::from(context.target.triple.as_str()),
+        );
+        table.insert("os".into(), toml::Value::from(context.target.os.as_str()));
+        table.insert(
+            "arch".into(),
+            toml::Value::from(context.target.arch.as_str()),
+        );
+        table.insert("cross".into(), toml::Value::from(context.target.cross));
+        table.insert("profile".into(), toml::Value::from(context.profile.name()));
+        // u64 does not fit into TOML integers
+        table.insert("seed".into(), toml::Value::from(context.seed.to_string()));
+        if let Some(level) = context.target.android_api {
+            table.insert("android_api".into(), toml::Value::from(level));
+        }
+        if let Some(c_compiler) = &context.c_compiler {
+            table.insert(
+                "c_compiler".into(),
+                toml::Value::from(c_compiler.path.to_string_lossy().as_ref()),
+            );
+        }
+        if let Some(group_dir) = group_dir {
+            table.insert(
+                "group_dir".into(),
+                toml::Value::from(group_dir.to_string_lossy().as_ref()),
+            );
+        }
+        let values: toml::value::Table = context
+            .env
+            .iter()
+            .map(|(key, value)| (key.clone(), toml::Value::from(value.as_str())))
+            .collect();
+        table.insert("values".into(), toml::Value::Table(values));
+
+        let path = context.out_dir.join(format!("{}.context.toml", feature));
+        let source =
+            toml::to_string(&toml::Value::Table(table)).expect("Failed to format test context");
+        atomic::write(&path, source).expect("Failed to write test context");
+        path
+    }
+
+    /// Creates the directory for a run of the test for 'feature', 'conf_test/work/FEATURE.N'
+    /// with the first N not taken yet.
+    fn work_dir(context: &Context, feature: &str) -> PathBuf {
+        let mut work_dir = PathBuf::new();
+        work_dir.push(context.builder.conf_test_dir());
+        work_dir.push("work");
+        DirBuilder::new()
+            .recursive(true)
+            .create(&work_dir)
+            .expect("Failed to create work directory");
+        let mut n = 0;
+        loop {
+            let dir = work_dir.join(format!("{}.{}", feature, n));
+            match std::fs::create_dir(&dir) {
+                Ok(()) => return dir,
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => n += 1,
+                Err(err) => panic!("Failed to create work directory {}: {}", dir.display(), err),
+            }
         }
     }
 
     fn compile_test(
+        context: &Context,
+        feature: &str,
         src: &Path,
-        edition: &Edition,
-        extern_libs: &BTreeMap<OsString, (String, PathBuf)>,
+        directives: &Directives,
         features: &[String],
-    ) -> Option<PathBuf> {
-        let mut out_file = PathBuf::new();
-        out_file.push(env("OUT_DIR").expect("env var OUT_DIR is not set"));
-        out_file.push("conf_test");
-        out_file.push(src.file_stem().unwrap());
+        timeout: Option<Duration>,
+        diagnostics: &mut String,
+    ) -> Result<PathBuf, Status> {
+        let mut out_file = context.out_dir.clone();
+        out_file.push(feature);
+
+        let crate_type = match directives.crate_type {
+            CrateType::Bin => "bin",
+            CrateType::Lib => {
+                out_file.set_extension("rlib");
+                "lib"
+            }
+        };
+
+        // templates are expanded next to the compiled test
+        let expanded;
+        let src = match Self::template_vars(context, feature, directives) {
+            Some(vars) => {
+                let source = std::fs::read_to_string(src).expect("Failed to read template");
+                expanded = context.out_dir.join(format!("{}.rs", feature));
+                std::fs::write(&expanded, template::expand(&source, &vars, src))
+                    .expect("Failed to write expanded template");
+                expanded.as_path()
+            }
+            None => src,
+        };
 
         let mut rust_cmd = Command::new(env("RUSTC").unwrap_or_else(|| OsString::from("rustc")));
         let rust_cmd = rust_cmd
             .arg("--crate-type")
-            .arg("bin")
+            .arg(crate_type)
             .arg("--edition")
-            .arg(edition_to_str(edition))
+            .arg(&context.edition)
             .arg("-o")
             .arg(&out_file)
             .arg("-v")
             .arg(src);
 
-        for (name, filename) in extern_libs.values() {
-            rust_cmd.arg("--extern").arg(format!(
-                "{}={}", //FIXME: needs some better way to compose an OsString here
-                name,
-                filename.to_str().expect("invalid file name")
-            ));
+        if context.target.compile_only {
+            // the dependencies are built for the host, tests can not use them here
+            rust_cmd.arg("--target").arg(&context.target.triple);
+            if let Some(linker) = &context.target.linker {
+                let mut arg = OsString::from("linker=");
+                arg.push(linker);
+                rust_cmd.arg("-C").arg(arg);
+            }
+        } else {
+            for (name, filename) in context.extern_libs.values() {
+                let mut arg = OsString::from(name);
+                arg.push("=");
+                arg.push(filename);
+                rust_cmd.arg("--extern").arg(arg);
+            }
+            for lib in SYSTEM_LIBS {
+                rust_cmd.arg("-l").arg(format!("dylib={}", lib));
+            }
+        }
+
+        // the artifacts of the group setup, libraries to link against
+        if let Some(group_dir) = Self::group_dir(context, directives) {
+            rust_cmd.arg("-L").arg(group_dir);
         }
 
         for feature in features {
-            rust_cmd
-                .arg("--cfg")
-                .arg(format!("feature=\"{}\"", feature));
+            for style in context.builder.cfg_styles() {
+                rust_cmd.arg("--cfg").arg(style.cfg(feature));
+            }
         }
 
-        let rust_output = rust_cmd.output().ok()?;
+        rust_cmd.envs(&context.env);
+
+        let rust_output = match process::output(rust_cmd, timeout, None) {
+            Ok(output) => output,
+            Err(err) => {
+                *diagnostics = format!("rustc {}\n", err);
+                return Err(match err.kind() {
+                    std::io::ErrorKind::TimedOut => Status::CompileFailed,
+                    std::io::ErrorKind::PermissionDenied => Status::Sandboxed,
+                    _ => Status::Inconclusive,
+                });
+            }
+        };
+        *diagnostics = String::from_utf8_lossy(&rust_output.stderr).into_owned();
 
         if rust_output.status.success() {
-            Some(out_file)
+            Ok(out_file)
+        } else if rust_output.status.code() == Some(101)
+            || diagnostics.contains("internal compiler error")
+        {
+            // an ICE tells nothing about the feature
+            Err(Status::Inconclusive)
         } else {
-            None
+            Err(Status::CompileFailed)
         }
     }
 
-    fn get_extern_libs(dependencies: &BTreeSet<String>) -> BTreeMap<OsString, (String, PathBuf)> {
+    fn get_extern_libs(
+        builder: &ConfTestBuilder,
+        dependencies: &BTreeSet<String>,
+    ) -> BTreeMap<OsString, (String, PathBuf)> {
         let mut extern_libs = BTreeMap::new();
+        if builder.skip_extern_crates {
+            return extern_libs;
+        }
 
         //PLANNED: get rid of extra target dir, is there any way to work around the build lock?
         let mut target_dir = PathBuf::new();
-        target_dir.push(env("OUT_DIR").expect("env var OUT_DIR is not set"));
+        target_dir.push(builder.build_dir());
         target_dir.push("conf_test");
 
         // let cargo start a rustc process that does not build the project but returns the
         // metadata about compilation artifacts
-        let mut cargo = Command::new(env("CARGO").unwrap_or_else(|| OsString::from("cargo")))
+        let mut cargo = Command::new(env("CARGO").unwrap_or_else(|| OsString::from("cargo")));
+        let mut cargo = cargo
             .arg("--offline")
+            .current_dir(builder.crate_dir())
             .arg("rustc")
             .arg("--message-format")
             .arg("json")
@@ -426,11 +3598,25 @@ impl ConfTest {
     }
 }
 
-fn edition_to_str(edition: &Edition) -> &str {
-    match edition {
-        Edition::E2015 => "2015",
-        Edition::E2018 => "2018",
-        Edition::E2021 => "2021",
-        _ => todo!("send PR for new editions"),
+/// The C preprocessor define for 'feature', `o_path` becomes `HAVE_O_PATH`.
+fn have_define(feature: &str) -> String {
+    let mut define = String::from("HAVE_");
+    define.extend(feature.chars().map(|c| {
+        if c.is_ascii_alphanumeric() {
+            c.to_ascii_uppercase()
+        } else {
+            '_'
+        }
+    }));
+    define
+}
+
+/// The stdout of 'command', empty when it is not available.
+fn command_output(command: &mut Command) -> String {
+    match command.output() {
+        Ok(output) if output.status.success() => {
+            String::from_utf8_lossy(&output.stdout).into_owned()
+        }
+        _ => String::new(),
     }
 }
@@ -0,0 +1,382 @@
+//! Querying the features and dependencies of the package from 'Cargo.toml'.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use cargo_metadata::{DependencyKind, Edition, Metadata, MetadataCommand, Package};
+use toml::Value;
+
+use crate::{ExitAction, Profile};
+
+/// The parts of 'Cargo.toml' ConfTest uses, returned by `ConfTest::manifest()`.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct Manifest {
+    /// The features and the features or dependencies each one enables.
+    pub features: BTreeMap<String, Vec<String>>,
+    /// The names of all dependencies.
+    pub dependencies: BTreeSet<String>,
+    /// The names of the build-dependencies, the crates tests can use.
+    pub build_dependencies: BTreeSet<String>,
+    /// The edition of the package, "2021" when not known.
+    pub edition: String,
+    /// The root directory of the workspace, the directory of the package itself when it is
+    /// not part of a workspace.
+    pub workspace_root: PathBuf,
+    /// Time limits for single tests from `[package.metadata.conf_test.probes.<name>]`
+    /// `timeout = SECONDS`, these take precedence over the `timeout` directive.
+    pub probe_timeouts: BTreeMap<String, Duration>,
+    /// What exit codes of all tests mean, from `[package.metadata.conf_test.exit-codes]`
+    /// `CODE = "ACTION"`.
+    pub exit_codes: BTreeMap<i32, ExitAction>,
+    /// What exit codes of single tests mean, from
+    /// `[package.metadata.conf_test.probes.<name>.exit-codes]`, these take precedence over
+    /// the `on-exit` directive.
+    pub probe_exit_codes: BTreeMap<String, BTreeMap<i32, ExitAction>>,
+    /// The only builds single tests run in, from `[package.metadata.conf_test.probes.<name>]`
+    /// `profile = "debug|release"`, these take precedence over the `profile` directive.
+    pub probe_profiles: BTreeMap<String, Profile>,
+    /// The features to be probed by catalog tests, from `[package.metadata.conf_test]`
+    /// `catalog = ["FEATURE", ...]`.
+    pub catalog: BTreeSet<String>,
+}
+
+impl Manifest {
+    /// Reads the manifest of the crate in 'dir', without 'dir' the one of the crate being
+    /// built. 'Cargo.toml' is parsed directly, `cargo metadata` is only queried for manifests
+    /// that can not be understood otherwise.
+    pub(crate) fn query(dir: Option<&Path>) -> Manifest {
+        dir.map(Path::to_path_buf)
+            .or_else(|| std::env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from))
+            .and_then(|manifest_dir| Self::parse(&manifest_dir))
+            .unwrap_or_else(|| Self::metadata(dir))
+    }
+
+    /// Parses 'dir/Cargo.toml', None when it is missing, not a package or inherits the edition
+    /// from a workspace that can not be read.
+    fn parse(dir: &Path) -> Option<Manifest> {
+        let toml: Value = fs::read_to_string(dir.join("Cargo.toml"))
+            .ok()?
+            .parse()
+            .ok()?;
+        let package = toml.get("package")?;
+        let workspace_root = workspace_root(dir, package);
+        let edition = match package.get("edition") {
+            None => "2015".to_string(),
+            Some(Value::String(edition)) => edition.clone(),
+            // edition.workspace = true
+            Some(edition) if edition.get("workspace").and_then(Value::as_bool) == Some(true) => {
+                workspace_edition(&workspace_root)?
+            }
+            Some(_) => return None,
+        };
+
+        let mut manifest = Manifest {
+            edition,
+            workspace_root,
+            ..Manifest::default()
+        };
+        for (feature, enables) in toml
+            .get("features")
+            .and_then(Value::as_table)
+            .into_iter()
+            .flatten()
+        {
+            let enables = enables
+                .as_array()?
+                .iter()
+                .map(|enable| enable.as_str().map(String::from))
+                .collect::<Option<Vec<_>>>()?;
+            manifest.features.insert(feature.clone(), enables);
+        }
+
+        // dependencies may be declared per target as well
+        let mut tables = vec![&toml];
+        tables.extend(
+            toml.get("target")
+                .and_then(Value::as_table)
+                .into_iter()
+                .flat_map(|targets| targets.values()),
+        );
+        let mut optional = Vec::new();
+        for table in tables {
+            for kind in [
+                "dependencies",
+                "dev-dependencies",
+                "build-dependencies",
+                "build_dependencies",
+            ] {
+                for (key, dep) in table
+                    .get(kind)
+                    .and_then(Value::as_table)
+                    .into_iter()
+                    .flatten()
+                {
+                    let name = dep.get("package").and_then(Value::as_str).unwrap_or(key);
+                    manifest.dependencies.insert(name.to_string());
+                    if kind.starts_with("build") {
+                        manifest.build_dependencies.insert(name.to_string());
+                    }
+                    if dep.get("optional").and_then(Value::as_bool) == Some(true) {
+                        optional.push(key.clone());
+                    }
+                }
+            }
+        }
+        // optional dependencies not referred to by 'dep:' are features on their own
+        for dep in optional {
+            let enable = format!("dep:{}", dep);
+            if !manifest
+                .features
+                .values()
+                .flatten()
+                .any(|enables| *enables == enable)
+            {
+                manifest.features.entry(dep).or_insert_with(|| vec![enable]);
+            }
+        }
+
+        let conf_test = package
+            .get("metadata")
+            .and_then(|metadata| metadata.get("conf_test"));
+        let table_codes = |codes: Option<&Value>, of: &str| {
+            exit_codes(
+                codes
+                    .and_then(Value::as_table)
+                    .into_iter()
+                    .flatten()
+                    .map(|(code, action)| (code.as_str(), action.as_str())),
+                of,
+            )
+        };
+        manifest.exit_codes = table_codes(
+            conf_test.and_then(|conf_test| conf_test.get("exit-codes")),
+            "all probes",
+        );
+        if let Some(catalog) = conf_test.and_then(|conf_test| conf_test.get("catalog")) {
+            manifest.catalog = catalog_list(
+                catalog
+                    .as_array()
+                    .map(|features| features.iter().map(Value::as_str).collect()),
+                catalog,
+            );
+        }
+        let probes = conf_test
+            .and_then(|conf_test| conf_test.get("probes"))
+            .and_then(Value::as_table);
+        for (name, probe) in probes.into_iter().flatten() {
+            if let Some(codes) = probe.get("exit-codes") {
+                manifest
+                    .probe_exit_codes
+                    .insert(name.clone(), table_codes(Some(codes), name));
+            }
+            if let Some(timeout) = probe.get("timeout") {
+                let seconds = timeout
+                    .as_float()
+                    .or_else(|| timeout.as_integer().map(|seconds| seconds as f64));
+                manifest
+                    .probe_timeouts
+                    .insert(name.clone(), probe_timeout(name, seconds, timeout));
+            }
+            if let Some(profile) = probe.get("profile") {
+                manifest
+                    .probe_profiles
+                    .insert(name.clone(), probe_profile(name, profile.as_str(), profile));
+            }
+        }
+        Some(manifest)
+    }
+
+    /// Queries `cargo metadata` run in 'dir' for the manifest.
+    fn metadata(dir: Option<&Path>) -> Manifest {
+        let mut command = MetadataCommand::new();
+        if let Some(dir) = dir {
+            command.current_dir(dir);
+        }
+        let metadata = command
+            .other_options(["--frozen".to_string()])
+            .no_deps()
+            .exec()
+            .expect("Querying cargo metadata failed");
+
+        let package = select_package(&metadata, dir);
+        let mut manifest = Manifest {
+            features: package.features.clone().into_iter().collect(),
+            edition: edition_to_string(&package.edition),
+            workspace_root: metadata.workspace_root.clone().into(),
+            ..Manifest::default()
+        };
+        for dep in &package.dependencies {
+            manifest.dependencies.insert(dep.name.clone());
+            if dep.kind == DependencyKind::Build {
+                manifest.build_dependencies.insert(dep.name.clone());
+            }
+        }
+        let conf_test = package.metadata.get("conf_test");
+        manifest.exit_codes = exit_codes(
+            conf_test
+                .and_then(|conf_test| conf_test.get("exit-codes"))
+                .and_then(|codes| codes.as_object())
+                .into_iter()
+                .flatten()
+                .map(|(code, action)| (code.as_str(), action.as_str())),
+            "all probes",
+        );
+        if let Some(catalog) = conf_test.and_then(|conf_test| conf_test.get("catalog")) {
+            manifest.catalog = catalog_list(
+                catalog
+                    .as_array()
+                    .map(|features| features.iter().map(|feature| feature.as_str()).collect()),
+                catalog,
+            );
+        }
+        let probes = conf_test
+            .and_then(|conf_test| conf_test.get("probes"))
+            .and_then(|probes| probes.as_object());
+        for (name, probe) in probes.into_iter().flatten() {
+            if let Some(codes) = probe.get("exit-codes").and_then(|codes| codes.as_object()) {
+                let codes = codes
+                    .iter()
+                    .map(|(code, action)| (code.as_str(), action.as_str()));
+                manifest
+                    .probe_exit_codes
+                    .insert(name.clone(), exit_codes(codes, name));
+            }
+            if let Some(timeout) = probe.get("timeout") {
+                manifest
+                    .probe_timeouts
+                    .insert(name.clone(), probe_timeout(name, timeout.as_f64(), timeout));
+            }
+            if let Some(profile) = probe.get("profile") {
+                manifest
+                    .probe_profiles
+                    .insert(name.clone(), probe_profile(name, profile.as_str(), profile));
+            }
+        }
+        manifest
+    }
+}
+
+/// Converts the `timeout` of 'probe' given in seconds, panics when it is invalid.
+fn probe_timeout(probe: &str, seconds: Option<f64>, value: &dyn Display) -> Duration {
+    seconds
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .unwrap_or_else(|| panic!("Invalid timeout for probe '{}': {}", probe, value))
+}
+
+/// Parses the `profile` of 'probe', panics when it is invalid.
+fn probe_profile(probe: &str, profile: Option<&str>, value: &dyn Display) -> Profile {
+    profile
+        .and_then(Profile::parse)
+        .unwrap_or_else(|| panic!("Invalid profile for probe '{}': {}", probe, value))
+}
+
+/// Collects the `catalog` list, panics when it is no list of names.
+fn catalog_list(features: Option<Vec<Option<&str>>>, value: &dyn Display) -> BTreeSet<String> {
+    features
+        .and_then(|features| {
+            features
+                .into_iter()
+                .map(|feature| feature.map(String::from))
+                .collect()
+        })
+        .unwrap_or_else(|| panic!("Invalid catalog list in package metadata: {}", value))
+}
+
+/// Parses an exit code table of 'of', panics when it is invalid.
+fn exit_codes<'a>(
+    codes: impl Iterator<Item = (&'a str, Option<&'a str>)>,
+    of: &str,
+) -> BTreeMap<i32, ExitAction> {
+    codes
+        .map(|(code, action)| {
+            code.parse()
+                .ok()
+                .zip(action.and_then(ExitAction::parse))
+                .unwrap_or_else(|| panic!("Invalid exit code for {}: {} = {:?}", of, code, action))
+        })
+        .collect()
+}
+
+/// The root of the workspace of the crate in 'dir', the closest directory upwards whose
+/// 'Cargo.toml' has a '[workspace]' section unless the package names it explicitly.
+fn workspace_root(dir: &Path, package: &Value) -> PathBuf {
+    if let Some(root) = package.get("workspace").and_then(Value::as_str) {
+        return dir.join(root);
+    }
+    dir.ancestors()
+        .find(|ancestor| {
+            fs::read_to_string(ancestor.join("Cargo.toml"))
+                .ok()
+                .and_then(|source| source.parse::<Value>().ok())
+                .is_some_and(|toml| toml.get("workspace").is_some())
+        })
+        .unwrap_or(dir)
+        .to_path_buf()
+}
+
+/// The edition the workspace at 'root' gives its members in `[workspace.package]`.
+fn workspace_edition(root: &Path) -> Option<String> {
+    let toml: Value = fs::read_to_string(root.join("Cargo.toml"))
+        .ok()?
+        .parse()
+        .ok()?;
+    toml.get("workspace")?
+        .get("package")?
+        .get("edition")?
+        .as_str()
+        .map(String::from)
+}
+
+/// Picks the package of the crate in 'dir' (or `CARGO_MANIFEST_DIR`) from the members of a
+/// workspace. Without 'dir' the package named `CARGO_PKG_NAME` is the fallback, an explicit
+/// 'dir' may be the root of a virtual workspace which has no package.
+fn select_package<'a>(metadata: &'a Metadata, dir: Option<&Path>) -> &'a Package {
+    let packages = &metadata.packages;
+    let manifest_dir = dir
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from));
+    let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+    if let Some(manifest_dir) = &manifest_dir {
+        let manifest_path = canonical(&manifest_dir.join("Cargo.toml"));
+        if let Some(package) = packages
+            .iter()
+            .find(|package| canonical(package.manifest_path.as_std_path()) == manifest_path)
+        {
+            return package;
+        }
+    }
+    if dir.is_none() {
+        if let Ok(name) = std::env::var("CARGO_PKG_NAME") {
+            if let Some(package) = packages.iter().find(|package| package.name == name) {
+                return package;
+            }
+        }
+    }
+    match (packages.as_slice(), metadata.root_package()) {
+        ([package], _) => package,
+        (_, None) => panic!(
+            "'{}' is a virtual workspace, the directory of one of its members must be given",
+            manifest_dir.unwrap_or_default().display()
+        ),
+        _ => panic!("Can not tell which package of the workspace to test"),
+    }
+}
+
+/// The year of 'edition', editions newer than cargo_metadata knows by name ('_E2024') are
+/// taken from their debug representation.
+fn edition_to_string(edition: &Edition) -> String {
+    match edition {
+        Edition::E2015 => "2015".to_string(),
+        Edition::E2018 => "2018".to_string(),
+        Edition::E2021 => "2021".to_string(),
+        edition => format!("{:?}", edition)
+            .chars()
+            .filter(char::is_ascii_digit)
+            .collect(),
+    }
+}
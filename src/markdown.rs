@@ -0,0 +1,46 @@
+//! Rendering of the features as Markdown table for READMEs and crate docs.
+
+use crate::report::Report;
+
+/// Renders 'report' as Markdown table of the features, what their test detects (from the
+/// `describe` directive) and the result of this run. Features probed in a matrix get a second
+/// table with the result per combination of enabled features.
+pub(crate) fn render(report: &Report) -> String {
+    let mut markdown = String::from("| Feature | Detects | Result |\n| --- | --- | --- |\n");
+    for (feature, status) in &report.features {
+        markdown.push_str(&format!(
+            "| `{}` | {} | {} |\n",
+            feature,
+            escape(
+                report
+                    .descriptions
+                    .get(feature)
+                    .map(String::as_str)
+                    .unwrap_or_default()
+            ),
+            status.description()
+        ));
+    }
+    if !report.combinations.is_empty() {
+        markdown.push_str("\n| Feature | Enabled | Result |\n| --- | --- | --- |\n");
+        for (feature, combinations) in &report.combinations {
+            for (combination, status) in combinations {
+                markdown.push_str(&format!(
+                    "| `{}` | {} | {} |\n",
+                    feature,
+                    combination
+                        .iter()
+                        .map(|enabled| format!("`{}`", enabled))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    status.description()
+                ));
+            }
+        }
+    }
+    markdown
+}
+
+fn escape(text: &str) -> String {
+    text.replace('|', "\\|")
+}
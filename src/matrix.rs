@@ -0,0 +1,43 @@
+//! Rendering of the report as plain text feature support matrix.
+
+use crate::report::Report;
+
+/// Renders 'report' as table with one line per feature, whether it is supported and why,
+/// followed by a count of the supported features and the results of the features probed in a
+/// matrix, one line per combination of enabled features.
+pub(crate) fn render(report: &Report) -> String {
+    let width = report
+        .features
+        .keys()
+        .map(String::len)
+        .chain(Some("Feature".len()))
+        .max()
+        .unwrap_or_default();
+
+    let mut matrix = format!("{:width$}  {:9}  {}\n", "Feature", "Supported", "Reason");
+    for (feature, status) in &report.features {
+        matrix.push_str(&format!(
+            "{:width$}  {:9}  {}\n",
+            feature,
+            status.outcome().answer(),
+            status.description()
+        ));
+    }
+    matrix.push_str(&format!(
+        "\n{} of {} features supported\n",
+        report.enabled().count(),
+        report.features.len()
+    ));
+    for (feature, combinations) in &report.combinations {
+        matrix.push_str(&format!("\n{} with\n", feature));
+        for (combination, status) in combinations {
+            matrix.push_str(&format!(
+                "  [{}]  {:9}  {}\n",
+                combination.join(", "),
+                status.outcome().answer(),
+                status.description()
+            ));
+        }
+    }
+    matrix
+}
@@ -0,0 +1,328 @@
+//! Test outcomes which are forced instead of running the tests.
+
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::Path;
+
+use crate::target::Target;
+
+/// A forced test outcome.
+#[derive(Debug, Clone)]
+pub(crate) struct Forced {
+    pub(crate) enabled: bool,
+    /// Forwarded to cargo in place of the stdout of the test when enabled.
+    pub(crate) output: String,
+    /// Where the outcome got forced, for the log.
+    pub(crate) origin: &'static str,
+}
+
+impl Forced {
+    pub(crate) fn new(enabled: bool, origin: &'static str) -> Forced {
+        Forced {
+            enabled,
+            output: String::new(),
+            origin,
+        }
+    }
+}
+
+/// Parses a `CONF_TEST_ASSUME` value in the form of `feature=yes,other=no`.
+pub(crate) fn parse_assume(assumptions: &OsStr) -> BTreeMap<String, Forced> {
+    let assumptions = assumptions
+        .to_str()
+        .expect("CONF_TEST_ASSUME is not valid UTF-8");
+
+    assumptions
+        .split(',')
+        .map(str::trim)
+        .filter(|assumption| !assumption.is_empty())
+        .map(|assumption| match assumption.split_once('=') {
+            Some((feature, "yes")) => (
+                feature.trim().to_string(),
+                Forced::new(true, "CONF_TEST_ASSUME"),
+            ),
+            Some((feature, "no")) => (
+                feature.trim().to_string(),
+                Forced::new(false, "CONF_TEST_ASSUME"),
+            ),
+            // Bail on any unknown value to catch 'undefined' states/typos
+            _ => panic!("Invalid CONF_TEST_ASSUME entry: {:?}", assumption),
+        })
+        .collect()
+}
+
+/// Loads an overrides file. Each feature is either set to a boolean or to a table with an
+/// `enabled` boolean and an optional `output` string or array of strings:
+///
+/// ```toml
+/// o_path = false
+///
+/// [io_uring]
+/// enabled = true
+/// output = ["cargo:rustc-env=URING_ENTRIES=256"]
+/// ```
+pub(crate) fn load(path: &Path) -> BTreeMap<String, Forced> {
+    parse_features(read_table(path), path, "overrides file")
+}
+
+/// Loads the assumptions for cross builds to 'target' from a table of sections keyed by
+/// target triples, which may contain `*` wildcards, or by `target_os=OS` or
+/// `target_arch=ARCH`. Each section is in the same form as the overrides file. Triples take
+/// precedence over architectures and these over operating systems, of several matching
+/// triples the longest pattern wins.
+pub(crate) fn load_cross(path: &Path, target: &Target) -> BTreeMap<String, Forced> {
+    let mut sections = Vec::new();
+    for (pattern, value) in read_table(path) {
+        let rank = match pattern.split_once('=') {
+            Some(("target_os", os)) if os == target.os => (0, 0),
+            Some(("target_arch", arch)) if arch == target.arch => (1, 0),
+            Some(("target_os" | "target_arch", _)) => continue,
+            Some(_) => panic!("Invalid section '{}' in {}", pattern, path.display()),
+            None if glob_match(&pattern, &target.triple) => (2, pattern.len()),
+            None => continue,
+        };
+        match value {
+            toml::Value::Table(features) => sections.push((rank, features)),
+            _ => panic!("Invalid section '{}' in {}", pattern, path.display()),
+        }
+    }
+    sections.sort_by_key(|(rank, _)| *rank);
+
+    let mut assume = BTreeMap::new();
+    for (_, features) in sections {
+        assume.extend(parse_features(features, path, "cross assumptions"));
+    }
+    assume
+}
+
+/// Matches 'text' against 'pattern' in which `*` matches any number of characters.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((prefix, rest)) => text.strip_prefix(prefix).is_some_and(|text| {
+            text.char_indices()
+                .map(|(index, _)| index)
+                .chain(Some(text.len()))
+                .any(|index| glob_match(rest, &text[index..]))
+        }),
+    }
+}
+
+/// The user-local configuration from 'conf_test.local.toml'.
+#[derive(Debug, Default)]
+pub(crate) struct LocalConfig {
+    /// Forced features, in the same form as in the overrides file.
+    pub(crate) features: BTreeMap<String, Forced>,
+    /// Environment variables set when compiling and running tests.
+    pub(crate) env: BTreeMap<String, String>,
+}
+
+/// Loads the user-local configuration file:
+///
+/// ```toml
+/// [features]
+/// o_path = false
+///
+/// [env]
+/// LIBFOO_PREFIX = "/opt/foo"
+/// ```
+pub(crate) fn load_local(path: &Path) -> LocalConfig {
+    let mut local = LocalConfig::default();
+
+    for (section, value) in read_table(path) {
+        match (section.as_str(), value) {
+            ("features", toml::Value::Table(features)) => {
+                local.features = parse_features(features, path, "local config");
+            }
+            ("env", toml::Value::Table(vars)) => {
+                for (name, value) in vars {
+                    let value = match value {
+                        toml::Value::String(value) => value,
+                        _ => panic!("Invalid env var '{}' in {}", name, path.display()),
+                    };
+                    local.env.insert(name, value);
+                }
+            }
+            _ => panic!("Invalid section '{}' in {}", section, path.display()),
+        }
+    }
+
+    local
+}
+
+fn read_table(path: &Path) -> toml::value::Table {
+    let source = fs::read_to_string(path)
+        .unwrap_or_else(|err| panic!("Failed to read {}: {}", path.display(), err));
+    match source
+        .parse::<toml::Value>()
+        .unwrap_or_else(|err| panic!("Failed to parse {}: {}", path.display(), err))
+    {
+        toml::Value::Table(table) => table,
+        _ => panic!("{} is not a table", path.display()),
+    }
+}
+
+fn parse_features(
+    table: toml::value::Table,
+    path: &Path,
+    origin: &'static str,
+) -> BTreeMap<String, Forced> {
+    let invalid =
+        |feature: &str| -> ! { panic!("Invalid override for '{}' in {}", feature, path.display()) };
+
+    let mut overrides = BTreeMap::new();
+    for (feature, value) in table {
+        let forced = match &value {
+            toml::Value::Boolean(enabled) => Forced::new(*enabled, origin),
+            toml::Value::Table(entry) => {
+                let mut forced = Forced::new(
+                    entry
+                        .get("enabled")
+                        .and_then(toml::Value::as_bool)
+                        .unwrap_or_else(|| invalid(&feature)),
+                    origin,
+                );
+                match entry.get("output") {
+                    None => {}
+                    Some(toml::Value::String(line)) => {
+                        forced.output = format!("{}\n", line);
+                    }
+                    Some(toml::Value::Array(lines)) => {
+                        for line in lines {
+                            forced
+                                .output
+                                .push_str(line.as_str().unwrap_or_else(|| invalid(&feature)));
+                            forced.output.push('\n');
+                        }
+                    }
+                    Some(_) => invalid(&feature),
+                }
+                forced
+            }
+            _ => invalid(&feature),
+        };
+        overrides.insert(feature, forced);
+    }
+    overrides
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(source: &str) -> toml::value::Table {
+        match source.parse::<toml::Value>().expect("invalid toml") {
+            toml::Value::Table(table) => table,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn assume() {
+        let assume = parse_assume(OsStr::new("o_path=yes, io_uring=no ,,"));
+        assert!(assume["o_path"].enabled);
+        assert!(!assume["io_uring"].enabled);
+        assert_eq!(assume["o_path"].origin, "CONF_TEST_ASSUME");
+        assert_eq!(assume.len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid CONF_TEST_ASSUME entry")]
+    fn assume_invalid() {
+        parse_assume(OsStr::new("o_path=true"));
+    }
+
+    #[test]
+    fn glob() {
+        assert!(glob_match(
+            "x86_64-unknown-linux-gnu",
+            "x86_64-unknown-linux-gnu"
+        ));
+        assert!(glob_match("*-linux-*", "x86_64-unknown-linux-gnu"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("aarch64-*-musl", "aarch64-unknown-linux-musl"));
+        assert!(!glob_match("*-musl", "x86_64-unknown-linux-gnu"));
+        assert!(!glob_match("x86_64", "x86_64-unknown-linux-gnu"));
+    }
+
+    #[test]
+    fn features() {
+        let overrides = parse_features(
+            table(
+                r#"
+                o_path = false
+
+                [io_uring]
+                enabled = true
+                output = ["cargo:rustc-env=A=1", "cargo:rustc-env=B=2"]
+
+                [simd]
+                enabled = true
+                output = "cargo:rustc-cfg=simd"
+                "#,
+            ),
+            Path::new("overrides.toml"),
+            "overrides file",
+        );
+        assert!(!overrides["o_path"].enabled);
+        assert!(overrides["o_path"].output.is_empty());
+        assert_eq!(
+            overrides["io_uring"].output,
+            "cargo:rustc-env=A=1\ncargo:rustc-env=B=2\n"
+        );
+        assert_eq!(overrides["simd"].output, "cargo:rustc-cfg=simd\n");
+    }
+
+    #[test]
+    fn cross_precedence() {
+        let path =
+            std::env::temp_dir().join(format!("conf_test_cross_{}.toml", std::process::id()));
+        fs::write(
+            &path,
+            r#"
+            ["*-linux-*"]
+            a = true
+            b = true
+            c = true
+
+            ["aarch64-unknown-linux-*"]
+            a = false
+
+            ["target_arch=aarch64"]
+            b = false
+            c = false
+
+            ["target_os=linux"]
+            c = true
+            d = true
+
+            ["x86_64-*"]
+            d = false
+            "#,
+        )
+        .expect("Failed to write cross assumptions");
+        let target = Target {
+            triple: "aarch64-unknown-linux-gnu".to_string(),
+            os: "linux".to_string(),
+            arch: "aarch64".to_string(),
+            ..Target::default()
+        };
+        let assume = load_cross(&path, &target);
+        fs::remove_file(&path).expect("Failed to remove cross assumptions");
+        assert!(!assume["a"].enabled);
+        assert!(assume["b"].enabled);
+        assert!(assume["c"].enabled);
+        assert!(assume["d"].enabled);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid override for 'io_uring'")]
+    fn features_without_enabled() {
+        parse_features(
+            table("[io_uring]\noutput = \"cargo:rustc-cfg=x\"\n"),
+            Path::new("overrides.toml"),
+            "overrides file",
+        );
+    }
+}
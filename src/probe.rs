@@ -0,0 +1,68 @@
+//! Running single test files outside the feature name convention.
+
+use std::collections::BTreeMap;
+
+/// Options for `ConfTest::run_probe()`.
+///
+/// ```rust,ignore
+/// let result = conf_test::ConfTest::run_probe(
+///     "probes/page_size.rs",
+///     conf_test::ProbeOptions::new().feature("o_path"),
+/// );
+/// if result.succeeded {
+///     println!("cargo:rustc-env=PAGE_SIZE={}", result.stdout.trim());
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ProbeOptions {
+    pub(crate) name: Option<String>,
+    pub(crate) features: Vec<String>,
+    pub(crate) env: BTreeMap<String, String>,
+}
+
+impl ProbeOptions {
+    /// Creates the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The name of the probe in logs and in `CONF_TEST_FEATURE`, defaults to the file name
+    /// without extension.
+    pub fn name(&mut self, name: &str) -> &mut Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Compile the probe with the cfg of 'feature' enabled.
+    pub fn feature(&mut self, feature: &str) -> &mut Self {
+        self.features.push(feature.to_string());
+        self
+    }
+
+    /// Set an environment variable for compiling and running the probe.
+    pub fn env(&mut self, key: &str, value: &str) -> &mut Self {
+        self.env.insert(key.to_string(), value.to_string());
+        self
+    }
+}
+
+/// The result of `ConfTest::run_probe()`.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ProbeResult {
+    /// The probe compiled successfully.
+    pub compiled: bool,
+    /// The probe compiled and executed successfully, for probes with `exit=` directives when
+    /// the exit code selected a variant.
+    pub succeeded: bool,
+    /// The exit code of the probe when it was executed and not killed by a signal.
+    pub exit_code: Option<i32>,
+    /// The variant selected by the exit code of a probe with `exit=` directives.
+    pub variant: Option<String>,
+    /// The stdout of a successful probe.
+    pub stdout: String,
+    /// The stderr of rustc.
+    pub diagnostics: String,
+    /// The log lines ConfTest wrote while running the probe.
+    pub log: String,
+}
@@ -0,0 +1,322 @@
+//! Running tests and the compiler as child processes with an optional time limit.
+
+use std::fmt;
+use std::io::{self, Read};
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The error payload when a child printed more than the limit on stdout.
+#[derive(Debug)]
+pub(crate) struct OutputLimitExceeded(pub(crate) usize);
+
+impl fmt::Display for OutputLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "printed more than {} bytes on stdout", self.0)
+    }
+}
+
+impl std::error::Error for OutputLimitExceeded {}
+
+/// Runs 'command' to completion and collects its output like `Command::output()`. When it is
+/// still running after 'timeout' it gets killed and an error of kind `TimedOut` is returned.
+/// When it prints more than 'stdout_limit' bytes on stdout it gets killed and an error
+/// carrying `OutputLimitExceeded` is returned.
+///
+/// The child stays in the process group of the build script, so a Ctrl-C on the terminal
+/// reaches it as well. When it times out or the build script panics meanwhile it gets killed.
+/// Processes it started are left running, once the child ended their output is collected
+/// for a short grace period only. On windows the child is put into a job object which kills
+/// the processes it started once it ends.
+pub(crate) fn output(
+    command: &mut Command,
+    timeout: Option<Duration>,
+    stdout_limit: Option<usize>,
+) -> io::Result<Output> {
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let mut guard = Guard::new(command.spawn()?);
+
+    // drain the pipes while waiting, a child blocked on a full pipe would never exit
+    let exceeded = Arc::new(AtomicBool::new(false));
+    let stdout = drain(guard.child.stdout.take(), stdout_limit, exceeded.clone());
+    let stderr = drain(guard.child.stderr.take(), None, exceeded.clone());
+
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let mut status = None;
+    let mut reaped_at = Instant::now();
+    let status = loop {
+        if status.is_none() {
+            status = guard.child.try_wait()?;
+            guard.reaped = status.is_some();
+            reaped_at = Instant::now();
+        }
+        // the pipes stay open as long as processes started by the child hold them
+        if let Some(status) = status.filter(|_| {
+            stdout.is_finished() && stderr.is_finished() || reaped_at.elapsed() >= PIPE_GRACE
+        }) {
+            break status;
+        }
+        if exceeded.load(Ordering::Relaxed) {
+            drop(guard);
+            return Err(io::Error::other(OutputLimitExceeded(
+                stdout_limit.unwrap_or_default(),
+            )));
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            drop(guard);
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!(
+                    "timed out after {:.1}s",
+                    timeout.unwrap_or_default().as_secs_f64()
+                ),
+            ));
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+    drop(guard);
+    if exceeded.load(Ordering::Relaxed) {
+        return Err(io::Error::other(OutputLimitExceeded(
+            stdout_limit.unwrap_or_default(),
+        )));
+    }
+
+    Ok(Output {
+        status,
+        stdout: stdout.take(),
+        stderr: stderr.take(),
+    })
+}
+
+/// How long the pipes of a child are read after it ended, processes it started may keep them
+/// open.
+const PIPE_GRACE: Duration = Duration::from_millis(100);
+
+/// Returns true when a failed child was stopped by a sandbox rather than failing on its own:
+/// it was killed with `SIGSYS` by a seccomp filter or it panicked with an `EPERM` or `EACCES`
+/// error (access denied on windows).
+pub(crate) fn denied_by_sandbox(output: &Output) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        const SIGSYS: i32 = if cfg!(any(target_os = "linux", target_os = "android"))
+            && !cfg!(any(
+                target_arch = "mips",
+                target_arch = "mips64",
+                target_arch = "sparc64"
+            )) {
+            31
+        } else {
+            12
+        };
+        if output.status.signal() == Some(SIGSYS) {
+            return true;
+        }
+    }
+    // as printed by unwrap() and by Display, ERROR_ACCESS_DENIED on windows
+    let denied: &[&str] = if cfg!(windows) {
+        &["kind: PermissionDenied", "(os error 5)"]
+    } else {
+        &["kind: PermissionDenied", "(os error 1)", "(os error 13)"]
+    };
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    !output.status.success()
+        && stderr.contains("panicked")
+        && denied.iter().any(|error| stderr.contains(error))
+}
+
+/// The output read from a pipe so far.
+struct Drain {
+    buffer: Arc<Mutex<Vec<u8>>>,
+    thread: thread::JoinHandle<()>,
+}
+
+impl Drain {
+    fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// The output read so far, the thread may still be blocked on a pipe held open by a
+    /// process the child started.
+    fn take(self) -> Vec<u8> {
+        std::mem::take(&mut *self.buffer.lock().unwrap_or_else(|err| err.into_inner()))
+    }
+}
+
+/// Reads 'pipe' to its end, or until more than 'limit' bytes came which sets 'exceeded'.
+fn drain<R: Read + Send + 'static>(
+    pipe: Option<R>,
+    limit: Option<usize>,
+    exceeded: Arc<AtomicBool>,
+) -> Drain {
+    let buffer = Arc::new(Mutex::new(Vec::new()));
+    let shared = buffer.clone();
+    let thread = thread::spawn(move || {
+        if let Some(mut pipe) = pipe {
+            let limit = limit.map_or(usize::MAX, |limit| limit + 1);
+            let mut chunk = [0u8; 8192];
+            loop {
+                let len = match pipe.read(&mut chunk) {
+                    Ok(0) => break,
+                    Ok(len) => len,
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(_) => break,
+                };
+                let mut buffer = shared.lock().unwrap_or_else(|err| err.into_inner());
+                let len = len.min(limit - buffer.len());
+                buffer.extend_from_slice(&chunk[..len]);
+                if buffer.len() == limit {
+                    exceeded.store(true, Ordering::Relaxed);
+                    break;
+                }
+            }
+        }
+    });
+    Drain { buffer, thread }
+}
+
+/// A child which gets killed when this is dropped, unless it was reaped already.
+struct Guard {
+    child: Child,
+    /// The exit status of the child was collected, its pid may be reused by now.
+    reaped: bool,
+    #[cfg(windows)]
+    job: Option<job::Job>,
+}
+
+impl Guard {
+    fn new(child: Child) -> Guard {
+        Guard {
+            // without a job object only the child itself can be killed
+            #[cfg(windows)]
+            job: job::Job::assign(&child),
+            child,
+            reaped: false,
+        }
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        #[cfg(windows)]
+        if let Some(job) = self.job.take() {
+            job.terminate();
+        }
+        if !self.reaped {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+/// Job objects collect the processes a child starts on windows. Processes started by a
+/// process in a job belong to the job as well and the job is set up to kill them all once it
+/// is closed.
+#[cfg(windows)]
+mod job {
+    use std::ffi::c_void;
+    use std::os::windows::io::AsRawHandle;
+    use std::process::Child;
+
+    type Handle = *mut c_void;
+
+    const JOB_OBJECT_EXTENDED_LIMIT_INFORMATION: i32 = 9;
+    const JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE: u32 = 0x2000;
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct BasicLimitInformation {
+        per_process_user_time_limit: i64,
+        per_job_user_time_limit: i64,
+        limit_flags: u32,
+        minimum_working_set_size: usize,
+        maximum_working_set_size: usize,
+        active_process_limit: u32,
+        affinity: usize,
+        priority_class: u32,
+        scheduling_class: u32,
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct IoCounters {
+        read_operation_count: u64,
+        write_operation_count: u64,
+        other_operation_count: u64,
+        read_transfer_count: u64,
+        write_transfer_count: u64,
+        other_transfer_count: u64,
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct ExtendedLimitInformation {
+        basic_limit_information: BasicLimitInformation,
+        io_info: IoCounters,
+        process_memory_limit: usize,
+        job_memory_limit: usize,
+        peak_process_memory_used: usize,
+        peak_job_memory_used: usize,
+    }
+
+    extern "system" {
+        fn CreateJobObjectW(attributes: *mut c_void, name: *const u16) -> Handle;
+        fn SetInformationJobObject(job: Handle, class: i32, info: *mut c_void, len: u32) -> i32;
+        fn AssignProcessToJobObject(job: Handle, process: Handle) -> i32;
+        fn TerminateJobObject(job: Handle, exit_code: u32) -> i32;
+        fn CloseHandle(handle: Handle) -> i32;
+    }
+
+    pub(super) struct Job(Handle);
+
+    impl Job {
+        /// Puts 'child' into a new job with kill-on-close. Processes the child started before
+        /// it got assigned escape the job, this window is tiny for freshly spawned tests.
+        pub(super) fn assign(child: &Child) -> Option<Job> {
+            unsafe {
+                let job = CreateJobObjectW(std::ptr::null_mut(), std::ptr::null());
+                if job.is_null() {
+                    return None;
+                }
+                let job = Job(job);
+
+                let mut info = ExtendedLimitInformation::default();
+                info.basic_limit_information.limit_flags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+                if SetInformationJobObject(
+                    job.0,
+                    JOB_OBJECT_EXTENDED_LIMIT_INFORMATION,
+                    &mut info as *mut ExtendedLimitInformation as *mut c_void,
+                    std::mem::size_of::<ExtendedLimitInformation>() as u32,
+                ) == 0
+                {
+                    return None;
+                }
+
+                if AssignProcessToJobObject(job.0, child.as_raw_handle() as Handle) == 0 {
+                    return None;
+                }
+                Some(job)
+            }
+        }
+
+        /// Kills all processes in the job.
+        pub(super) fn terminate(self) {
+            unsafe {
+                TerminateJobObject(self.0, 1);
+            }
+        }
+    }
+
+    impl Drop for Job {
+        fn drop(&mut self) {
+            unsafe {
+                CloseHandle(self.0);
+            }
+        }
+    }
+}
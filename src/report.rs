@@ -0,0 +1,554 @@
+//! Recording of the results of a ConfTest run.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::atomic;
+use crate::instructions::TestOutput;
+use crate::CCompiler;
+
+/// What is known about a feature after a run: whether it was checked to be present or
+/// absent, or could not be checked at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The feature is enabled, by its test, manually or by an assumption.
+    Enabled,
+    /// The feature is disabled, the platform lacks it or it was not tested.
+    Disabled,
+    /// The feature is disabled because its test could not decide, with the reason.
+    Indeterminate(&'static str),
+}
+
+impl Outcome {
+    /// Returns true for `Outcome::Enabled`.
+    pub fn is_enabled(self) -> bool {
+        self == Outcome::Enabled
+    }
+
+    /// The name of the outcome in reports and events, 'enabled', 'disabled' or
+    /// 'indeterminate'.
+    pub fn name(self) -> &'static str {
+        match self {
+            Outcome::Enabled => "enabled",
+            Outcome::Disabled => "disabled",
+            Outcome::Indeterminate(_) => "indeterminate",
+        }
+    }
+
+    /// Short answer for tables and the log, 'yes', 'no' or 'unknown'.
+    pub(crate) fn answer(self) -> &'static str {
+        match self {
+            Outcome::Enabled => "yes",
+            Outcome::Disabled => "no",
+            Outcome::Indeterminate(_) => "unknown",
+        }
+    }
+}
+
+/// What happened to a feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Status {
+    /// Set manually with `--features`, not tested.
+    Manual,
+    /// Enabled because its test succeeded.
+    Enabled,
+    /// Disabled because its test failed to compile.
+    CompileFailed,
+    /// Disabled because its test failed when executed.
+    RunFailed,
+    /// No test exists for the feature.
+    Untested,
+    /// Disabled because its variant test selected another variant.
+    Unselected,
+    /// Not tested, the outcome was assumed to be enabled (true) or disabled (false).
+    Assumed(bool),
+    /// Disabled without testing because the target is too old for the test.
+    Unsupported,
+    /// Disabled because the test exited with a code that means it does not apply.
+    Skipped,
+    /// Disabled without testing because the test runs only in builds of another profile.
+    OtherProfile,
+    /// Disabled without testing because a feature the test requires is not enabled.
+    RequirementUnmet,
+    /// Disabled because the test could not be compiled or executed at all: rustc crashed
+    /// with an internal compiler error, was missing or a process failed to spawn.
+    Inconclusive,
+    /// Disabled because a sandbox denied the test: it could not be spawned for lack of
+    /// permissions, was killed by a seccomp filter or panicked with a permission error.
+    Sandboxed,
+}
+
+impl Status {
+    /// Returns true when the feature ends up enabled.
+    pub(crate) fn is_enabled(self) -> bool {
+        matches!(
+            self,
+            Status::Manual | Status::Enabled | Status::Assumed(true)
+        )
+    }
+
+    /// The outcome this status amounts to.
+    pub(crate) fn outcome(self) -> Outcome {
+        match self {
+            _ if self.is_enabled() => Outcome::Enabled,
+            Status::Inconclusive | Status::Sandboxed => Outcome::Indeterminate(self.description()),
+            _ => Outcome::Disabled,
+        }
+    }
+
+    /// Human readable description of the status.
+    pub(crate) fn description(self) -> &'static str {
+        match self {
+            Status::Manual => "enabled manually",
+            Status::Enabled => "enabled by test",
+            Status::CompileFailed => "test failed to compile",
+            Status::RunFailed => "test failed when executed",
+            Status::Untested => "not tested",
+            Status::Unselected => "another variant selected",
+            Status::Assumed(true) => "assumed enabled",
+            Status::Assumed(false) => "assumed disabled",
+            Status::Unsupported => "not supported by the target",
+            Status::Skipped => "test skipped itself",
+            Status::OtherProfile => "not tested in this profile",
+            Status::RequirementUnmet => "required feature not enabled",
+            Status::Inconclusive => "test could not be run",
+            Status::Sandboxed => "indeterminate due to sandbox",
+        }
+    }
+
+    /// The name of the status as stored in the report file.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Status::Manual => "manual",
+            Status::Enabled => "enabled",
+            Status::CompileFailed => "compile_failed",
+            Status::RunFailed => "run_failed",
+            Status::Untested => "untested",
+            Status::Unselected => "unselected",
+            Status::Assumed(true) => "assumed_enabled",
+            Status::Assumed(false) => "assumed_disabled",
+            Status::Unsupported => "unsupported",
+            Status::Skipped => "skipped",
+            Status::OtherProfile => "other_profile",
+            Status::RequirementUnmet => "requirement_unmet",
+            Status::Inconclusive => "inconclusive",
+            Status::Sandboxed => "sandboxed",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Status> {
+        Some(match name {
+            "manual" => Status::Manual,
+            "enabled" => Status::Enabled,
+            "compile_failed" => Status::CompileFailed,
+            "run_failed" => Status::RunFailed,
+            "untested" => Status::Untested,
+            "unselected" => Status::Unselected,
+            "assumed_enabled" => Status::Assumed(true),
+            "assumed_disabled" => Status::Assumed(false),
+            "unsupported" => Status::Unsupported,
+            "skipped" => Status::Skipped,
+            "other_profile" => Status::OtherProfile,
+            "requirement_unmet" => Status::RequirementUnmet,
+            "inconclusive" => Status::Inconclusive,
+            "sandboxed" => Status::Sandboxed,
+            _ => return None,
+        })
+    }
+}
+
+/// What happened while a test was compiled and executed.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ProbeDetails {
+    pub(crate) compile_time: Option<Duration>,
+    pub(crate) run_time: Option<Duration>,
+    /// The stderr of rustc.
+    pub(crate) diagnostics: String,
+    /// The exit code when the test was executed.
+    pub(crate) exit_code: Option<i32>,
+    /// The variant selected by the exit code of a test with `exit=` directives.
+    pub(crate) variant: Option<String>,
+    /// The result came from the cache, the test was not compiled in this run.
+    pub(crate) cached: bool,
+    /// The crate the test failed to compile for because it is not a build-dependency.
+    pub(crate) missing_dependency: Option<String>,
+}
+
+/// The results of all features of a ConfTest run, returned by `ConfTest::run_report()`.
+///
+/// ```rust,ignore
+/// let report = conf_test::ConfTest::run_report();
+/// if report.compile_failed().contains(&"simd") {
+///     println!("cargo:warning=SIMD support is broken on this toolchain");
+/// }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Report {
+    pub(crate) features: BTreeMap<String, Status>,
+    /// The outputs of enabled features which have been tested or forced.
+    pub(crate) outputs: BTreeMap<String, TestOutput>,
+    pub(crate) c_compiler: Option<CCompiler>,
+    /// Details of the tests which have been compiled in this run.
+    pub(crate) details: BTreeMap<String, ProbeDetails>,
+    /// Relations between tests and features as (from, to, label).
+    pub(crate) edges: BTreeSet<(String, String, &'static str)>,
+    /// What the tests of the features detect, from their `describe` directives.
+    pub(crate) descriptions: BTreeMap<String, String>,
+    /// The seed the tests got for randomized inputs.
+    pub(crate) seed: Option<u64>,
+    /// The named instances whose results this report holds.
+    pub(crate) instances: BTreeSet<String>,
+    /// The results of the features probed in a matrix, per combination of enabled features.
+    pub(crate) combinations: BTreeMap<String, Vec<(Vec<String>, Status)>>,
+}
+
+impl Report {
+    /// Records the status of a feature.
+    pub(crate) fn record(&mut self, feature: &str, status: Status) {
+        self.features.insert(feature.to_string(), status);
+    }
+
+    /// Records a relation between tests or features.
+    pub(crate) fn add_edge(&mut self, from: &str, to: &str, label: &'static str) {
+        self.edges.insert((from.to_string(), to.to_string(), label));
+    }
+
+    /// Adds the results of the run of another instance. Features only one of them tested
+    /// take the result of that one, panics when both came to different results for a feature.
+    pub(crate) fn merge(&mut self, other: Report) {
+        for (feature, status) in other.features {
+            match self.features.get(&feature) {
+                None | Some(Status::Untested) => {}
+                Some(_) if status == Status::Untested => continue,
+                Some(previous) if *previous == status => {}
+                Some(previous) => panic!(
+                    "Feature '{}' is {} in one ConfTest instance and {} in another",
+                    feature,
+                    previous.name(),
+                    status.name()
+                ),
+            }
+            if let Some(output) = other.outputs.get(&feature) {
+                self.outputs.insert(feature.clone(), output.clone());
+            }
+            if let Some(details) = other.details.get(&feature) {
+                self.details.insert(feature.clone(), details.clone());
+            }
+            if let Some(description) = other.descriptions.get(&feature) {
+                self.descriptions
+                    .insert(feature.clone(), description.clone());
+            }
+            self.features.insert(feature, status);
+        }
+        self.edges.extend(other.edges);
+        self.combinations.extend(other.combinations);
+        self.c_compiler = self.c_compiler.take().or(other.c_compiler);
+        self.seed = self.seed.or(other.seed);
+        self.instances.extend(other.instances);
+    }
+
+    /// Records the output of an enabled feature.
+    pub(crate) fn record_output(&mut self, feature: &str, output: TestOutput) {
+        self.outputs.insert(feature.to_string(), output);
+    }
+
+    /// Returns the include directories of all enabled features.
+    pub(crate) fn include_dirs(&self) -> impl Iterator<Item = &PathBuf> {
+        self.outputs
+            .iter()
+            .filter(|(feature, _)| self.is_enabled(feature))
+            .flat_map(|(_, output)| output.include_dirs.iter())
+    }
+
+    /// The cargo instructions for the `conf_test:set-env` instructions of all enabled
+    /// features. Each variable is set once, conflicting values are warned about and the value
+    /// of the first feature in sort order wins.
+    pub(crate) fn rustc_env(&self) -> Vec<String> {
+        let mut env: BTreeMap<&str, (&str, &str)> = BTreeMap::new();
+        let mut rustc_env = Vec::new();
+        for (feature, output) in &self.outputs {
+            if !self.is_enabled(feature) {
+                continue;
+            }
+            for (key, value) in &output.env {
+                match env.get(key.as_str()) {
+                    None => {
+                        env.insert(key, (value, feature));
+                        rustc_env.push(format!("cargo:rustc-env={}={}\n", key, value));
+                    }
+                    Some((first, _)) if first == value => {}
+                    Some((first, other)) => rustc_env.push(format!(
+                        "cargo:warning=Feature '{}' sets {}={:?} but '{}' already set it to {:?}\n",
+                        feature, key, value, other, first
+                    )),
+                }
+            }
+        }
+        rustc_env
+    }
+
+    /// The cargo instructions for the link arguments of all enabled features, each argument
+    /// is passed once per directive in sort order of the features.
+    pub(crate) fn link_args(&self) -> Vec<String> {
+        let mut seen = BTreeSet::new();
+        self.outputs
+            .iter()
+            .filter(|(feature, _)| self.is_enabled(feature))
+            .flat_map(|(_, output)| output.link_args.iter())
+            .filter(|link_arg| seen.insert(*link_arg))
+            .map(|(directive, arg)| format!("cargo:{}={}\n", directive, arg))
+            .collect()
+    }
+
+    /// The value an enabled feature set for 'key' with `conf_test:set-env` or else
+    /// `conf_test:export`, the first feature in sort order wins.
+    pub fn value(&self, key: &str) -> Option<&str> {
+        let mut exported = None;
+        for (feature, output) in &self.outputs {
+            if !self.is_enabled(feature) {
+                continue;
+            }
+            if let Some((_, value)) = output.env.iter().find(|(name, _)| name == key) {
+                return Some(value);
+            }
+            if exported.is_none() {
+                exported = output.exports.iter().find(|(name, _)| name == key);
+            }
+        }
+        exported.map(|(_, value)| value.as_str())
+    }
+
+    /// Returns all features in sort order.
+    pub fn features(&self) -> impl Iterator<Item = &str> {
+        self.features.keys().map(String::as_str)
+    }
+
+    /// Returns the enabled features in sort order.
+    pub fn enabled(&self) -> impl Iterator<Item = &str> {
+        self.features
+            .iter()
+            .filter(|(_, status)| status.is_enabled())
+            .map(|(feature, _)| feature.as_str())
+    }
+
+    /// Returns true when 'feature' is enabled.
+    pub fn is_enabled(&self, feature: &str) -> bool {
+        self.features
+            .get(feature)
+            .is_some_and(|status| status.is_enabled())
+    }
+
+    /// The outcome for 'feature', None when it is no feature of the crate.
+    pub fn outcome(&self, feature: &str) -> Option<Outcome> {
+        self.features.get(feature).map(|status| status.outcome())
+    }
+
+    /// What happened to 'feature' as named in the log, for example 'enabled',
+    /// 'compile_failed' or 'assumed_disabled'. None when it is no feature of the crate.
+    pub fn status(&self, feature: &str) -> Option<&'static str> {
+        self.features.get(feature).map(|status| status.name())
+    }
+
+    /// Returns the features whose test failed to compile in sort order.
+    pub fn compile_failed(&self) -> Vec<&str> {
+        self.with_status(&[Status::CompileFailed])
+    }
+
+    /// Returns the features whose test compiled but failed when executed in sort order.
+    pub fn run_failed(&self) -> Vec<&str> {
+        self.with_status(&[Status::RunFailed])
+    }
+
+    /// Returns the features which were not tested or whose test did not apply in sort order:
+    /// there is no test, it skipped itself, the target or the profile does not support it or
+    /// a feature it requires is not enabled.
+    pub fn skipped(&self) -> Vec<&str> {
+        self.with_status(&[
+            Status::Untested,
+            Status::Skipped,
+            Status::Unsupported,
+            Status::OtherProfile,
+            Status::RequirementUnmet,
+        ])
+    }
+
+    /// Returns the features which were set manually, assumed or decided by a hook instead of
+    /// being tested in sort order.
+    pub fn overridden(&self) -> Vec<&str> {
+        self.with_status(&[
+            Status::Manual,
+            Status::Assumed(true),
+            Status::Assumed(false),
+        ])
+    }
+
+    /// Returns the features whose test could not decide in sort order, see
+    /// `Outcome::Indeterminate`.
+    pub fn indeterminate(&self) -> Vec<&str> {
+        self.with_status(&[Status::Inconclusive, Status::Sandboxed])
+    }
+
+    /// The name of 'feature' followed by the description of its test in parentheses, when
+    /// there is one.
+    pub(crate) fn described(&self, feature: &str) -> String {
+        match self.descriptions.get(feature) {
+            Some(description) => format!("{} ({})", feature, description),
+            None => feature.to_string(),
+        }
+    }
+
+    /// Returns the features with one of the given states in sort order.
+    pub(crate) fn with_status(&self, states: &[Status]) -> Vec<&str> {
+        self.features
+            .iter()
+            .filter(|(_, status)| states.contains(status))
+            .map(|(feature, _)| feature.as_str())
+            .collect()
+    }
+
+    /// Stores the status of all features and the 'artifacts' generated from them in 'path' for
+    /// comparison by the next run.
+    pub(crate) fn save(&self, path: &Path, artifacts: &[PathBuf]) {
+        let features: toml::value::Table = self
+            .features
+            .iter()
+            .map(|(feature, status)| (feature.clone(), toml::Value::from(status.name())))
+            .collect();
+        let artifacts = artifacts
+            .iter()
+            .map(|artifact| toml::Value::from(artifact.to_string_lossy().as_ref()))
+            .collect();
+        let mut report = toml::value::Table::new();
+        report.insert("features".into(), toml::Value::Table(features));
+        report.insert("artifacts".into(), toml::Value::Array(artifacts));
+        let report = toml::to_string(&toml::Value::Table(report)).expect("Failed to format report");
+        atomic::write(path, report).expect("Failed to write report");
+    }
+
+    /// Loads the status of the features stored by the previous run, empty when there was none.
+    pub(crate) fn load_previous(path: &Path) -> BTreeMap<String, Status> {
+        let report = fs::read_to_string(path)
+            .ok()
+            .and_then(|source| source.parse::<toml::Value>().ok());
+        let features = match report.as_ref().and_then(|report| report.get("features")) {
+            Some(toml::Value::Table(features)) => features,
+            _ => return BTreeMap::new(),
+        };
+        features
+            .iter()
+            .filter_map(|(feature, status)| {
+                Some((feature.clone(), Status::from_name(status.as_str()?)?))
+            })
+            .collect()
+    }
+
+    /// The files generated from the report of the previous run, empty when there was none.
+    pub(crate) fn previous_artifacts(path: &Path) -> Vec<PathBuf> {
+        let report = fs::read_to_string(path)
+            .ok()
+            .and_then(|source| source.parse::<toml::Value>().ok());
+        match report.as_ref().and_then(|report| report.get("artifacts")) {
+            Some(toml::Value::Array(artifacts)) => artifacts
+                .iter()
+                .filter_map(|artifact| Some(PathBuf::from(artifact.as_str()?)))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Compares against the 'previous' run. Features which were enabled by their test before
+    /// and are disabled by their test now are warned about, this usually means that the
+    /// toolchain or build environment regressed.
+    pub(crate) fn regressions(&self, previous: &BTreeMap<String, Status>) -> Vec<String> {
+        let lost: Vec<String> = self
+            .with_status(&[Status::CompileFailed, Status::RunFailed])
+            .into_iter()
+            .filter(|feature| previous.get(*feature) == Some(&Status::Enabled))
+            .map(|feature| self.described(feature))
+            .collect();
+
+        if lost.is_empty() {
+            Vec::new()
+        } else {
+            vec![format!(
+                "cargo:warning=Features lost since the previous run: {}\n",
+                lost.join(", ")
+            )]
+        }
+    }
+
+    /// Warns about the features whose tests could not be run, these are disabled although
+    /// nothing tells that the target lacks them.
+    pub(crate) fn inconclusive(&self) -> Vec<String> {
+        let inconclusive: Vec<String> = self
+            .with_status(&[Status::Inconclusive])
+            .into_iter()
+            .map(|feature| self.described(feature))
+            .collect();
+
+        if inconclusive.is_empty() {
+            Vec::new()
+        } else {
+            vec![format!(
+                "cargo:warning=Tests could not be run, features disabled: {}\n",
+                inconclusive.join(", ")
+            )]
+        }
+    }
+
+    /// Warns about the features whose tests were denied by a sandbox, these are disabled
+    /// although the platform may well have them.
+    pub(crate) fn sandboxed(&self) -> Vec<String> {
+        let sandboxed: Vec<String> = self
+            .with_status(&[Status::Sandboxed])
+            .into_iter()
+            .map(|feature| self.described(feature))
+            .collect();
+
+        if sandboxed.is_empty() {
+            Vec::new()
+        } else {
+            vec![format!(
+                "cargo:warning=Tests denied by a sandbox, features disabled: {}\n",
+                sandboxed.join(", ")
+            )]
+        }
+    }
+
+    /// Formats a categorized summary as comment lines for the build script output.
+    pub(crate) fn summary(&self) -> Vec<String> {
+        let categories = [
+            ("enabled manually", &[Status::Manual][..]),
+            ("enabled by test", &[Status::Enabled][..]),
+            (
+                "disabled by failed test",
+                &[Status::CompileFailed, Status::RunFailed][..],
+            ),
+            ("disabled by variant selection", &[Status::Unselected][..]),
+            ("not supported by the target", &[Status::Unsupported][..]),
+            ("skipped by test", &[Status::Skipped][..]),
+            ("not tested in this profile", &[Status::OtherProfile][..]),
+            (
+                "skipped, required feature not enabled",
+                &[Status::RequirementUnmet][..],
+            ),
+            ("inconclusive", &[Status::Inconclusive][..]),
+            ("indeterminate due to sandbox", &[Status::Sandboxed][..]),
+            ("assumed enabled", &[Status::Assumed(true)][..]),
+            ("assumed disabled", &[Status::Assumed(false)][..]),
+            ("not tested", &[Status::Untested][..]),
+        ];
+
+        let mut summary = vec![String::from("# ConfTest summary\n")];
+        for (category, states) in categories {
+            summary.push(format!(
+                "#   {}: {}\n",
+                category,
+                self.with_status(states).join(", ")
+            ));
+        }
+        summary
+    }
+}
@@ -0,0 +1,111 @@
+//! Delivery of the outcome and timings of each test to sinks registered with
+//! `ConfTestBuilder::report_sink()`.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::{atomic, event, Outcome};
+
+/// The outcome of a single feature as delivered to a `ReportSink`.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ProbeRecord {
+    pub feature: String,
+    /// The status as stored in the report file, 'enabled' or 'compile_failed' for example.
+    pub status: &'static str,
+    /// The feature got enabled.
+    pub enabled: bool,
+    /// Whether the feature is enabled, disabled or could not be decided.
+    pub outcome: Outcome,
+    /// The result came from the cache, the test was not compiled in this run.
+    pub cached: bool,
+    /// Left out in reproducible mode.
+    pub compile_time: Option<Duration>,
+    /// Left out in reproducible mode.
+    pub run_time: Option<Duration>,
+}
+
+impl ProbeRecord {
+    /// The record as single line JSON object, durations are given in seconds.
+    pub fn json(&self) -> String {
+        let seconds = |duration: Option<Duration>| match duration {
+            Some(duration) => format!("{:.6}", duration.as_secs_f64()),
+            None => String::from("null"),
+        };
+        format!(
+            "{{\"feature\":{},\"status\":{},\"enabled\":{},\"outcome\":{},\"cached\":{},\"compile_seconds\":{},\"run_seconds\":{}}}",
+            event::json_string(&self.feature),
+            event::json_string(self.status),
+            self.enabled,
+            event::json_string(self.outcome.name()),
+            self.cached,
+            seconds(self.compile_time),
+            seconds(self.run_time)
+        )
+    }
+}
+
+/// Receives the outcome and timings of every feature at the end of a run, for shipping
+/// configure metrics to build observability systems. Sinks are called after the log is
+/// written, in the order they were registered.
+pub trait ReportSink: fmt::Debug + Send + Sync {
+    /// Called once per feature, in sort order.
+    fn record(&self, record: &ProbeRecord);
+
+    /// Called after all records. Does nothing by default.
+    fn finish(&self) {}
+}
+
+/// Writes the records as JSON lines to a file, replacing it on each run.
+#[derive(Debug)]
+pub struct FileSink {
+    path: PathBuf,
+    lines: Mutex<Vec<String>>,
+}
+
+impl FileSink {
+    /// A sink writing to 'path', relative paths are relative to the crate directory.
+    pub fn new<P: AsRef<Path>>(path: P) -> FileSink {
+        FileSink {
+            path: path.as_ref().to_path_buf(),
+            lines: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl ReportSink for FileSink {
+    fn record(&self, record: &ProbeRecord) {
+        self.lines
+            .lock()
+            .expect("FileSink poisoned")
+            .push(record.json() + "\n");
+    }
+
+    fn finish(&self) {
+        let lines = std::mem::take(&mut *self.lines.lock().expect("FileSink poisoned"));
+        atomic::write(&self.path, lines.concat()).unwrap_or_else(|err| {
+            panic!(
+                "Failed to write metrics to {}: {}",
+                self.path.display(),
+                err
+            )
+        });
+    }
+}
+
+/// Prints the records as `# conf_test/1 {"event":"metrics",...}` comments to the build
+/// script output, where CI log scrapers can pick them up.
+#[derive(Debug, Default)]
+pub struct StdoutSink;
+
+impl ReportSink for StdoutSink {
+    fn record(&self, record: &ProbeRecord) {
+        println!(
+            "# {} {{\"event\":\"metrics\",{}",
+            event::SCHEMA,
+            &record.json()[1..]
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! What ConfTest knows about the target the crate is built for.
+
+use std::env;
+use std::ffi::OsString;
+use std::path::Path;
+
+/// The environment variables the Android API level is taken from, in order of precedence.
+pub(crate) const ANDROID_API_VARS: &[&str] = &[
+    "CONF_TEST_ANDROID_API",
+    "ANDROID_PLATFORM",
+    "ANDROID_NATIVE_API_LEVEL",
+];
+
+/// Targets whose tests can neither be executed on the host nor be meaningfully checked
+/// against it.
+const NICHE_OS: &[&str] = &["haiku", "redox", "uefi"];
+
+/// What happens to the tests when cross building for a niche target (Haiku, Redox, UEFI),
+/// set by `ConfTestBuilder::niche_target_policy()`. Executing the tests on the host would
+/// only describe the host.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NicheTargetPolicy {
+    /// Compile and link the tests for the target without executing them, the default. Tests
+    /// that need 'std' fail on targets that have none.
+    #[default]
+    CompileOnly,
+    /// Do not compile the tests at all, their features are assumed to be disabled.
+    AssumeDisabled,
+}
+
+/// The target of the build and how tests are probed for it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Target {
+    /// The target triple, from `TARGET`.
+    pub(crate) triple: String,
+    /// The target OS, from `CARGO_CFG_TARGET_OS`.
+    pub(crate) os: String,
+    /// The target architecture, from `CARGO_CFG_TARGET_ARCH`.
+    pub(crate) arch: String,
+    /// Building for another target than the host.
+    pub(crate) cross: bool,
+    /// A cross build for one of the niche targets.
+    pub(crate) niche: bool,
+    /// Tests are compiled and linked for the target but not executed.
+    pub(crate) compile_only: bool,
+    /// Tests are not compiled, their features are assumed to be disabled.
+    pub(crate) assume_disabled: bool,
+    /// The linker for the target, from `RUSTC_LINKER`.
+    pub(crate) linker: Option<OsString>,
+    /// The Android API level when targeting Android and the level is known.
+    pub(crate) android_api: Option<u32>,
+}
+
+impl Target {
+    /// Determines the target from the environment cargo sets for build scripts, tests for
+    /// niche targets are handled by 'policy'.
+    pub(crate) fn detect(policy: NicheTargetPolicy) -> Target {
+        let var = |name| env::var(name).unwrap_or_default();
+        let triple = var("TARGET");
+        let os = var("CARGO_CFG_TARGET_OS");
+        let linker = env::var_os("RUSTC_LINKER");
+        let cross = !triple.is_empty() && triple != var("HOST");
+
+        let android_api = if os == "android" {
+            android_api(&triple, linker.as_deref().map(Path::new))
+        } else {
+            None
+        };
+
+        let niche = cross && NICHE_OS.contains(&os.as_str());
+
+        Target {
+            niche,
+            // a cross build can not execute tests for Android
+            compile_only: cross && os == "android"
+                || niche && policy == NicheTargetPolicy::CompileOnly,
+            assume_disabled: niche && policy == NicheTargetPolicy::AssumeDisabled,
+            triple,
+            os,
+            arch: var("CARGO_CFG_TARGET_ARCH"),
+            cross,
+            linker,
+            android_api,
+        }
+    }
+}
+
+/// The Android API level from the environment or else from the name of the NDK compiler
+/// wrapper ('aarch64-linux-android24-clang') used as linker or C compiler.
+fn android_api(triple: &str, linker: Option<&Path>) -> Option<u32> {
+    for var in ANDROID_API_VARS {
+        if let Some(level) = env::var(var)
+            .ok()
+            .and_then(|value| parse_level(value.trim_start_matches("android-")))
+        {
+            return Some(level);
+        }
+    }
+
+    let cc = env::var_os(format!("CC_{}", triple.replace('-', "_")))
+        .or_else(|| env::var_os("TARGET_CC"));
+    linker
+        .into_iter()
+        .chain(cc.as_deref().map(Path::new))
+        .filter_map(|path| path.file_name()?.to_str())
+        .find_map(|name| {
+            let (_, level) = name.split_once("android")?;
+            parse_level(level.trim_start_matches("eabi").split('-').next()?)
+        })
+}
+
+fn parse_level(level: &str) -> Option<u32> {
+    level.parse().ok().filter(|&level| level > 0)
+}
@@ -0,0 +1,85 @@
+//! Substitution of `@NAME@` placeholders in probe templates.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Replaces each `@NAME@` in 'source' by the value of the variable NAME, `@@` becomes a single
+/// `@`. An `@` not followed by an identifier and a closing `@` is kept. Panics on undefined
+/// variables, naming the template at 'path'.
+pub(crate) fn expand(source: &str, vars: &BTreeMap<String, String>, path: &Path) -> String {
+    let mut expanded = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(at) = rest.find('@') {
+        expanded.push_str(&rest[..at]);
+        rest = &rest[at + 1..];
+        if let Some(after) = rest.strip_prefix('@') {
+            expanded.push('@');
+            rest = after;
+            continue;
+        }
+        let len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let name = &rest[..len];
+        if name.is_empty()
+            || name.starts_with(|c: char| c.is_ascii_digit())
+            || !rest[len..].starts_with('@')
+        {
+            expanded.push('@');
+            continue;
+        }
+        match vars.get(name) {
+            Some(value) => expanded.push_str(value),
+            None => panic!(
+                "Undefined template variable @{}@ in {}",
+                name,
+                path.display()
+            ),
+        }
+        rest = &rest[len + 1..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> BTreeMap<String, String> {
+        [("HEADER", "zlib.h"), ("FEATURE", "have_zlib_h")]
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn expand(source: &str) -> String {
+        super::expand(source, &vars(), Path::new("template.rs"))
+    }
+
+    #[test]
+    fn placeholders() {
+        assert_eq!(expand("#include <@HEADER@>"), "#include <zlib.h>");
+        assert_eq!(expand("@FEATURE@@HEADER@"), "have_zlib_hzlib.h");
+        assert_eq!(expand("no placeholders"), "no placeholders");
+    }
+
+    #[test]
+    fn escaped_at() {
+        assert_eq!(expand("user@@host"), "user@host");
+        assert_eq!(expand("@@HEADER@@"), "@HEADER@");
+    }
+
+    #[test]
+    fn literal_at() {
+        assert_eq!(expand("mail@example.com"), "mail@example.com");
+        assert_eq!(expand("@1A@ @ @-x@ trailing @"), "@1A@ @ @-x@ trailing @");
+        assert_eq!(expand("@HEADER"), "@HEADER");
+    }
+
+    #[test]
+    #[should_panic(expected = "Undefined template variable @LIB@ in template.rs")]
+    fn undefined() {
+        expand("-l@LIB@");
+    }
+}
@@ -0,0 +1,94 @@
+//! Timestamps and phase durations for the log.
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Measures the duration of a phase of the run.
+#[derive(Debug)]
+pub(crate) struct Phase {
+    name: String,
+    start: Instant,
+}
+
+impl Phase {
+    /// Starts timing the phase 'name'.
+    pub(crate) fn start(name: impl Into<String>) -> Phase {
+        Phase {
+            name: name.into(),
+            start: Instant::now(),
+        }
+    }
+
+    /// The time passed since the phase started.
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// The log line telling when the phase ended and how long it took. Reproducible runs only
+    /// note that it ended.
+    pub(crate) fn finish(self, reproducible: bool) -> String {
+        if reproducible {
+            return format!("# {} finished\n", self.name);
+        }
+        format!(
+            "# [{}] {} took {:.3}s\n",
+            timestamp(),
+            self.name,
+            self.start.elapsed().as_secs_f64()
+        )
+    }
+}
+
+/// The time written into logs and reports in ISO-8601 format (UTC, millisecond precision).
+/// This is the time given by 'SOURCE_DATE_EPOCH' when set, so that crates embedding generated
+/// files still build reproducibly, the current time otherwise.
+pub(crate) fn timestamp() -> String {
+    source_date().unwrap_or_else(wall_clock)
+}
+
+/// The current time in ISO-8601 format, regardless of 'SOURCE_DATE_EPOCH'.
+pub(crate) fn wall_clock() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    format_timestamp(now.as_secs(), now.subsec_millis())
+}
+
+/// The time given by 'SOURCE_DATE_EPOCH' in ISO-8601 format. None when it is not set or not a
+/// number of seconds.
+pub(crate) fn source_date() -> Option<String> {
+    let secs = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(format_timestamp(secs, 0))
+}
+
+fn format_timestamp(secs: u64, millis: u32) -> String {
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        secs / 3600 % 24,
+        secs / 60 % 60,
+        secs % 60,
+        millis
+    )
+}
+
+/// Converts days since 1970-01-01 to a (year, month, day) date, after Howard Hinnant's
+/// 'civil_from_days' algorithm.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
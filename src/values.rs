@@ -0,0 +1,101 @@
+//! The generated values module, 'OUT_DIR/conf_test/values.rs'.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use crate::{atomic, command_output};
+
+/// Constants written to the generated values module.
+#[derive(Debug, Default)]
+pub(crate) struct Values {
+    /// Constant name to its complete item.
+    items: BTreeMap<String, String>,
+}
+
+impl Values {
+    /// Adds a `&str` constant.
+    pub(crate) fn add_str(&mut self, name: &str, value: &str) {
+        self.items.insert(
+            name.to_string(),
+            format!("pub const {}: &str = {:?};\n", name, value),
+        );
+    }
+
+    /// Adds a documented `usize` constant.
+    pub(crate) fn add_usize(&mut self, name: &str, value: usize, doc: &str) {
+        self.items.insert(
+            name.to_string(),
+            format!("/// {}\npub const {}: usize = {};\n", doc, name, value),
+        );
+    }
+
+    /// Adds the details of the host the tests ran on.
+    pub(crate) fn add_host_info(&mut self) {
+        self.add_str("HOST_OS", std::env::consts::OS);
+        self.add_str("HOST_ARCH", std::env::consts::ARCH);
+        self.add_str(
+            "HOST_KERNEL_RELEASE",
+            command_output(Command::new("uname").arg("-r")).trim(),
+        );
+        self.add_str(
+            "HOST_KERNEL_VERSION",
+            command_output(Command::new("uname").arg("-v")).trim(),
+        );
+        self.add_str("HOST_DISTRO", &distro());
+    }
+
+    /// Adds the CPU count of the build host and the thread pool size recommended there.
+    pub(crate) fn add_host_parallelism(&mut self) {
+        let available = std::thread::available_parallelism()
+            .map(usize::from)
+            .unwrap_or(1);
+        self.add_usize(
+            "HOST_CPUS",
+            online_cpus().unwrap_or(available),
+            "CPUs online on the build host, not on the machine the binary runs on.",
+        );
+        self.add_usize(
+            "HOST_DEFAULT_THREADS",
+            available,
+            "Threads the build host could run in parallel (CPU affinity and quotas applied), \
+             a default thread pool size for builds targeting identical machines.",
+        );
+    }
+
+    /// Writes the values module to 'path'.
+    pub(crate) fn write(&self, path: &Path) {
+        let mut module = String::from("// Generated by conf_test, do not edit.\n");
+        for item in self.items.values() {
+            module.push_str(item);
+        }
+        atomic::write(path, module).expect("Failed to write values module");
+    }
+}
+
+/// The number of CPUs in '/sys/devices/system/cpu/online' ('0-3,8-11'), None when that is not
+/// available.
+fn online_cpus() -> Option<usize> {
+    fs::read_to_string("/sys/devices/system/cpu/online")
+        .ok()?
+        .trim()
+        .split(',')
+        .map(|range| match range.split_once('-') {
+            Some((first, last)) => {
+                Some(last.parse::<usize>().ok()? + 1 - first.parse::<usize>().ok()?)
+            }
+            None => range.parse::<usize>().ok().map(|_| 1),
+        })
+        .sum()
+}
+
+/// The 'PRETTY_NAME' from '/etc/os-release', empty when not available.
+fn distro() -> String {
+    fs::read_to_string("/etc/os-release")
+        .unwrap_or_default()
+        .lines()
+        .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+        .map(|name| name.trim_matches('"').to_string())
+        .unwrap_or_default()
+}